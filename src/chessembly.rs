@@ -1,9 +1,23 @@
 use std::cmp::Ordering;
 use std::{collections::HashMap, hash::Hash};
+pub mod attacks;
 mod behavior;
 pub mod board;
+pub mod fog;
+pub mod four_player;
+pub mod hex;
 pub mod moves;
+#[cfg(test)]
+mod perft;
+pub mod rules;
+pub mod squareset;
+pub mod zobrist;
+pub mod zones;
 use behavior::{Behavior, BehaviorChain};
+use rules::Grid;
+pub use squareset::{SquareSet, MAX_BOARD_SIZE};
+use attacks::NativeAttack;
+pub use zobrist::{ids, PieceId};
 pub(crate) use board::Board;
 use serde::Serialize;
 
@@ -13,6 +27,8 @@ pub enum GameResult {
     WhiteResigns,
     BlackCheckmates,
     BlackResigns,
+    WhiteWins,
+    BlackWins,
     Stalemate,
     DrawAccepted,
     DrawDeclared,
@@ -22,24 +38,47 @@ pub enum GameResult {
 pub enum Color {
     White,
     Black,
+    /// 어느 쪽에도 속하지 않는 기물 (덕, 장애물 등). 캡처·이동 가능 여부는 `Rules`가 정합니다.
+    Neutral,
 }
 
 impl Color {
+    /// 상대 색. 중립은 그대로 중립입니다.
     pub fn invert(&self) -> Color {
         match self {
             Self::White => Self::Black,
             Self::Black => Self::White,
+            Self::Neutral => Self::Neutral,
+        }
+    }
+
+    /// 서버 배치 형식(`piece:color`)에 쓰는 이름.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::White => "white",
+            Self::Black => "black",
+            Self::Neutral => "neutral",
         }
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
 pub struct Piece<'a> {
     pub piece_type: &'a str,
+    /// `piece_type`의 인턴 ID. 이동 생성과 평가는 이름 대신 이것을 봅니다.
+    pub id: PieceId,
     pub color: Color,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+impl<'a> Piece<'a> {
+    /// `pieces` 인터너에서 ID를 찾아 기물을 만듭니다.
+    #[inline]
+    pub fn new(piece_type: &'a str, color: Color, pieces: &zobrist::PieceRegistry) -> Piece<'a> {
+        Piece { piece_type, id: pieces.id_of(piece_type), color }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
 pub enum PieceSpan<'a> {
     Piece(Piece<'a>),
     Empty,
@@ -54,7 +93,9 @@ pub enum MoveType {
     Catch,
     Shift,
     Castling,
-    PlaceMove
+    PlaceMove,
+    /// 배치 단계에서 보관함의 기물(`transition`)을 `move_to`에 놓는 수.
+    Place,
 
     // Void, Pause, Block
 }
@@ -70,6 +111,19 @@ pub struct ChessMoveUnit<'a> {
     pub move_type: MoveType,
     pub state_change: Option<Vec<(&'a str, u8)>>,
     pub transition: Option<&'a str>,
+    /// 수를 두면서 칸에 남기거나 지우는 표식 (`mark`, `unmark`).
+    pub marks: Option<Vec<MarkChange<'a>>>,
+}
+
+/// 수에 딸린 표식 변경. 좌표는 수를 만들 때 절대 좌표로 풀어 둡니다.
+#[derive(Clone, Eq, PartialOrd, PartialEq, Debug, Hash, Serialize)]
+pub struct MarkChange<'a> {
+    pub name: &'a str,
+    pub position: Position,
+    /// 표식이 남는 플라이 수. `None`이면 지워질 때까지 남습니다.
+    pub plies: Option<u8>,
+    /// true면 그 칸의 같은 이름 표식을 지웁니다.
+    pub remove: bool,
 }
 
 #[derive(Clone, Eq, PartialOrd, PartialEq, Debug, Hash, Serialize)]
@@ -104,13 +158,34 @@ impl<'a> ChessMove<'a> {
             ChessMove::Multiple(v) => &v[0].transition
         }
     }
+
+    /// 수를 이루는 수 단위들 (적용 순서).
+    #[inline]
+    pub fn units(&self) -> &[ChessMoveUnit<'a>] {
+        match self {
+            ChessMove::Single(n) => std::slice::from_ref(n),
+            ChessMove::Multiple(v) => v,
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct ChessemblyCompiled<'a> {
+    /// 양쪽이 함께 쓰는 체인 (공통 스크립트).
     pub chains: Vec<BehaviorChain<'a>>,
+    /// 백 기물에만 적용되는 체인. 공통 체인 뒤에 이어서 실행됩니다.
+    pub white_chains: Vec<BehaviorChain<'a>>,
+    /// 흑 기물에만 적용되는 체인.
+    pub black_chains: Vec<BehaviorChain<'a>>,
+    /// 기물 이름 인터너 (Zobrist 키에 쓰이는 기물 ID).
+    pub pieces: zobrist::PieceRegistry<'a>,
+    /// 스크립트에서 선언한 이름 붙은 구역.
+    pub zones: zones::ZoneMap<'a>,
 }
 
+/// 생성 중의 표식 변경 (이름, 칸, 남는 플라이 수, 지우기).
+type RawMark = (*const str, Position, Option<u8>, bool);
+
 #[derive(Clone, Debug, Copy, PartialEq)]
 enum WallCollision {
     EdgeTop,
@@ -126,51 +201,87 @@ enum WallCollision {
 
 pub struct MoveGen {}
 
+/// 한 진영 킹의 체크·핀 정보 (`MoveGen::king_safety`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KingSafety {
+    pub king: Position,
+    /// 킹을 잡을 수 있는 상대 기물.
+    pub checkers: SquareSet,
+    /// 킹과 상대 슬라이딩 기물 사이에 홀로 선 기물.
+    pub pinned: SquareSet,
+}
+
+impl KingSafety {
+    /// 두어 보지 않아도 킹을 위험에 두지 않는 수인지. 체크가 아닐 때 핀 되지 않은 킹 아닌 기물이
+    /// 도착 칸의 기물만 잡으며 움직이는 수가 해당합니다 (막힌 광선을 여는 경우는 핀뿐입니다).
+    /// 제자리 캡처는 상대 기물이 막던 광선을 열 수 있어 빠지고, 킹으로 변할 수 있는 변신 수도 빠집니다.
+    pub fn is_safe(&self, node: &ChessMove) -> bool {
+        let ChessMove::Single(unit) = node else {
+            return false;
+        };
+        self.checkers.is_empty()
+            && matches!(unit.move_type, MoveType::Move | MoveType::TakeMove)
+            && unit.take == unit.move_to
+            && unit.from != self.king
+            && !self.pinned.contains(&unit.from)
+            && unit.transition.is_none()
+    }
+}
+
 impl MoveGen {
-    pub fn get_all_moves<'a, const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(board: &mut Board<'a, MACHO, IMPRISONED, SIZE>, turn: Color, check_danger: bool) -> Vec<ChessMove<'a>> {
+    pub fn get_all_moves<'a, const SIZE: usize>(board: &mut Board<'a, SIZE>, turn: Color, check_danger: bool) -> Vec<ChessMove<'a>> {
+        if board.is_placing() {
+            // 배치 단계에는 기물을 놓는 수뿐이며 상대의 위협도 없습니다.
+            return if turn == board.turn { MoveGen::placement_moves(board, turn, None) } else { Vec::new() };
+        }
         let mut ret = Vec::new();
-        for j in 0..board.get_height() {
-            for i in 0..board.get_width() {
-                if board.color_on(&(i as u8, j as u8)) == Some(turn) {
-                    if check_danger || MACHO {
-                        let a = board
-                            .script
-                            .get_moves::<MACHO, IMPRISONED, SIZE>(board, &(i as u8, j as u8), check_danger);
-                        let b = board.script.filter_nodes::<MACHO, IMPRISONED, SIZE>(a, board);
-                        ret.extend(b);
-                    } else {
-                        ret.extend(board.script.get_moves::<MACHO, IMPRISONED, SIZE>(
-                            board,
-                            &(i as u8, j as u8),
-                            check_danger,
-                        ));
-                    }
+        for position in MoveGen::movable_candidates(board, turn).iter() {
+            if board.is_movable_by(&position, turn) {
+                if check_danger || board.rules.macho {
+                    let a = board
+                        .script
+                        .get_moves::<SIZE>(board, &position, check_danger);
+                    let b = board.script.filter_nodes::<SIZE>(a, board);
+                    ret.extend(b);
+                } else {
+                    ret.extend(board.script.get_moves::<SIZE>(
+                        board,
+                        &position,
+                        check_danger,
+                    ));
                 }
             }
         }
         ret
     }
 
-    pub fn has_any_moves<'a, const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(board: &mut Board<'a, MACHO, IMPRISONED, SIZE>, turn: Color, check_danger: bool) -> bool {
-        for j in 0..board.get_height() {
-            for i in 0..board.get_width() {
-                if board.color_on(&(i as u8, j as u8)) == Some(turn) {
-                    if check_danger || MACHO {
-                        let a = board
-                            .script
-                            .get_moves::<MACHO, IMPRISONED, SIZE>(board, &(i as u8, j as u8), check_danger);
-                        let b = board.script.filter_nodes::<MACHO, IMPRISONED, SIZE>(a, board);
-                        if !b.is_empty() {
-                            return true;
-                        }
-                    } else {
-                        if !board.script.get_moves::<MACHO, IMPRISONED, SIZE>(
-                            board,
-                            &(i as u8, j as u8),
-                            check_danger,
-                        ).is_empty() {
-                            return true;
-                        }
+    /// `turn`이 움직일 수도 있는 기물의 칸: 자기 기물과 중립 기물 (행 우선 순서로 순회됩니다).
+    #[inline]
+    fn movable_candidates<const SIZE: usize>(board: &Board<SIZE>, turn: Color) -> SquareSet {
+        board.pieces_of(turn) | board.pieces_of(Color::Neutral)
+    }
+
+    pub fn has_any_moves<'a, const SIZE: usize>(board: &mut Board<'a, SIZE>, turn: Color, check_danger: bool) -> bool {
+        if board.is_placing() {
+            return turn == board.turn && !MoveGen::placement_moves(board, turn, None).is_empty();
+        }
+        for position in MoveGen::movable_candidates(board, turn).iter() {
+            if board.is_movable_by(&position, turn) {
+                if check_danger || board.rules.macho {
+                    let a = board
+                        .script
+                        .get_moves::<SIZE>(board, &position, check_danger);
+                    let b = board.script.filter_nodes::<SIZE>(a, board);
+                    if !b.is_empty() {
+                        return true;
+                    }
+                } else {
+                    if !board.script.get_moves::<SIZE>(
+                        board,
+                        &position,
+                        check_danger,
+                    ).is_empty() {
+                        return true;
                     }
                 }
             }
@@ -178,15 +289,114 @@ impl MoveGen {
         false
     }
 
+    /// 차례인 쪽의 합법 수. 강제 캡처 규칙이면 잡는 수가 있을 때 잡는 수만 반환합니다.
+    pub fn new_legal<'a, const SIZE: usize>(board: &mut Board<'a, SIZE>) -> Vec<ChessMove<'a>> {
+        let moves = MoveGen::get_all_moves::<SIZE>(board, board.side_to_move(), true);
+        if board.rules.compulsory_capture {
+            MoveGen::captures_only(board, moves)
+        } else {
+            moves
+        }
+    }
+
+    /// 차례인 쪽의 합법 수 중 시끄러운 수(`is_loud`)만, 또는 조용한 수만. 검색이 단계별로 수를 만들 때 씁니다.
+    /// 두 결과를 합치면 `new_legal`과 같은 수이고, 각 결과 안의 순서도 `new_legal`을 따릅니다.
+    pub fn new_legal_staged<'a, const SIZE: usize>(board: &mut Board<'a, SIZE>, loud: bool) -> Vec<ChessMove<'a>> {
+        if board.is_placing() || board.rules.macho || board.rules.compulsory_capture {
+            let moves = MoveGen::new_legal(board);
+            return moves.into_iter().filter(|node| MoveGen::is_loud(board, node) == loud).collect();
+        }
+        let turn = board.side_to_move();
+        let mut ret = Vec::new();
+        for position in MoveGen::movable_candidates(board, turn).iter() {
+            if !board.is_movable_by(&position, turn) || (loud && MoveGen::is_quiet_only(board, &position)) {
+                continue;
+            }
+            let nodes: Vec<ChessMove<'a>> = board
+                .script
+                .get_moves::<SIZE>(board, &position, true)
+                .into_iter()
+                .filter(|node| MoveGen::is_loud(board, node) == loud)
+                .collect();
+            if !nodes.is_empty() {
+                ret.extend(board.script.filter_nodes::<SIZE>(nodes, board));
+            }
+        }
+        ret
+    }
+
+    /// 도착 칸에 기물이 있거나 승급하는 수 (검색의 캡처·프로모션 단계).
     #[inline]
-    pub fn new_legal<'a, const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(board: &mut Board<'a, MACHO, IMPRISONED, SIZE>) -> Vec<ChessMove<'a>> {
-        MoveGen::get_all_moves::<MACHO, IMPRISONED, SIZE>(board, board.side_to_move(), true)
+    pub fn is_loud<'a, const SIZE: usize>(board: &Board<'a, SIZE>, node: &ChessMove<'a>) -> bool {
+        board.piece_on(&node.get_dest()).is_some() || node.get_promotion().is_some()
+    }
+
+    /// 공격 칸에 기물이 하나도 없는 기본 기물이라 수를 만들지 않아도 시끄러운 수가 없는지.
+    /// 승급하는 폰과 캐슬링하는 킹은 세지 않습니다.
+    fn is_quiet_only<const SIZE: usize>(board: &Board<SIZE>, position: &Position) -> bool {
+        board.piece_id_on(position) != Some(ids::KING)
+            && matches!(ChessemblyCompiled::native_attack_at::<SIZE>(board, position), Some(NativeAttack::Pattern(..)))
+            && ChessemblyCompiled::native_attacks::<SIZE>(board, position).is_some_and(|x| (x & board.occupied()).is_empty())
+    }
+
+    /// 배치 단계의 수: 보관함의 기물 종류마다 놓을 수 있는 빈 칸 하나씩. `target`이 주어지면 그 칸만 봅니다.
+    pub fn placement_moves<'a, const SIZE: usize>(board: &Board<'a, SIZE>, color: Color, target: Option<Position>) -> Vec<ChessMove<'a>> {
+        let reserve = &board.state(color).reserve;
+        let mut pieces: Vec<&'a str> = Vec::new();
+        for piece in reserve {
+            if !pieces.contains(piece) {
+                pieces.push(piece);
+            }
+        }
+        let mut ret = Vec::new();
+        for piece in pieces {
+            for y in 0..board.height {
+                for x in 0..board.width {
+                    if target.is_some_and(|target| target != (x, y)) || !board.can_place(color, piece, &(x, y)) {
+                        continue;
+                    }
+                    ret.push(ChessMove::Single(ChessMoveUnit {
+                        from: (x, y),
+                        take: (x, y),
+                        move_to: (x, y),
+                        move_type: MoveType::Place,
+                        state_change: None,
+                        transition: Some(piece),
+                        marks: None,
+                    }));
+                }
+            }
+        }
+        ret
+    }
+
+    /// 수가 상대 기물을 잡는지. `Take`, `TakeJump`, `Catch`와 잡을 수 있는 기물 위로의 `TakeMove`가 해당합니다.
+    pub fn is_capture<'a, const SIZE: usize>(board: &Board<'a, SIZE>, node: &ChessMove<'a>) -> bool {
+        let turn = board.side_to_move();
+        let captures = |n: &ChessMoveUnit| match n.move_type {
+            MoveType::Take | MoveType::TakeJump | MoveType::Catch => true,
+            MoveType::TakeMove => board.is_capturable_by(&n.take, turn),
+            _ => false,
+        };
+        match node {
+            ChessMove::Single(n) => captures(n),
+            ChessMove::Multiple(v) => v.iter().any(captures),
+        }
+    }
+
+    /// 잡는 수가 하나라도 있으면 잡는 수만 남깁니다 (모든 기물에 걸쳐 판정).
+    pub fn captures_only<'a, const SIZE: usize>(board: &Board<'a, SIZE>, moves: Vec<ChessMove<'a>>) -> Vec<ChessMove<'a>> {
+        if moves.iter().any(|node| MoveGen::is_capture(board, node)) {
+            moves.into_iter().filter(|node| MoveGen::is_capture(board, node)).collect()
+        } else {
+            moves
+        }
     }
 
     #[inline]
-    pub fn get_danger_zones<const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(board: &mut Board<MACHO, IMPRISONED, SIZE>, enemy: Color) -> Vec<Position> {
+    pub fn get_danger_zones<const SIZE: usize>(board: &mut Board<SIZE>, enemy: Color) -> Vec<Position> {
         let mut ret = Vec::new();
-        let all_moves = MoveGen::get_all_moves::<MACHO, IMPRISONED, SIZE>(board, enemy, false);
+        let all_moves = MoveGen::get_all_moves::<SIZE>(board, enemy, false);
         for node in all_moves {
             match node {
                 ChessMove::Multiple(v) => {
@@ -215,38 +425,201 @@ impl MoveGen {
         ret
     }
 
-    pub fn get_danger_zones_bit<const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(board: &mut Board<MACHO, IMPRISONED, SIZE>, enemy: Color) -> u64 {
-        let mut ret: u64 = 0;
-        let all_moves = MoveGen::get_all_moves::<MACHO, IMPRISONED, SIZE>(board, enemy, false);
-        for node in all_moves {
-            ret |= match node {
-                ChessMove::Multiple(v) => {
-                    v.iter().map(|x| (match x.move_type {
-                        MoveType::Take => 1,
-                        MoveType::TakeMove => 1,
-                        MoveType::TakeJump => 1,
-                        MoveType::Catch => 1,
-                        _ => 0,
-                    }) << (x.take.1 * 8 + x.take.0)).fold(0, |a, b| a | b)
-                },
-                ChessMove::Single(n) => {
-                    match n.move_type {
-                        MoveType::Take => 1 << (n.take.1 * 8 + n.take.0),
-                        MoveType::TakeMove => 1 << (n.take.1 * 8 + n.take.0),
-                        MoveType::TakeJump => 1 << (n.take.1 * 8 + n.take.0),
-                        MoveType::Catch => 1 << (n.take.1 * 8 + n.take.0),
-                        _ => 0
+    pub fn get_danger_zones_bit<const SIZE: usize>(board: &mut Board<SIZE>, enemy: Color) -> SquareSet {
+        let mut ret = SquareSet::EMPTY;
+        // 평평한 보드의 기본 기물은 수를 만들지 않고 공격 테이블로 바로 모읍니다.
+        if board.is_flat() && !board.rules.macho && !board.is_placing() {
+            for position in board.occupied().iter() {
+                if !board.is_movable_by(&position, enemy) {
+                    continue;
+                }
+                match ChessemblyCompiled::native_attacks::<SIZE>(board, &position) {
+                    Some(attacks) => ret |= attacks,
+                    None => {
+                        for node in board.script.get_moves::<SIZE>(board, &position, false) {
+                            MoveGen::insert_attacks(&mut ret, &node);
+                        }
                     }
                 }
+            }
+            return ret;
+        }
+        for node in MoveGen::get_all_moves::<SIZE>(board, enemy, false) {
+            MoveGen::insert_attacks(&mut ret, &node);
+        }
+        ret
+    }
+
+    /// `by` 진영이 `square`를 잡을 수 있는지 (`get_danger_zones_bit(board, by)`에 드는지).
+    /// 평평한 보드에서는 기본 기물을 `square`에서 거꾸로 찾고, 스크립트 기물만 수를 만들어 봅니다.
+    /// 주사위는 움직일 기물만 정하므로 공격하는 기물은 굴린 면과 관계없이 모두 셉니다.
+    pub fn is_attacked<const SIZE: usize>(board: &mut Board<SIZE>, square: &Position, by: Color) -> bool {
+        let roll = board.roll.take();
+        let ret = MoveGen::is_attacked_unrolled::<SIZE>(board, square, by);
+        board.roll = roll;
+        ret
+    }
+
+    fn is_attacked_unrolled<const SIZE: usize>(board: &mut Board<SIZE>, square: &Position, by: Color) -> bool {
+        if !board.is_flat() || board.rules.macho || board.is_placing() {
+            return MoveGen::get_danger_zones_bit::<SIZE>(board, by).contains(square);
+        }
+        if !MoveGen::native_attackers::<SIZE>(board, square, by).is_empty() {
+            return true;
+        }
+        for position in board.occupied().iter() {
+            if !board.is_movable_by(&position, by) || ChessemblyCompiled::native_attack_at::<SIZE>(board, &position).is_some() {
+                continue;
+            }
+            let mut attacks = SquareSet::EMPTY;
+            for node in board.script.get_moves::<SIZE>(board, &position, false) {
+                MoveGen::insert_attacks(&mut attacks, &node);
+            }
+            if attacks.contains(square) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// `square`를 잡을 수 있는 `by` 진영의 기본 기물 (평평한 보드 전용, 스크립트 기물은 세지 않습니다).
+    /// 슬라이딩 기물은 `square`에서 8방향으로 쏜 광선의 첫 기물로 찾습니다.
+    pub fn native_attackers<const SIZE: usize>(board: &Board<SIZE>, square: &Position, by: Color) -> SquareSet {
+        let mut ret = SquareSet::EMPTY;
+        if !board.is_playable(square) || board.blockers_for(by).contains(square) {
+            return ret;
+        }
+        let occupied = board.occupied();
+        for delta in attacks::KING.iter() {
+            let Some(hit) = MoveGen::first_on_ray(board, square, delta) else {
+                continue;
             };
+            if board.is_movable_by(&hit, by)
+                && matches!(ChessemblyCompiled::native_attack_at::<SIZE>(board, &hit), Some(NativeAttack::Pattern(_, slides)) if slides.contains(delta))
+            {
+                ret.insert(&hit);
+            }
+        }
+        for position in occupied.iter() {
+            if !board.is_movable_by(&position, by) {
+                continue;
+            }
+            let hit = match ChessemblyCompiled::native_attack_at::<SIZE>(board, &position) {
+                Some(NativeAttack::Pattern(leaps, _)) => {
+                    let delta = (square.0 as i8 - position.0 as i8, position.1 as i8 - square.1 as i8);
+                    leaps.contains(&delta)
+                }
+                Some(NativeAttack::Pawn) => {
+                    ChessemblyCompiled::native_attacks::<SIZE>(board, &position).is_some_and(|x| x.contains(square))
+                }
+                None => false,
+            };
+            if hit {
+                ret.insert(&position);
+            }
         }
         ret
     }
+
+    /// `from`에서 `delta` 방향으로 처음 만나는 기물의 칸. 구멍이나 보드 끝에서 멈춥니다.
+    fn first_on_ray<const SIZE: usize>(board: &Board<SIZE>, from: &Position, delta: &DeltaPosition) -> Option<Position> {
+        let occupied = board.occupied();
+        let hit = attacks::slide(from, delta, occupied | board.rules.holes)? & occupied;
+        if attacks::is_ascending(delta) { hit.first() } else { hit.last() }
+    }
+
+    /// `color` 킹의 체크·핀 정보. 잡힐 수 있는 킹이 `color`의 킹 하나뿐이고 상대의 공격이 모두
+    /// 기본 기물이라 점유 칸만으로 판정할 수 있을 때만 Some입니다.
+    pub fn king_safety<const SIZE: usize>(board: &Board<SIZE>, color: Color) -> Option<KingSafety> {
+        let enemy = color.invert();
+        if !board.is_flat() || board.rules.macho || board.is_placing() {
+            return None;
+        }
+        let occupied = board.occupied();
+        if occupied.iter().any(|x| board.is_movable_by(&x, enemy) && ChessemblyCompiled::native_attack_at::<SIZE>(board, &x).is_none()) {
+            return None;
+        }
+        let capturable = board.kings & !board.blockers_for(enemy);
+        let mut kings = capturable.iter();
+        let king = kings.next()?;
+        if kings.next().is_some() || board.color_on(&king) != Some(color) {
+            return None;
+        }
+
+        // 킹에서 뻗은 광선의 첫 기물 너머에 이 방향으로 미끄러지는 상대 기물이 있으면 첫 기물은 핀 된 것입니다.
+        let mut pinned = SquareSet::EMPTY;
+        for delta in attacks::KING.iter() {
+            let Some(shield) = MoveGen::first_on_ray(board, &king, delta) else {
+                continue;
+            };
+            let Some(pinner) = MoveGen::first_on_ray(board, &shield, delta) else {
+                continue;
+            };
+            if board.is_movable_by(&pinner, enemy)
+                && matches!(ChessemblyCompiled::native_attack_at::<SIZE>(board, &pinner), Some(NativeAttack::Pattern(_, slides)) if slides.contains(delta))
+            {
+                pinned.insert(&shield);
+            }
+        }
+
+        Some(KingSafety {
+            king,
+            checkers: MoveGen::native_attackers::<SIZE>(board, &king, enemy),
+            pinned,
+        })
+    }
+
+    /// 수가 잡을 수 있는 칸을 `set`에 더합니다.
+    fn insert_attacks(set: &mut SquareSet, node: &ChessMove) {
+        let is_attack = |n: &ChessMoveUnit| matches!(
+            n.move_type,
+            MoveType::Take | MoveType::TakeMove | MoveType::TakeJump | MoveType::Catch
+        );
+        match node {
+            ChessMove::Multiple(v) => {
+                for n in v.iter().filter(|n| is_attack(n)) {
+                    set.insert(&n.take);
+                }
+            },
+            ChessMove::Single(n) => {
+                if is_attack(n) {
+                    set.insert(&n.take);
+                }
+            }
+        }
+    }
 }
 
 impl<'a> ChessemblyCompiled<'a> {
     pub fn new() -> ChessemblyCompiled<'a> {
-        ChessemblyCompiled { chains: Vec::new() }
+        ChessemblyCompiled {
+            chains: Vec::new(),
+            white_chains: Vec::new(),
+            black_chains: Vec::new(),
+            pieces: zobrist::PieceRegistry::new(),
+            zones: zones::ZoneMap::default(),
+        }
+    }
+
+    /// 이미 만들어진 체인으로 스크립트를 구성합니다 (내장 기물 생성기용).
+    /// 해시에는 보드의 스크립트 인터너만 쓰이므로 빈 인터너를 둡니다.
+    pub fn from_chains(chains: Vec<BehaviorChain<'a>>) -> ChessemblyCompiled<'a> {
+        ChessemblyCompiled {
+            chains,
+            white_chains: Vec::new(),
+            black_chains: Vec::new(),
+            pieces: zobrist::PieceRegistry::default(),
+            zones: zones::ZoneMap::default(),
+        }
+    }
+
+    /// 배치 문자열(`name:color` 토큰, '/'로 행 구분)에 나오는 기물 이름을 모두 인턴합니다.
+    pub fn intern_placement(&mut self, placement: &'a str) {
+        for token in placement.split(['/', ' ']) {
+            if let Some((piece_name, _)) = token.split_once(':') {
+                self.pieces.intern(piece_name);
+            }
+        }
     }
 
     #[inline]
@@ -264,14 +637,46 @@ impl<'a> ChessemblyCompiled<'a> {
 
     pub fn from_script(script: &'a str) -> Result<ChessemblyCompiled<'a>, ()> {
         let mut ret = ChessemblyCompiled::new();
+        ret.extend_from_script(script);
+        Ok(ret)
+    }
+
+    /// 공통 스크립트와 진영별 스크립트를 함께 컴파일합니다. 기물 인터너와 구역은 공유하며,
+    /// 진영별 스크립트는 그 색의 기물에만 적용됩니다. 빈 문자열이면 그 진영은 공통 스크립트만 씁니다.
+    pub fn from_side_scripts(base: &'a str, white: &'a str, black: &'a str) -> ChessemblyCompiled<'a> {
+        let mut ret = ChessemblyCompiled::new();
+        ret.extend_from_script(white);
+        ret.white_chains = std::mem::take(&mut ret.chains);
+        ret.extend_from_script(black);
+        ret.black_chains = std::mem::take(&mut ret.chains);
+        ret.extend_from_script(base);
+        ret
+    }
+
+    /// `color` 기물에 적용되는 체인: 공통 체인 다음에 그 진영의 체인.
+    pub fn chains_for(&self, color: Color) -> impl Iterator<Item = &BehaviorChain<'a>> {
+        let side: &[BehaviorChain<'a>] = match color {
+            Color::White => &self.white_chains,
+            Color::Black => &self.black_chains,
+            Color::Neutral => &[],
+        };
+        self.chains.iter().chain(side)
+    }
+
+    /// 스크립트를 컴파일해 `chains` 뒤에 덧붙입니다. 구역 선언과 기물 이름도 등록합니다.
+    fn extend_from_script(&mut self, script: &'a str) {
+        let first = self.chains.len();
         let chains = script.split(';');
         for chain_str in chains {
             if chain_str.trim().starts_with('#') {
                 continue;
+            } else if let Some(params) = chain_str.trim().strip_prefix("declare-zone(") {
+                self.zones.declare(params.trim_end_matches(')'));
+                continue;
             } else if chain_str.chars().all(char::is_whitespace) {
                 continue;
             } else {
-                ret.add_command();
+                self.add_command();
                 let mut i = 0;
                 let mut j = 0;
                 while j < chain_str.len() - 1 {
@@ -283,7 +688,7 @@ impl<'a> ChessemblyCompiled<'a> {
                             .all(|c| char::is_alphabetic(c) || c == '{' || c == '}' || c == '+' || c == '|')
                         {
                             if chain_str[i..j].trim().len() > 0 {
-                                ret.push_behavior(Behavior::from_str(&chain_str[i..j].trim()));
+                                self.push_behavior(Behavior::from_str(&chain_str[i..j].trim()));
                                 i = j;
                             }
                         }
@@ -291,89 +696,140 @@ impl<'a> ChessemblyCompiled<'a> {
                     j = jp1;
                 }
                 if !chain_str[i..].chars().all(char::is_whitespace) {
-                    ret.push_behavior(Behavior::from_str(&chain_str[i..].trim()));
+                    self.push_behavior(Behavior::from_str(&chain_str[i..].trim()));
+                }
+            }
+        }
+        for chain in &self.chains[first..] {
+            for behavior in chain {
+                match behavior {
+                    Behavior::Transition(name) | Behavior::Piece(name)
+                    | Behavior::PlaceMove((name, _)) | Behavior::PieceOn((name, _, _)) if !name.is_empty() => {
+                        self.pieces.intern(name);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        // piece-on은 이름 대신 ID로 비교하도록 여기서 한 번 바꿔 둡니다.
+        let pieces = &self.pieces;
+        for chain in &mut self.chains[first..] {
+            for behavior in chain.iter_mut() {
+                if let Behavior::PieceOn((name, id, _)) = behavior {
+                    *id = pieces.id_of(name);
+                }
+            }
+        }
+    }
+
+    /// 감싸는 축(`Topology`)에서는 좌표를 먼저 접어 넣으므로 이음매에서는 충돌이 없습니다.
+    /// 따라서 bound/edge/corner는 실제 벽과 구멍에서만 참이 됩니다.
+    fn wall_collision<const SIZE: usize>(anchor: &Position, delta: &DeltaPosition, board: &Board<SIZE>, color: Color) -> WallCollision {
+        let (a0, a1) = board.wrap(anchor.0 as i16 + delta.0 as i16, anchor.1 as i16 - delta.1 as i16);
+        match (a0.cmp(&0), a0.cmp(&(board.get_width() as i16)), a1.cmp(&0), a1.cmp(&(board.get_height() as i16))) {
+            (Ordering::Less, _, Ordering::Less, _) => if color != Color::Black { WallCollision::CornerTopLeft } else { WallCollision::CornerBottomRight }
+            (_, Ordering::Equal, Ordering::Less, _) => if color != Color::Black { WallCollision::CornerTopRight } else { WallCollision::CornerBottomLeft }
+            (_, Ordering::Greater, Ordering::Less, _) => if color != Color::Black { WallCollision::CornerTopRight } else { WallCollision::CornerBottomLeft }
+            (Ordering::Less, _, _, Ordering::Equal) => if color != Color::Black { WallCollision::CornerBottomLeft } else { WallCollision::CornerTopRight }
+            (Ordering::Less, _, _, Ordering::Greater) => if color != Color::Black { WallCollision::CornerBottomLeft } else { WallCollision::CornerTopRight }
+            (_, Ordering::Equal, _, Ordering::Equal) => if color != Color::Black { WallCollision::CornerBottomRight } else { WallCollision::CornerTopLeft }
+            (_, Ordering::Greater, _, Ordering::Greater) => if color != Color::Black { WallCollision::CornerBottomRight } else { WallCollision::CornerTopLeft }
+            (Ordering::Less, _, _, _) => if color != Color::Black { WallCollision::EdgeLeft } else { WallCollision::EdgeRight }
+            (_, Ordering::Equal, _, _) => if color != Color::Black { WallCollision::EdgeRight } else { WallCollision::EdgeLeft }
+            (_, Ordering::Greater, _, _) => if color != Color::Black { WallCollision::EdgeRight } else { WallCollision::EdgeLeft }
+            (_, _, Ordering::Less, _) => if color != Color::Black { WallCollision::EdgeTop } else { WallCollision::EdgeBottom }
+            (_, _, _, Ordering::Equal) => if color != Color::Black { WallCollision::EdgeBottom } else { WallCollision::EdgeTop }
+            (_, _, _, Ordering::Greater) => if color != Color::Black { WallCollision::EdgeBottom } else { WallCollision::EdgeTop }
+            _ => {
+                if board.rules.holes.contains(&(a0 as u8, a1 as u8)) {
+                    ChessemblyCompiled::hole_collision(anchor, delta, board, color)
+                } else {
+                    WallCollision::NoCollision
                 }
             }
         }
-        Ok(ret)
     }
 
-    fn wall_collision<const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(anchor: &Position, delta: &DeltaPosition, board: &Board<MACHO, IMPRISONED, SIZE>, color: Color) -> WallCollision {
-        let a0 = (anchor.0 as i8) + delta.0;
-        let a1 = (anchor.1 as i8) - delta.1;
-        match (a0.cmp(&0), a0.cmp(&(board.get_width() as i8)), a1.cmp(&0), a1.cmp(&(board.get_height() as i8))) {
-            (Ordering::Less, _, Ordering::Less, _) => if color == Color::White { WallCollision::CornerTopLeft } else { WallCollision::CornerBottomRight }
-            (_, Ordering::Equal, Ordering::Less, _) => if color == Color::White { WallCollision::CornerTopRight } else { WallCollision::CornerBottomLeft }
-            (_, Ordering::Greater, Ordering::Less, _) => if color == Color::White { WallCollision::CornerTopRight } else { WallCollision::CornerBottomLeft }
-            (Ordering::Less, _, _, Ordering::Equal) => if color == Color::White { WallCollision::CornerBottomLeft } else { WallCollision::CornerTopRight }
-            (Ordering::Less, _, _, Ordering::Greater) => if color == Color::White { WallCollision::CornerBottomLeft } else { WallCollision::CornerTopRight }
-            (_, Ordering::Equal, _, Ordering::Equal) => if color == Color::White { WallCollision::CornerBottomRight } else { WallCollision::CornerTopLeft }
-            (_, Ordering::Greater, _, Ordering::Greater) => if color == Color::White { WallCollision::CornerBottomRight } else { WallCollision::CornerTopLeft }
-            (Ordering::Less, _, _, _) => if color == Color::White { WallCollision::EdgeLeft } else { WallCollision::EdgeRight }
-            (_, Ordering::Equal, _, _) => if color == Color::White { WallCollision::EdgeRight } else { WallCollision::EdgeLeft }
-            (_, Ordering::Greater, _, _) => if color == Color::White { WallCollision::EdgeRight } else { WallCollision::EdgeLeft }
-            (_, _, Ordering::Less, _) => if color == Color::White { WallCollision::EdgeTop } else { WallCollision::EdgeBottom }
-            (_, _, _, Ordering::Equal) => if color == Color::White { WallCollision::EdgeBottom } else { WallCollision::EdgeTop }
-            (_, _, _, Ordering::Greater) => if color == Color::White { WallCollision::EdgeBottom } else { WallCollision::EdgeTop }
-            _ => WallCollision::NoCollision
+    /// 구멍에 부딪힌 경우의 충돌 종류. 가로·세로 한 축으로만 움직였을 때 막히는지로 벽의 방향을 정합니다.
+    fn hole_collision<const SIZE: usize>(anchor: &Position, delta: &DeltaPosition, board: &Board<SIZE>, color: Color) -> WallCollision {
+        let blocked = |dx: i8, dy: i8| {
+            let (x, y) = board.wrap(anchor.0 as i16 + dx as i16, anchor.1 as i16 - dy as i16);
+            x < 0 || y < 0 || !board.is_playable(&(x as u8, y as u8))
+        };
+        let horizontal = delta.0 != 0 && blocked(delta.0, 0);
+        let vertical = delta.1 != 0 && blocked(0, delta.1);
+        // 절대 방향 (백 기준) 계산 후 흑이면 뒤집습니다.
+        let (right, up) = if color != Color::Black { (delta.0 > 0, delta.1 > 0) } else { (delta.0 < 0, delta.1 < 0) };
+        match (horizontal || vertical, horizontal && !vertical, vertical && !horizontal) {
+            (true, true, _) => if right { WallCollision::EdgeRight } else { WallCollision::EdgeLeft },
+            (true, _, true) => if up { WallCollision::EdgeTop } else { WallCollision::EdgeBottom },
+            _ => match (delta.0 == 0, delta.1 == 0) {
+                (true, _) => if up { WallCollision::EdgeTop } else { WallCollision::EdgeBottom },
+                (_, true) => if right { WallCollision::EdgeRight } else { WallCollision::EdgeLeft },
+                _ => match (right, up) {
+                    (true, true) => WallCollision::CornerTopRight,
+                    (false, true) => WallCollision::CornerTopLeft,
+                    (true, false) => WallCollision::CornerBottomRight,
+                    (false, false) => WallCollision::CornerBottomLeft,
+                },
+            },
         }
     }
 
-    fn move_anchor<const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(anchor: &mut Position, delta: &DeltaPosition, board: &Board<MACHO, IMPRISONED, SIZE>, color: Color) -> WallCollision {
+    fn move_anchor<const SIZE: usize>(anchor: &mut Position, delta: &DeltaPosition, board: &Board<SIZE>, color: Color) -> WallCollision {
         let wc = ChessemblyCompiled::wall_collision(anchor, delta, board, color);
         if wc == WallCollision::NoCollision {
-            anchor.0 = ((anchor.0 as i8) + delta.0) as u8;
-            anchor.1 = ((anchor.1 as i8) - delta.1) as u8;
+            let (x, y) = board.wrap(anchor.0 as i16 + delta.0 as i16, anchor.1 as i16 - delta.1 as i16);
+            *anchor = (x as u8, y as u8);
             return WallCollision::NoCollision;
         }
         wc
     }
 
-    pub fn cancel_move_anchor(anchor: &mut Position, delta: &DeltaPosition) {
-        anchor.0 = ((anchor.0 as i8) - delta.0) as u8;
-        anchor.1 = ((anchor.1 as i8) + delta.1) as u8;
+    pub fn cancel_move_anchor<const SIZE: usize>(anchor: &mut Position, delta: &DeltaPosition, board: &Board<SIZE>) {
+        let (x, y) = board.wrap(anchor.0 as i16 - delta.0 as i16, anchor.1 as i16 + delta.1 as i16);
+        *anchor = (x as u8, y as u8);
     }
 
-    pub fn is_enemy<const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(anchor: &Position, board: &Board<MACHO, IMPRISONED, SIZE>, color: Color) -> bool {
-        if board.color_on(anchor) == Some(color.invert()) {
-            return true;
-        }
-        false
+    /// 잡을 수 있는 기물인지. 중립 기물은 `neutral-capture` 규칙일 때만 적입니다.
+    pub fn is_enemy<const SIZE: usize>(anchor: &Position, board: &Board<SIZE>, color: Color) -> bool {
+        board.is_capturable_by(anchor, color)
     }
 
-    pub fn is_friendly<const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(anchor: &Position, board: &Board<MACHO, IMPRISONED, SIZE>, color: Color) -> bool {
-        if board.color_on(anchor) == Some(color) {
-            return true;
-        }
-        false
+    /// 들어갈 수 없는 칸인지. 아군과 잡을 수 없는 중립 기물이 해당합니다.
+    pub fn is_friendly<const SIZE: usize>(anchor: &Position, board: &Board<SIZE>, color: Color) -> bool {
+        board.is_blocked_for(anchor, color)
     }
     
     pub fn is_zero_vector(delta: &DeltaPosition) -> bool {
         delta.0 == 0 && delta.1 == 0
     }
 
-    pub fn is_danger<const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(&self, board: &mut Board<MACHO, IMPRISONED, SIZE>, position: &Position, color: Color) -> bool {
-        let danger_zones = MoveGen::get_danger_zones_bit::<MACHO, IMPRISONED, SIZE>(board, color);
-        ChessemblyCompiled::is_danger_bit(danger_zones, position.0, position.1)
+    pub fn is_danger<const SIZE: usize>(&self, board: &mut Board<SIZE>, position: &Position, color: Color) -> bool {
+        MoveGen::is_attacked::<SIZE>(board, position, color)
     }
 
-    pub fn is_danger_bit(danger_zones_bit: u64, x: u8, y: u8) -> bool {
-        (danger_zones_bit & (1 << (8 * y + x))) != 0
+    pub fn is_danger_bit(danger_zones_bit: SquareSet, x: u8, y: u8) -> bool {
+        danger_zones_bit.contains(&(x, y))
     }
 
-    pub fn is_check<const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(&self, board: &mut Board<MACHO, IMPRISONED, SIZE>, color: Color) -> bool {
-        let danger_zones = MoveGen::get_danger_zones::<MACHO, IMPRISONED, SIZE>(board, color);
-        danger_zones
-            .iter()
-            .any(|x| board.piece_on(x) == Some("king"))
+    /// `color` 진영이 잡을 수 있는 킹이 있는지. 킹 칸마다 거꾸로 공격을 찾습니다.
+    pub fn is_check<const SIZE: usize>(&self, board: &mut Board<SIZE>, color: Color) -> bool {
+        let kings = board.kings;
+        for king in kings.iter() {
+            if MoveGen::is_attacked::<SIZE>(board, &king, color) {
+                return true;
+            }
+        }
+        false
     }
 
-    pub fn is_check_dbg<const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(&self, board: &mut Board<MACHO, IMPRISONED, SIZE>, color: Color) -> bool {
-        let danger_zones = MoveGen::get_danger_zones::<MACHO, IMPRISONED, SIZE>(board, color);
+    pub fn is_check_dbg<const SIZE: usize>(&self, board: &mut Board<SIZE>, color: Color) -> bool {
+        let danger_zones = MoveGen::get_danger_zones::<SIZE>(board, color);
         println!("------------------------ {:?}", color.invert());
-        for i in 0..8 {
+        for i in 0..board.get_height() as u8 {
             let mut x = String::new();
-            for j in 0..8 {
+            for j in 0..board.get_width() as u8 {
                 if danger_zones.contains(&(j, i)) {
                     x.push_str(
                         &format!(
@@ -400,7 +856,7 @@ impl<'a> ChessemblyCompiled<'a> {
         }
         let ret = danger_zones
             .iter()
-            .any(|x| board.piece_on(x) == Some("king"));
+            .any(|x| board.piece_id_on(x) == Some(ids::KING));
 
         if ret {
             println!("==================> Check!")
@@ -411,6 +867,30 @@ impl<'a> ChessemblyCompiled<'a> {
         ret
     }
 
+    /// 앵커에서 `delta`만큼 떨어진 칸. 앵커는 제자리로 돌려 두며, 벽·구멍 너머면 `None`.
+    fn marker_target<const SIZE: usize>(anchor: &mut Position, delta: &DeltaPosition, board: &Board<SIZE>, color: Color) -> Option<Position> {
+        if ChessemblyCompiled::move_anchor(anchor, delta, board, color) != WallCollision::NoCollision {
+            return None;
+        }
+        let target = *anchor;
+        ChessemblyCompiled::cancel_move_anchor(anchor, delta, board);
+        Some(target)
+    }
+
+    /// 생성 중에 모은 표식 변경을 수에 붙일 형태로 바꿉니다.
+    fn marks_of(marks: &Option<Vec<RawMark>>) -> Option<Vec<MarkChange<'a>>> {
+        marks.as_ref().map(|x| {
+            x.iter()
+                .map(|(name, position, plies, remove)| MarkChange {
+                    name: unsafe { name.as_ref().unwrap() },
+                    position: *position,
+                    plies: *plies,
+                    remove: *remove,
+                })
+                .collect()
+        })
+    }
+
     pub fn push_single_node(nodes: &mut Vec<ChessMove<'a>>, node: ChessMoveUnit<'a>) {
         if let Some(i) = nodes
             .iter()
@@ -460,9 +940,9 @@ impl<'a> ChessemblyCompiled<'a> {
         }
     }
 
-    pub fn generate_moves<const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(
+    pub fn generate_moves<const SIZE: usize>(
         &self,
-        board: &mut Board<'a, MACHO, IMPRISONED, SIZE>,
+        board: &mut Board<'a, SIZE>,
         position: &Position,
         check_danger: bool,
     ) -> Result<Vec<ChessMove<'a>>, ()> {
@@ -470,7 +950,7 @@ impl<'a> ChessemblyCompiled<'a> {
         
         let piece_color = board.color_on(position).unwrap();
 
-        for chain in &self.chains {
+        for chain in self.chains_for(piece_color) {
             let mut rip: usize = 0;
             let mut loops = 0;
             let mut stack: Vec<(Position, usize)> = vec![(*position, chain.len())];
@@ -481,6 +961,7 @@ impl<'a> ChessemblyCompiled<'a> {
             let mut states: Vec<bool> = vec![true];
             let mut transition: Option<*const str> = None;
             let mut state_change: Option<Vec<(*const str, u8)>> = None;
+            let mut marks: Option<Vec<RawMark>> = None;
 
             let mut value_array: u16 = 0;
             let mut anchor_array: [Position; 16] = [(0, 0); 16];
@@ -524,7 +1005,7 @@ impl<'a> ChessemblyCompiled<'a> {
                     break;
                 }
                 let abs_inst = &chain[rip];
-                let inst = abs_inst.reflect_turn(piece_color);
+                let inst = abs_inst.reflect_turn(piece_color, (board.get_width() as u8, board.get_height() as u8));
 
                 if stack.len() == 0 || states.len() == 0 {
                     break;
@@ -555,6 +1036,7 @@ impl<'a> ChessemblyCompiled<'a> {
                             ChessemblyCompiled::cancel_move_anchor(
                                 &mut stack_top.0,
                                 &delta,
+                                board,
                             );
                             *states_top = false;
                             rip += 1;
@@ -578,6 +1060,7 @@ impl<'a> ChessemblyCompiled<'a> {
                                             .collect()
                                     }),
                                     transition: transition.map(|x| unsafe { x.as_ref().unwrap() }),
+                                    marks: Self::marks_of(&marks),
                                 },
                             );
                             *states_top = false;
@@ -598,6 +1081,7 @@ impl<'a> ChessemblyCompiled<'a> {
                                             .collect()
                                     }),
                                     transition: transition.map(|x| unsafe { x.as_ref().unwrap() }),
+                                    marks: Self::marks_of(&marks),
                                 },
                             );
                             rip += 1;
@@ -669,6 +1153,7 @@ impl<'a> ChessemblyCompiled<'a> {
                             ChessemblyCompiled::cancel_move_anchor(
                                 &mut stack_top.0,
                                 &delta,
+                                board,
                             );
                             *states_top = false;
                             rip += 1;
@@ -697,6 +1182,7 @@ impl<'a> ChessemblyCompiled<'a> {
                         ChessemblyCompiled::cancel_move_anchor(
                             &mut stack_top.0,
                             &delta,
+                            board,
                         );
                         rip += 1;
                         continue;
@@ -711,15 +1197,7 @@ impl<'a> ChessemblyCompiled<'a> {
                     }
                     Behavior::Color(color_name) => {
                         if let Some(color) = board.color_on(position) {
-                            if color_name == "white" {
-                                *states.last_mut().unwrap() = color == Color::White;
-                            }
-                            else if color_name == "black" {
-                                *states.last_mut().unwrap() = color == Color::Black;
-                            }
-                            else {
-                                *states.last_mut().unwrap() = false;
-                            }
+                            *states.last_mut().unwrap() = color.as_str() == color_name;
                         } else {
                             *states.last_mut().unwrap() = false;
                         }
@@ -852,7 +1330,7 @@ impl<'a> ChessemblyCompiled<'a> {
                     }
                     Behavior::Check => {
                         *states.last_mut().unwrap() =
-                            self.is_check::<MACHO, IMPRISONED, SIZE>(board, piece_color);
+                            self.is_check::<SIZE>(board, piece_color);
                         rip += 1;
                     }
                     Behavior::Danger(delta) => {
@@ -873,7 +1351,7 @@ impl<'a> ChessemblyCompiled<'a> {
                             continue;
                         }
 
-                        *states.last_mut().unwrap() = self.is_danger::<MACHO, IMPRISONED, SIZE>(
+                        *states.last_mut().unwrap() = self.is_danger::<SIZE>(
                             board,
                             &stack.last().unwrap().0,
                             piece_color,
@@ -881,6 +1359,7 @@ impl<'a> ChessemblyCompiled<'a> {
                         ChessemblyCompiled::cancel_move_anchor(
                             &mut stack.last_mut().unwrap().0,
                             &delta,
+                            board,
                         );
                     }
                     Behavior::Enemy(delta) => {
@@ -903,6 +1382,7 @@ impl<'a> ChessemblyCompiled<'a> {
                         ChessemblyCompiled::cancel_move_anchor(
                             &mut stack.last_mut().unwrap().0,
                             &delta,
+                            board,
                         );
                         rip += 1;
                     }
@@ -926,6 +1406,7 @@ impl<'a> ChessemblyCompiled<'a> {
                         ChessemblyCompiled::cancel_move_anchor(
                             &mut stack.last_mut().unwrap().0,
                             &delta,
+                            board,
                         );
                         rip += 1;
                     }
@@ -952,6 +1433,7 @@ impl<'a> ChessemblyCompiled<'a> {
                             ChessemblyCompiled::cancel_move_anchor(
                                 &mut stack.last_mut().unwrap().0,
                                 &delta,
+                                board,
                             );
                             *states.last_mut().unwrap() = false;
                         } else if ChessemblyCompiled::is_enemy(
@@ -962,6 +1444,7 @@ impl<'a> ChessemblyCompiled<'a> {
                             ChessemblyCompiled::cancel_move_anchor(
                                 &mut stack.last_mut().unwrap().0,
                                 &delta,
+                                board,
                             );
                             *states.last_mut().unwrap() = false;
                         } else {
@@ -985,6 +1468,7 @@ impl<'a> ChessemblyCompiled<'a> {
                                             .collect()
                                     }),
                                     transition: transition.map(|x| unsafe { x.as_ref().unwrap() }),
+                                    marks: Self::marks_of(&marks),
                                 },
                             );
                             
@@ -992,7 +1476,7 @@ impl<'a> ChessemblyCompiled<'a> {
                         }
                         rip += 1;
                     }
-                    Behavior::PieceOn((piece_name, delta)) => {
+                    Behavior::PieceOn((_, piece, delta)) => {
                         let wc = ChessemblyCompiled::move_anchor(
                             &mut stack.last_mut().unwrap().0,
                             &delta,
@@ -1005,10 +1489,11 @@ impl<'a> ChessemblyCompiled<'a> {
                             continue;
                         }
                         *states.last_mut().unwrap() =
-                            board.piece_on(&stack.last().unwrap().0) == Some(&piece_name[..]);
+                            board.piece_id_on(&stack.last().unwrap().0) == Some(piece);
                         ChessemblyCompiled::cancel_move_anchor(
                             &mut stack.last_mut().unwrap().0,
                             &delta,
+                            board,
                         );
                         rip += 1;
                     }
@@ -1025,13 +1510,12 @@ impl<'a> ChessemblyCompiled<'a> {
                             continue;
                         }
                         *states.last_mut().unwrap() = if let Some(color) = board.color_on(&stack.last().unwrap().0) {
-                            if color_name == "white" { color == Color::White }
-                            else if color_name == "black" { color == Color::Black }
-                            else { false }
+                            color.as_str() == color_name
                         } else { false };
                         ChessemblyCompiled::cancel_move_anchor(
                             &mut stack.last_mut().unwrap().0,
                             &delta,
+                            board,
                         );
                         rip += 1;
                     }
@@ -1053,6 +1537,14 @@ impl<'a> ChessemblyCompiled<'a> {
                         }
                         rip += 1;
                     }
+                    Behavior::Irreversible => {
+                        if let Some(state_changes) = &mut state_change {
+                            state_changes.push(("irreversible", 1));
+                        } else {
+                            state_change = Some(vec![("irreversible", 1)]);
+                        }
+                        rip += 1;
+                    }
                     Behavior::Transition(piece_name) => {
                         if piece_name.len() == 0 {
                             transition = None;
@@ -1087,6 +1579,7 @@ impl<'a> ChessemblyCompiled<'a> {
                             ChessemblyCompiled::cancel_move_anchor(
                                 &mut stack_top.0,
                                 &delta,
+                                board,
                             );
                             *states_top = false;
                             rip += 1;
@@ -1110,6 +1603,7 @@ impl<'a> ChessemblyCompiled<'a> {
                                             .collect()
                                     }),
                                     transition: transition.map(|x| unsafe { x.as_ref().unwrap() }),
+                                    marks: Self::marks_of(&marks),
                                 },
                             );
                             if let Some(_) = take_stack.pop() {
@@ -1161,6 +1655,7 @@ impl<'a> ChessemblyCompiled<'a> {
                                                     }),
                                                     transition: transition
                                                         .map(|x| unsafe { x.as_ref().unwrap() }),
+                                                    marks: Self::marks_of(&marks),
                                                 },
                                             );
                                             rip += 1;
@@ -1199,6 +1694,7 @@ impl<'a> ChessemblyCompiled<'a> {
                             ChessemblyCompiled::cancel_move_anchor(
                                 &mut stack_top.0,
                                 &delta,
+                                board,
                             );
                             *states_top = false;
                             rip += 1;
@@ -1222,6 +1718,7 @@ impl<'a> ChessemblyCompiled<'a> {
                                             .collect()
                                     }),
                                     transition: transition.map(|x| unsafe { x.as_ref().unwrap() }),
+                                    marks: Self::marks_of(&marks),
                                 },
                             );
                         }
@@ -1248,6 +1745,7 @@ impl<'a> ChessemblyCompiled<'a> {
                             ChessemblyCompiled::cancel_move_anchor(
                                 &mut stack.last_mut().unwrap().0,
                                 &delta,
+                                board,
                             );
                             *states.last_mut().unwrap() = false;
                         } else if ChessemblyCompiled::is_enemy(
@@ -1258,6 +1756,7 @@ impl<'a> ChessemblyCompiled<'a> {
                             ChessemblyCompiled::cancel_move_anchor(
                                 &mut stack.last_mut().unwrap().0,
                                 &delta,
+                                board,
                             );
                             *states.last_mut().unwrap() = false;
                         } else {
@@ -1275,6 +1774,7 @@ impl<'a> ChessemblyCompiled<'a> {
                                             .collect()
                                     }),
                                     transition: transition.map(|x| unsafe { x.as_ref().unwrap() }),
+                                    marks: Self::marks_of(&marks),
                                 },
                             );
                         }
@@ -1441,8 +1941,68 @@ impl<'a> ChessemblyCompiled<'a> {
                         }
                         rip += 1;
                     }
+                    Behavior::InZone((zone, delta)) => {
+                        let wc = ChessemblyCompiled::move_anchor(
+                            &mut stack.last_mut().unwrap().0,
+                            &delta,
+                            board,
+                            piece_color,
+                        );
+                        if wc != WallCollision::NoCollision {
+                            *states.last_mut().unwrap() = false;
+                            rip += 1;
+                            continue;
+                        }
+                        *states.last_mut().unwrap() = board.script.zones.contains(
+                            zone,
+                            piece_color,
+                            &stack.last().unwrap().0,
+                        );
+                        ChessemblyCompiled::cancel_move_anchor(
+                            &mut stack.last_mut().unwrap().0,
+                            &delta,
+                            board,
+                        );
+                        rip += 1;
+                    }
+                    Behavior::Mark((name, delta, plies)) => {
+                        let Some(target) = ChessemblyCompiled::marker_target(&mut stack.last_mut().unwrap().0, &delta, board, piece_color) else {
+                            *states.last_mut().unwrap() = false;
+                            rip += 1;
+                            continue;
+                        };
+                        marks.get_or_insert_with(Vec::new).push((name as *const str, target, plies, false));
+                        rip += 1;
+                    }
+                    Behavior::Unmark((name, delta)) => {
+                        let Some(target) = ChessemblyCompiled::marker_target(&mut stack.last_mut().unwrap().0, &delta, board, piece_color) else {
+                            *states.last_mut().unwrap() = false;
+                            rip += 1;
+                            continue;
+                        };
+                        marks.get_or_insert_with(Vec::new).push((name as *const str, target, None, true));
+                        rip += 1;
+                    }
+                    Behavior::IfMarked((name, delta)) => {
+                        *states.last_mut().unwrap() = ChessemblyCompiled::marker_target(&mut stack.last_mut().unwrap().0, &delta, board, piece_color)
+                            .is_some_and(|target| board.is_marked(&target, name, None));
+                        rip += 1;
+                    }
+                    Behavior::IfEnemyMarked((name, delta)) => {
+                        *states.last_mut().unwrap() = ChessemblyCompiled::marker_target(&mut stack.last_mut().unwrap().0, &delta, board, piece_color)
+                            .is_some_and(|target| board.is_marked(&target, name, Some(piece_color.invert())));
+                        rip += 1;
+                    }
+                    Behavior::Zone(zone) => {
+                        *states.last_mut().unwrap() = board.script.zones.contains(
+                            zone,
+                            piece_color,
+                            &stack.last().unwrap().0,
+                        );
+                        rip += 1;
+                    }
                     Behavior::AbsoulteX(x) => {
-                        if x < (SIZE as u8) {
+                        if board.is_playable(&(x, stack.last().unwrap().0.1)) {
                             stack.last_mut().unwrap().0.0 = x;
                         }
                         else {
@@ -1451,7 +2011,7 @@ impl<'a> ChessemblyCompiled<'a> {
                         rip += 1;
                     }
                     Behavior::AbsoulteY(y) => {
-                        if y < (SIZE as u8) {
+                        if board.is_playable(&(stack.last().unwrap().0.0, y)) {
                             stack.last_mut().unwrap().0.1 = y;
                         }
                         else {
@@ -1461,7 +2021,7 @@ impl<'a> ChessemblyCompiled<'a> {
                     }
                     Behavior::Absoulte(position) => {
                         let stack_top = &mut stack.last_mut().unwrap().0;
-                        if position.0 < (SIZE as u8) && position.1 < (SIZE as u8) {
+                        if board.is_playable(&position) {
                             *stack_top = position;
                         }
                         else {
@@ -1523,6 +2083,7 @@ impl<'a> ChessemblyCompiled<'a> {
                                         .collect()
                                 }),
                                 transition: transition.map(|x| unsafe { x.as_ref().unwrap() }),
+                                marks: Self::marks_of(&marks),
                             });
                         }
                         rip += 1;
@@ -1534,9 +2095,9 @@ impl<'a> ChessemblyCompiled<'a> {
         return Ok(nodes);
     }
 
-    pub fn filter_nodes<const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(&self, nodes: Vec<ChessMove<'a>>, board: &Board<'a, MACHO, IMPRISONED, SIZE>) -> Vec<ChessMove<'a>> {
+    pub fn filter_nodes<const SIZE: usize>(&self, nodes: Vec<ChessMove<'a>>, board: &mut Board<'a, SIZE>) -> Vec<ChessMove<'a>> {
         let mut ret: Vec<ChessMove> = Vec::new();
-        if MACHO {
+        if board.rules.macho {
             for testnode in nodes {
                 let piece_color = board.color_on(&testnode.get_source()).unwrap();
                 match (testnode.get_source().1.cmp(&testnode.get_dest().1), piece_color) {
@@ -1551,7 +2112,8 @@ impl<'a> ChessemblyCompiled<'a> {
                                     move_to: n.move_to,
                                     move_type: MoveType::Take,
                                     state_change: n.state_change,
-                                    transition: n.transition
+                                    transition: n.transition,
+                                    marks: n.marks,
                                 }));
                             }
                         }
@@ -1562,12 +2124,44 @@ impl<'a> ChessemblyCompiled<'a> {
             }
             ret
         }
+        else if !board.rules.royal_king {
+            // 킹이 평범한 기물이면 자기 킹을 위험에 두는 수도 합법입니다.
+            nodes
+        }
         else {
+            // 상대가 볼 국면(차례를 넘긴 단계 0, 주사위 없음)에서 킹의 체크·핀을 구합니다.
+            let (turn, phase, roll) = (board.turn, board.phase, board.roll);
+            board.turn = turn.invert();
+            board.phase = 0;
+            board.roll = None;
+            let safety = MoveGen::king_safety::<SIZE>(board, turn);
+            board.turn = turn;
+            board.phase = phase;
+            board.roll = roll;
+
+            // 나머지는 보드를 복사하지 않고 두어 본 뒤 되돌립니다.
             for testnode in nodes {
-                let mut new_board = board.make_move_new_nc(&testnode, false);
-                let turn = new_board.turn;
-                new_board.turn = new_board.turn.invert();
-                if !self.is_check::<MACHO, IMPRISONED, SIZE>(&mut new_board, turn.invert()) {
+                if safety.as_ref().is_some_and(|safety| safety.is_safe(&testnode)) {
+                    ret.push(testnode);
+                    continue;
+                }
+                // 캐슬링은 킹이 지나가는 칸에서도 체크가 아니어야 합니다.
+                let passes_check = match &testnode {
+                    ChessMove::Single(n) if n.move_type == MoveType::Castling => {
+                        let (from, to) = (n.from.0.min(n.move_to.0), n.from.0.max(n.move_to.0));
+                        (from + 1..to).any(|x| self.leaves_in_check::<SIZE>(board, &ChessMove::Single(ChessMoveUnit {
+                            from: n.from,
+                            take: (x, n.from.1),
+                            move_to: (x, n.from.1),
+                            move_type: MoveType::Move,
+                            state_change: None,
+                            transition: None,
+                            marks: None,
+                        })))
+                    }
+                    _ => false,
+                };
+                if !passes_check && !self.leaves_in_check::<SIZE>(board, &testnode) {
                     ret.push(testnode);
                 }
             }
@@ -1576,163 +2170,259 @@ impl<'a> ChessemblyCompiled<'a> {
         }
     }
 
-    pub fn get_moves<const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(&self, board: &mut Board<'a, MACHO, IMPRISONED, SIZE>, position: &Position, check_danger: bool) -> Vec<ChessMove<'a>> {
+    /// `node`를 두면 둔 쪽이 체크 상태로 남는지. 보드는 두어 본 뒤 되돌립니다.
+    fn leaves_in_check<const SIZE: usize>(&self, board: &mut Board<'a, SIZE>, node: &ChessMove<'a>) -> bool {
+        let undo = board.make_move_in_place(node, false);
+        let turn = board.turn;
+        board.turn = board.turn.invert();
+        board.phase = 0;
+        board.roll = None;
+        let check = self.is_check::<SIZE>(board, turn.invert());
+        board.unmake_move(undo);
+        check
+    }
+
+    pub fn get_moves<const SIZE: usize>(&self, board: &mut Board<'a, SIZE>, position: &Position, check_danger: bool) -> Vec<ChessMove<'a>> {
         if let Some(cached) = board.dp.get(position) {
             return cached.clone();
         }
 
-        let piece_on = board.piece_on(position);
-        let Some(piece) = piece_on else {
+        let Some(id) = board.piece_id_on(position) else {
             return Vec::new()
         };
-        // worker::console_log!("{}", piece);
-        match piece {
-            "pawn" => {
-                let ret = self.generate_pawn_moves::<MACHO, IMPRISONED, SIZE>(board, position);
+        if let Grid::Hex(_) = board.rules.grid {
+            if let Some(ret) = self.generate_hex_moves::<SIZE>(board, position, id, check_danger) {
+                board.dp.insert((position.0, position.1), ret.clone());
+                return ret;
+            }
+        }
+        match id {
+            ids::PAWN => {
+                let ret = self.generate_pawn_moves::<SIZE>(board, position);
                 board.dp.insert((position.0, position.1), ret.clone());
                 ret
             }
-            "king" => {
-                let danger_zones = if check_danger { MoveGen::get_danger_zones_bit::<MACHO, IMPRISONED, SIZE>(board, board.color_on(position).unwrap().invert()) } else { 0 };
-                let ret = self.generate_king_moves::<MACHO, IMPRISONED, SIZE>(board, position, danger_zones);
+            ids::KING => {
+                let danger_zones = if check_danger && board.rules.royal_king { MoveGen::get_danger_zones_bit::<SIZE>(board, board.color_on(position).unwrap().invert()) } else { SquareSet::EMPTY };
+                let ret = self.generate_king_moves::<SIZE>(board, position, danger_zones);
                 board.dp.insert((position.0, position.1), ret.clone());
                 ret
             }
-            "rook" => {
-                let ret = self.generate_rook_moves::<MACHO, IMPRISONED, SIZE>(board, position);
+            ids::ROOK => {
+                let ret = self.generate_rook_moves::<SIZE>(board, position);
                 board.dp.insert((position.0, position.1), ret.clone());
                 ret
             }
-            "knight" => {
-                let ret = self.generate_knight_moves::<MACHO, IMPRISONED, SIZE>(board, position);
+            ids::KNIGHT => {
+                let ret = self.generate_knight_moves::<SIZE>(board, position);
                 board.dp.insert((position.0, position.1), ret.clone());
                 ret
             }
-            "bishop" => {
-                let ret = self.generate_bishop_moves::<MACHO, IMPRISONED, SIZE>(board, position);
+            ids::BISHOP => {
+                let ret = self.generate_bishop_moves::<SIZE>(board, position);
                 board.dp.insert((position.0, position.1), ret.clone());
                 ret
             }
-            "queen" => {
-                let ret = self.generate_queen_moves::<MACHO, IMPRISONED, SIZE>(board, position);
+            ids::QUEEN => {
+                let ret = self.generate_queen_moves::<SIZE>(board, position);
                 board.dp.insert((position.0, position.1), ret.clone());
                 ret
             }
-            "tempest-rook" => {
-                let ret = self.generate_tempest_rook_moves::<MACHO, IMPRISONED, SIZE>(board, position);
+            ids::TEMPEST_ROOK => {
+                let ret = self.generate_tempest_rook_moves::<SIZE>(board, position);
                 board.dp.insert((position.0, position.1), ret.clone());
                 ret
             }
-            "bouncing-bishop" => {
-                let ret = self.generate_bouncing_bishop_moves::<MACHO, IMPRISONED, SIZE>(board, position);
+            ids::BOUNCING_BISHOP => {
+                let ret = self.generate_bouncing_bishop_moves::<SIZE>(board, position);
                 board.dp.insert((position.0, position.1), ret.clone());
                 ret
             }
-            "dozer" => {
-                let ret = self.generate_dozer_moves::<MACHO, IMPRISONED, SIZE>(board, position);
+            ids::DOZER => {
+                let ret = self.generate_dozer_moves::<SIZE>(board, position);
                 board.dp.insert((position.0, position.1), ret.clone());
                 ret
             }
-            "alfil" => {
-                let ret = self.generate_alfil_moves::<MACHO, IMPRISONED, SIZE>(board, position);
+            ids::ALFIL => {
+                let ret = self.generate_alfil_moves::<SIZE>(board, position);
                 board.dp.insert((position.0, position.1), ret.clone());
                 ret
             }
-            "bard" => {
-                let ret = self.generate_bard_moves::<MACHO, IMPRISONED, SIZE>(board, position);
+            ids::BARD => {
+                let ret = self.generate_bard_moves::<SIZE>(board, position);
                 board.dp.insert((position.0, position.1), ret.clone());
                 ret
             }
-            "wasp" => {
-                let ret = self.generate_wasp_moves::<MACHO, IMPRISONED, SIZE>(board, position);
+            ids::WASP => {
+                let ret = self.generate_wasp_moves::<SIZE>(board, position);
                 board.dp.insert((position.0, position.1), ret.clone());
                 ret
             }
-            "amazon" => {
-                let ret = self.generate_amazon_moves::<MACHO, IMPRISONED, SIZE>(board, position);
+            ids::AMAZON => {
+                let ret = self.generate_amazon_moves::<SIZE>(board, position);
                 board.dp.insert((position.0, position.1), ret.clone());
                 ret
             }
-            "chancellor" => {
-                let ret = self.generate_chancellor_moves::<MACHO, IMPRISONED, SIZE>(board, position);
+            ids::CHANCELLOR => {
+                let ret = self.generate_chancellor_moves::<SIZE>(board, position);
                 board.dp.insert((position.0, position.1), ret.clone());
                 ret
             }
-            "archbishop" => {
-                let ret = self.generate_archbishop_moves::<MACHO, IMPRISONED, SIZE>(board, position);
+            ids::ARCHBISHOP => {
+                let ret = self.generate_archbishop_moves::<SIZE>(board, position);
                 board.dp.insert((position.0, position.1), ret.clone());
                 ret
             }
-            "centaur" => {
-                let ret = self.generate_centaur_moves::<MACHO, IMPRISONED, SIZE>(board, position);
+            ids::CENTAUR => {
+                let ret = self.generate_centaur_moves::<SIZE>(board, position);
                 board.dp.insert((position.0, position.1), ret.clone());
                 ret
             }
-            "zebra" => {
-                let ret = self.generate_ij_moves::<MACHO, IMPRISONED, SIZE>(board, position, 3, 2);
+            ids::ZEBRA => {
+                let ret = self.generate_ij_moves::<SIZE>(board, position, 3, 2);
                 board.dp.insert((position.0, position.1), ret.clone());
                 ret
             }
-            "giraffe" => {
-                let ret = self.generate_ij_moves::<MACHO, IMPRISONED, SIZE>(board, position, 4, 1);
+            ids::GIRAFFE => {
+                let ret = self.generate_ij_moves::<SIZE>(board, position, 4, 1);
                 board.dp.insert((position.0, position.1), ret.clone());
                 ret
             }
-            "camel" => {
-                let ret = self.generate_ij_moves::<MACHO, IMPRISONED, SIZE>(board, position, 3, 1);
+            ids::CAMEL => {
+                let ret = self.generate_ij_moves::<SIZE>(board, position, 3, 1);
                 board.dp.insert((position.0, position.1), ret.clone());
                 ret
             }
-            "beacon" => {
-                let ret = self.generate_beacon_moves::<MACHO, IMPRISONED, SIZE>(board, position);
+            ids::BEACON => {
+                let ret = self.generate_beacon_moves::<SIZE>(board, position);
                 board.dp.insert((position.0, position.1), ret.clone());
                 ret
             }
-            "chameleon" => {
-                let ret = self.generate_chameleon_moves::<MACHO, IMPRISONED, SIZE>(board, position);
+            ids::CHAMELEON => {
+                let ret = self.generate_chameleon_moves::<SIZE>(board, position);
                 board.dp.insert((position.0, position.1), ret.clone());
                 ret
             }
-            "mirrored-pawn" => {
-                let ret = self.generate_mirrored_moves::<MACHO, IMPRISONED, SIZE>(board, position, "mirrored-pawn");
+            ids::MIRRORED_PAWN => {
+                let ret = self.generate_mirrored_moves::<SIZE>(board, position, ids::MIRRORED_PAWN);
                 board.dp.insert((position.0, position.1), ret.clone());
                 ret
             }
-            "mirrored-bishop" => {
-                let ret = self.generate_mirrored_moves::<MACHO, IMPRISONED, SIZE>(board, position, "mirrored-bishop");
+            ids::MIRRORED_BISHOP => {
+                let ret = self.generate_mirrored_moves::<SIZE>(board, position, ids::MIRRORED_BISHOP);
                 board.dp.insert((position.0, position.1), ret.clone());
                 ret
             }
-            "mirrored-rook" => {
-                let ret = self.generate_mirrored_moves::<MACHO, IMPRISONED, SIZE>(board, position, "mirrored-rook");
+            ids::MIRRORED_ROOK => {
+                let ret = self.generate_mirrored_moves::<SIZE>(board, position, ids::MIRRORED_ROOK);
                 board.dp.insert((position.0, position.1), ret.clone());
                 ret
             }
-            "mirrored-knight" => {
-                let ret = self.generate_mirrored_moves::<MACHO, IMPRISONED, SIZE>(board, position, "mirrored-knight");
+            ids::MIRRORED_KNIGHT => {
+                let ret = self.generate_mirrored_moves::<SIZE>(board, position, ids::MIRRORED_KNIGHT);
                 board.dp.insert((position.0, position.1), ret.clone());
                 ret
             }
-            "mirrored-queen" => {
-                let ret = self.generate_mirrored_moves::<MACHO, IMPRISONED, SIZE>(board, position, "mirrored-queen");
+            ids::MIRRORED_QUEEN => {
+                let ret = self.generate_mirrored_moves::<SIZE>(board, position, ids::MIRRORED_QUEEN);
                 board.dp.insert((position.0, position.1), ret.clone());
                 ret
             }
-            "windmill-rook" => {
-                let ret = self.generate_windmill_rook_moves::<MACHO, IMPRISONED, SIZE>(board, position);
+            ids::WINDMILL_ROOK => {
+                let ret = self.generate_windmill_rook_moves::<SIZE>(board, position);
                 board.dp.insert((position.0, position.1), ret.clone());
                 ret
             }
-            "windmill-bishop" => {
-                let ret = self.generate_windmill_bishop_moves::<MACHO, IMPRISONED, SIZE>(board, position);
+            ids::WINDMILL_BISHOP => {
+                let ret = self.generate_windmill_bishop_moves::<SIZE>(board, position);
+                board.dp.insert((position.0, position.1), ret.clone());
+                ret
+            }
+            ids::DUCK => {
+                let ret = self.generate_duck_moves::<SIZE>(board, position);
                 board.dp.insert((position.0, position.1), ret.clone());
                 ret
             }
             _ => {
-                let ret = self.generate_moves::<MACHO, IMPRISONED, SIZE>(board, position, check_danger);
+                let ret = self.generate_moves::<SIZE>(board, position, check_danger);
                 board.dp.insert((position.0, position.1), ret.clone().unwrap_or(Vec::new()));
                 ret.unwrap_or(Vec::new())
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::board::{Board, BoardStatus};
+    use super::rules::Rules;
+    use super::*;
+
+    /// 차례인 쪽이 `from`에서 `to`로 가는 합법 수를 둡니다.
+    fn play<'a>(board: &Board<'a, 8>, from: Position, to: Position) -> Board<'a, 8> {
+        let mut board = board.clone();
+        let node = MoveGen::new_legal(&mut board)
+            .into_iter()
+            .find(|node| node.get_source() == from && node.get_dest() == to)
+            .expect("legal move");
+        board.make_move_new(&node)
+    }
+
+    fn antichess_board<'a>(compiled: &'a ChessemblyCompiled<'a>, rules: &'a Rules<'a>) -> Board<'a, 8> {
+        let mut board = Board::<8>::new(compiled);
+        board.rules = rules;
+        board.hash = board.zobrist();
+        board
+    }
+
+    #[test]
+    fn compulsory_capture_returns_only_captures() {
+        let compiled = ChessemblyCompiled::new();
+        let rules = Rules::parse("antichess");
+        let board = antichess_board(&compiled, &rules);
+        // 1. e4 d5: 백은 exd5만 둘 수 있습니다.
+        let mut board = play(&play(&board, (4, 6), (4, 4)), (3, 1), (3, 3));
+        let moves = MoveGen::new_legal(&mut board);
+        assert_eq!(moves.len(), 1);
+        assert_eq!((moves[0].get_source(), moves[0].get_dest()), ((4, 4), (3, 3)));
+
+        // 잡을 수가 없으면 모든 수를 둘 수 있습니다.
+        let mut board = antichess_board(&compiled, &rules);
+        assert_eq!(MoveGen::new_legal(&mut board).len(), 20);
+    }
+
+    #[test]
+    fn compulsory_capture_includes_en_passant() {
+        let compiled = ChessemblyCompiled::new();
+        let rules = Rules::parse("antichess");
+        let mut board = antichess_board(&compiled, &rules);
+        // 1. e4 h6 2. e5 d5: 캡처는 exd6 앙파상뿐입니다.
+        for (from, to) in [((4, 6), (4, 4)), ((7, 1), (7, 2)), ((4, 4), (4, 3)), ((3, 1), (3, 3))] {
+            board = play(&board, from, to);
+        }
+        let moves = MoveGen::new_legal(&mut board);
+        assert_eq!(moves.len(), 1);
+        let ChessMove::Single(unit) = &moves[0] else { panic!("{:?}", moves[0]) };
+        assert_eq!((unit.from, unit.move_to, unit.take, unit.move_type), ((4, 3), (3, 2), (3, 3), MoveType::TakeJump));
+    }
+
+    #[test]
+    fn antichess_losing_all_pieces_wins() {
+        let compiled = ChessemblyCompiled::new();
+        let rules = Rules::parse("antichess");
+        let mut board = Board::<8>::empty(&compiled);
+        board.rules = &rules;
+        board.turn = Color::Black;
+        for (piece, (x, y), color) in [("pawn", (4, 4), Color::White), ("rook", (4, 0), Color::Black), ("knight", (0, 0), Color::Black)] {
+            board.board[y][x] = PieceSpan::Piece(Piece::new(piece, color, &compiled.pieces));
+        }
+        board.rebuild_occupancy();
+        board.hash = board.zobrist();
+        // 흑은 룩으로 마지막 백 기물을 잡아야 하고, 기물을 모두 잃은 백이 이깁니다.
+        let moves = MoveGen::new_legal(&mut board);
+        assert_eq!(moves.len(), 1);
+        let board = board.make_move_new(&moves[0]);
+        assert_eq!(board.status, BoardStatus::Victory(Color::White));
+        assert_eq!(board.result(), Some(GameResult::WhiteWins));
+    }
+}
@@ -0,0 +1,144 @@
+use std::sync::OnceLock;
+
+use super::{ids, DeltaPosition, PieceId, Position, SquareSet, MAX_BOARD_SIZE};
+
+// -----------------------------------------------------------------------------
+// 기본 기물용 공격 테이블
+// 최대 크기(16×16) 보드 기준으로 한 번만 만들고, 실제 보드 영역과 구멍은
+// 호출하는 쪽에서 마스크로 잘라 냅니다. 델타는 스크립트와 같이 y가 위쪽입니다.
+// -----------------------------------------------------------------------------
+
+/// 리퍼 테이블에 담는 최대 도약 거리. 더 먼 도약은 그때그때 계산합니다.
+pub const MAX_LEAP: i8 = 4;
+
+const LEAP_SPAN: usize = (2 * MAX_LEAP + 1) as usize;
+const SQUARES: usize = MAX_BOARD_SIZE * MAX_BOARD_SIZE;
+
+/// 슬라이딩 방향. `rays`의 두 번째 인덱스 순서입니다.
+const DIRECTIONS: [DeltaPosition; 8] = [(1, 0), (-1, 0), (0, 1), (0, -1), (1, 1), (1, -1), (-1, 1), (-1, -1)];
+
+struct Tables {
+    /// 도약 델타마다 칸별 도착 칸.
+    leaps: Vec<[Option<Position>; SQUARES]>,
+    /// 칸마다 방향별 광선 (출발 칸 제외, 16×16 끝까지).
+    rays: Vec<[SquareSet; 8]>,
+}
+
+fn tables() -> &'static Tables {
+    static TABLES: OnceLock<Tables> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        let mut leaps = vec![[None; SQUARES]; LEAP_SPAN * LEAP_SPAN];
+        let mut rays = vec![[SquareSet::EMPTY; 8]; SQUARES];
+        for i in 0..SQUARES {
+            let from = ((i % MAX_BOARD_SIZE) as u8, (i / MAX_BOARD_SIZE) as u8);
+            for dy in -MAX_LEAP..=MAX_LEAP {
+                for dx in -MAX_LEAP..=MAX_LEAP {
+                    leaps[leap_index(&(dx, dy))][i] = step(&from, &(dx, dy));
+                }
+            }
+            for (d, delta) in DIRECTIONS.iter().enumerate() {
+                let mut anchor = from;
+                while let Some(next) = step(&anchor, delta) {
+                    rays[i][d].insert(&next);
+                    anchor = next;
+                }
+            }
+        }
+        Tables { leaps, rays }
+    })
+}
+
+#[inline]
+fn leap_index(delta: &DeltaPosition) -> usize {
+    (delta.1 + MAX_LEAP) as usize * LEAP_SPAN + (delta.0 + MAX_LEAP) as usize
+}
+
+#[inline]
+fn step(from: &Position, delta: &DeltaPosition) -> Option<Position> {
+    let x = from.0 as i16 + delta.0 as i16;
+    let y = from.1 as i16 - delta.1 as i16;
+    let size = MAX_BOARD_SIZE as i16;
+    if x < 0 || y < 0 || x >= size || y >= size {
+        return None;
+    }
+    Some((x as u8, y as u8))
+}
+
+/// `from`에서 `delta`만큼 도약한 칸. 16×16 밖이면 None입니다.
+#[inline]
+pub fn leap(from: &Position, delta: &DeltaPosition) -> Option<Position> {
+    if delta.0.abs() > MAX_LEAP || delta.1.abs() > MAX_LEAP {
+        return step(from, delta);
+    }
+    tables().leaps[leap_index(delta)][SquareSet::index(from)]
+}
+
+/// 광선이 칸 인덱스가 커지는 쪽으로 뻗는지. 바깥쪽 순서로 순회할 때 씁니다.
+#[inline]
+pub fn is_ascending(delta: &DeltaPosition) -> bool {
+    delta.1 < 0 || (delta.1 == 0 && delta.0 > 0)
+}
+
+/// `from`에서 한 칸 단위 `delta` 방향으로 미끄러질 때 닿는 칸. `blockers` 중 처음 만나는 칸까지 포함합니다.
+/// 한 칸 단위 방향이 아니면 None입니다.
+#[inline]
+pub fn slide(from: &Position, delta: &DeltaPosition, blockers: SquareSet) -> Option<SquareSet> {
+    let d = DIRECTIONS.iter().position(|x| x == delta)?;
+    let tables = tables();
+    let ray = tables.rays[SquareSet::index(from)][d];
+    let hit = ray & blockers;
+    let first = if is_ascending(delta) { hit.first() } else { hit.last() };
+    Some(match first {
+        Some(blocker) => ray ^ tables.rays[SquareSet::index(&blocker)][d],
+        None => ray,
+    })
+}
+
+pub const ORTHOGONAL: [DeltaPosition; 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+pub const DIAGONAL: [DeltaPosition; 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+pub const KING: [DeltaPosition; 8] = [(1, 0), (-1, 0), (0, 1), (0, -1), (1, 1), (1, -1), (-1, 1), (-1, -1)];
+pub const KNIGHT: [DeltaPosition; 8] = leaper(2, 1);
+pub const ALFIL: [DeltaPosition; 4] = [(2, 2), (2, -2), (-2, 2), (-2, -2)];
+pub const BARD: [DeltaPosition; 8] = [(2, 0), (-2, 0), (0, 2), (0, -2), (2, 2), (2, -2), (-2, 2), (-2, -2)];
+pub const CENTAUR: [DeltaPosition; 16] = [
+    (2, 1), (-2, 1), (2, -1), (-2, -1), (1, 2), (-1, 2), (1, -2), (-1, -2),
+    (1, 0), (-1, 0), (0, 1), (0, -1), (1, 1), (1, -1), (-1, 1), (-1, -1),
+];
+
+/// (i, j) 도약 기물의 8방향 (`generate_ij_moves`와 같은 순서).
+pub const fn leaper(i: i8, j: i8) -> [DeltaPosition; 8] {
+    [(i, j), (-i, j), (i, -j), (-i, -j), (j, i), (-j, i), (j, -i), (-j, -i)]
+}
+
+/// 점유 칸만으로 공격 칸이 정해지는 기본 기물의 공격 방식.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum NativeAttack {
+    /// 앞 대각선 캡처와 앙파상.
+    Pawn,
+    /// 도약 방향과 슬라이딩 방향. 두 목록 모두 방향을 뒤집어도 그대로입니다.
+    Pattern(&'static [DeltaPosition], &'static [DeltaPosition]),
+}
+
+/// 기본 기물의 (도약, 슬라이딩) 방향. 방향이 고정되지 않거나 스크립트로 움직이는 기물은 None입니다.
+pub fn native_pattern(piece: PieceId) -> Option<(&'static [DeltaPosition], &'static [DeltaPosition])> {
+    const ZEBRA: [DeltaPosition; 8] = leaper(3, 2);
+    const GIRAFFE: [DeltaPosition; 8] = leaper(4, 1);
+    const CAMEL: [DeltaPosition; 8] = leaper(3, 1);
+    Some(match piece {
+        ids::KING => (&KING, &[]),
+        ids::KNIGHT => (&KNIGHT, &[]),
+        ids::BISHOP => (&[], &DIAGONAL),
+        ids::ROOK => (&[], &ORTHOGONAL),
+        ids::QUEEN => (&[], &KING),
+        ids::AMAZON => (&KNIGHT, &KING),
+        ids::CHANCELLOR => (&KNIGHT, &ORTHOGONAL),
+        ids::ARCHBISHOP => (&KNIGHT, &DIAGONAL),
+        ids::CENTAUR => (&CENTAUR, &[]),
+        ids::ALFIL => (&ALFIL, &[]),
+        ids::BARD => (&BARD, &[]),
+        ids::ZEBRA => (&ZEBRA, &[]),
+        ids::GIRAFFE => (&GIRAFFE, &[]),
+        ids::CAMEL => (&CAMEL, &[]),
+        _ => return None,
+    })
+}
@@ -1,6 +1,6 @@
 use crate::chessembly::Position;
 
-use super::{Color, DeltaPosition};
+use super::{zobrist::UNKNOWN_PIECE, Color, DeltaPosition, PieceId};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Behavior<'a> {
@@ -42,12 +42,14 @@ pub enum Behavior<'a> {
     Check,
     Enemy(DeltaPosition),
     Friendly(DeltaPosition),
-    PieceOn((&'a str, DeltaPosition)),
+    /// (기물 이름, 컴파일 때 정해지는 기물 ID, 델타)
+    PieceOn((&'a str, PieceId, DeltaPosition)),
     ColorOn((&'a str, DeltaPosition)),
     PlaceMove((&'a str, DeltaPosition)),
     SetState((&'a str, u8)),
     IfState((&'a str, u8)),
     Transition(&'a str),
+    Irreversible,
     Piece(&'a str),
     Color(&'a str),
     
@@ -63,6 +65,14 @@ pub enum Behavior<'a> {
     AbsoulteX(u8),
     AbsoulteY(u8),
     Absoulte(Position),
+
+    InZone((&'a str, DeltaPosition)),
+    Zone(&'a str),
+
+    Mark((&'a str, DeltaPosition, Option<u8>)),
+    Unmark((&'a str, DeltaPosition)),
+    IfMarked((&'a str, DeltaPosition)),
+    IfEnemyMarked((&'a str, DeltaPosition)),
     
     True,
     False
@@ -88,6 +98,8 @@ impl<'a> Behavior<'a> {
             return Behavior::Check;
         } else if fragment == "transition" {
             return Behavior::Transition("");
+        } else if fragment == "irreversible" {
+            return Behavior::Irreversible;
         } else if fragment.starts_with("}") {
             return Behavior::BlockClose;
         } else if fragment.starts_with("{") {
@@ -241,6 +253,7 @@ impl<'a> Behavior<'a> {
         } else if cmd == "piece-on" {
             return Behavior::PieceOn((
                 params_vec.get(0).unwrap_or(&""),
+                UNKNOWN_PIECE,
                 (
                     params_vec
                         .get(1)
@@ -266,6 +279,40 @@ impl<'a> Behavior<'a> {
                         .unwrap_or(0),
                 ),
             ));
+        } else if cmd == "in-zone" {
+            return Behavior::InZone((
+                params_vec.first().unwrap_or(&""),
+                (
+                    params_vec
+                        .get(1)
+                        .map(|s| s.parse::<i8>().unwrap_or(0))
+                        .unwrap_or(0),
+                    params_vec
+                        .get(2)
+                        .map(|s| s.parse::<i8>().unwrap_or(0))
+                        .unwrap_or(0),
+                ),
+            ));
+        } else if cmd == "mark" {
+            let delta = (
+                params_vec.get(1).map(|s| s.parse::<i8>().unwrap_or(0)).unwrap_or(0),
+                params_vec.get(2).map(|s| s.parse::<i8>().unwrap_or(0)).unwrap_or(0),
+            );
+            let plies = params_vec.get(3).and_then(|s| s.parse::<u8>().ok());
+            return Behavior::Mark((params_vec.first().unwrap_or(&""), delta, plies));
+        } else if cmd == "unmark" || cmd == "if-marked" || cmd == "if-enemy-marked" {
+            let name = params_vec.first().unwrap_or(&"");
+            let delta = (
+                params_vec.get(1).map(|s| s.parse::<i8>().unwrap_or(0)).unwrap_or(0),
+                params_vec.get(2).map(|s| s.parse::<i8>().unwrap_or(0)).unwrap_or(0),
+            );
+            return match cmd {
+                "unmark" => Behavior::Unmark((name, delta)),
+                "if-marked" => Behavior::IfMarked((name, delta)),
+                _ => Behavior::IfEnemyMarked((name, delta)),
+            };
+        } else if cmd == "zone" {
+            return Behavior::Zone(params_vec.first().unwrap_or(&""));
         } else if cmd == "color-on" {
             return Behavior::ColorOn((
                 params_vec.get(0).unwrap_or(&""),
@@ -557,15 +604,17 @@ impl<'a> Behavior<'a> {
         }
     }
 
-    fn reflect_abs_vector(position: &Position, turn: Color) -> Position {
+    fn reflect_abs_vector(position: &Position, turn: Color, size: (u8, u8)) -> Position {
         if turn == Color::Black {
-            return (7 - position.0, 7 - position.1);
+            return ((size.0 - 1).wrapping_sub(position.0), (size.1 - 1).wrapping_sub(position.1));
         } else {
             return position.clone();
         }
     }
 
-    pub fn reflect_turn(&'a self, turn: Color) -> Behavior<'a> {
+    /// 흑의 델타는 점대칭으로 뒤집습니다. 육각 보드의 축 좌표에서도 그대로 성립합니다.
+    /// 절대 좌표는 `size`(가로, 세로) 보드 기준으로 뒤집습니다.
+    pub fn reflect_turn(&'a self, turn: Color, size: (u8, u8)) -> Behavior<'a> {
         match self {
             Behavior::Bound(delta) => Behavior::Bound(Behavior::reflect_turn_vector(delta, turn)),
             Behavior::Edge(delta) => Behavior::Edge(Behavior::reflect_turn_vector(delta, turn)),
@@ -610,15 +659,30 @@ impl<'a> Behavior<'a> {
                 Behavior::Observe(Behavior::reflect_turn_vector(delta, turn))
             }
             Behavior::Peek(delta) => Behavior::Peek(Behavior::reflect_turn_vector(delta, turn)),
-            Behavior::Absoulte(coord) => Behavior::Absoulte(Behavior::reflect_abs_vector(coord, turn)),
-            Behavior::AbsoulteX(x) => Behavior::AbsoulteX(Behavior::reflect_abs_vector(&(*x, 0), turn).0),
-            Behavior::AbsoulteY(y) => Behavior::AbsoulteY(Behavior::reflect_abs_vector(&(0, *y), turn).1),
-            Behavior::PieceOn((piece, delta)) => {
-                Behavior::PieceOn((piece, Behavior::reflect_turn_vector(delta, turn)))
+            Behavior::Absoulte(coord) => Behavior::Absoulte(Behavior::reflect_abs_vector(coord, turn, size)),
+            Behavior::AbsoulteX(x) => Behavior::AbsoulteX(Behavior::reflect_abs_vector(&(*x, 0), turn, size).0),
+            Behavior::AbsoulteY(y) => Behavior::AbsoulteY(Behavior::reflect_abs_vector(&(0, *y), turn, size).1),
+            Behavior::PieceOn((piece, id, delta)) => {
+                Behavior::PieceOn((piece, *id, Behavior::reflect_turn_vector(delta, turn)))
             }
             Behavior::PlaceMove((piece, delta)) => {
                 Behavior::PlaceMove((piece, Behavior::reflect_turn_vector(delta, turn)))
             }
+            Behavior::InZone((zone, delta)) => {
+                Behavior::InZone((zone, Behavior::reflect_turn_vector(delta, turn)))
+            }
+            Behavior::Mark((name, delta, plies)) => {
+                Behavior::Mark((name, Behavior::reflect_turn_vector(delta, turn), *plies))
+            }
+            Behavior::Unmark((name, delta)) => {
+                Behavior::Unmark((name, Behavior::reflect_turn_vector(delta, turn)))
+            }
+            Behavior::IfMarked((name, delta)) => {
+                Behavior::IfMarked((name, Behavior::reflect_turn_vector(delta, turn)))
+            }
+            Behavior::IfEnemyMarked((name, delta)) => {
+                Behavior::IfEnemyMarked((name, Behavior::reflect_turn_vector(delta, turn)))
+            }
             _ => self.clone(),
         }
     }
@@ -1,12 +1,16 @@
-use crate::chessembly::{ChessMoveUnit, MoveType};
+use crate::chessembly::{ChessMoveUnit, MarkChange, MoveType};
 
-use super::{ChessMove, ChessemblyCompiled, Color, HashMap, MoveGen, Piece, PieceSpan, Position};
+use super::{ids, ChessMove, ChessemblyCompiled, Color, HashMap, MoveGen, Piece, PieceId, PieceSpan, Position, SquareSet};
+use super::rules::{Grid, Phase, Rules, StalemateRule, Topology, STANDARD_RULES};
+use super::zobrist;
 
 #[derive(Copy, Clone, PartialEq, PartialOrd, Debug, Eq)]
 pub enum BoardStatus {
     Ongoing,
     Stalemate,
     Checkmate,
+    Victory(Color),
+    Draw,
 }
 
 #[derive(PartialEq, Eq, Clone, Debug)]
@@ -15,6 +19,29 @@ pub struct BoardState<'a> {
     pub castling_ooo: bool,
     pub enpassant: Vec<Position>,
     pub register: HashMap<&'a str, u8>,
+    pub checks: u8,
+    /// 아직 놓지 않은 기물 (배치 단계의 보관함). 같은 종류가 여러 번 나올 수 있습니다.
+    pub reserve: Vec<&'a str>,
+}
+
+/// 칸에 남은 표식 (`mark`). 함정·임시 벽·일반화된 앙파상 칸 등을 스크립트가 직접 표현합니다.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Marker<'a> {
+    pub name: &'a str,
+    pub position: Position,
+    /// 표식을 남긴 진영.
+    pub owner: Color,
+    /// 앞으로 남는 플라이 수. 차례가 끝날 때마다 줄고, 0인 채로 차례가 끝나면 사라집니다.
+    /// `None`이면 지워질 때까지 남습니다.
+    pub expires: Option<u8>,
+}
+
+impl<'a> BoardState<'a> {
+    /// 보관함에 있는 `piece`의 개수.
+    #[inline]
+    pub fn reserve_count(&self, piece: &str) -> usize {
+        self.reserve.iter().filter(|x| **x == piece).count()
+    }
 }
 
 #[derive(PartialEq, Eq, Clone, Debug)]
@@ -23,38 +50,123 @@ pub struct BothBoardState<'a> {
     pub white: BoardState<'a>,
 }
 
+/// `Board::make_move_in_place`가 바꾼 것을 되돌리는 기록. 스크립트 레지스터는 수를 둘 때
+/// 바뀌지 않으므로(`set-state`의 사용자 키는 응답으로만 나갑니다) 담지 않습니다.
+#[derive(Clone, Debug)]
+pub struct MoveUndo<'a> {
+    /// 바뀔 수 있는 칸과 두기 전의 내용 (기록한 순서).
+    squares: Vec<(Position, PieceSpan<'a>)>,
+    /// 백, 흑 순서.
+    sides: [SideUndo; 2],
+    /// 배치 수가 있을 때만 보관함을 담습니다.
+    reserves: Option<[Vec<&'a str>; 2]>,
+    turn: Color,
+    phase: u8,
+    moves_left: u8,
+    roll: Option<u8>,
+    markers: Vec<Marker<'a>>,
+    status: BoardStatus,
+    halfmove_clock: u16,
+    fullmove_number: u16,
+    history_len: usize,
+    /// 비가역 수로 비운 반복 기록.
+    cleared_history: Option<Vec<u64>>,
+    hash: u64,
+    /// 두기 전 국면의 이동 캐시.
+    dp: HashMap<Position, Vec<ChessMove<'a>>>,
+}
+
+impl<'a> MoveUndo<'a> {
+    /// 수를 둔 쪽.
+    #[inline]
+    pub fn mover(&self) -> Color {
+        self.turn
+    }
+}
+
+#[derive(Clone, Debug)]
+struct SideUndo {
+    castling_oo: bool,
+    castling_ooo: bool,
+    enpassant: Vec<Position>,
+    checks: u8,
+}
+
 #[derive(Clone, PartialEq, Eq, Debug)]
-pub struct Board<'a, const MACHO: bool, const IMPRISONED: bool, const SIZE: usize> {
+pub struct Board<'a, const SIZE: usize> {
+    /// `SIZE`×`SIZE` 저장 공간 중 실제로 쓰는 것은 왼쪽 위 `width`×`height` 영역입니다.
     pub board: [[PieceSpan<'a>; SIZE]; SIZE],
+    pub width: u8,
+    pub height: u8,
     pub board_state: BothBoardState<'a>,
     pub turn: Color,
+    /// 차례 안의 현재 단계 (`Rules::phases`의 인덱스).
+    pub phase: u8,
+    /// 이번 차례에 남은 수 (지금 둘 수 포함). 여러 수를 두는 변형에서 1보다 큽니다.
+    pub moves_left: u8,
+    /// 이번 차례에 굴린 주사위 면 (`Rules::dice`의 인덱스). `None`이면 아직 굴리지 않은 우연 노드입니다.
+    pub roll: Option<u8>,
+    /// 칸 표식 층.
+    pub markers: Vec<Marker<'a>>,
     pub script: &'a ChessemblyCompiled<'a>,
+    pub rules: &'a Rules<'a>,
     pub status: BoardStatus,
+    /// 마지막 캡처·비가역 수 이후의 플라이 수 (50수 규칙).
+    pub halfmove_clock: u16,
+    pub fullmove_number: u16,
+    /// 마지막 비가역 수 이후 국면 키 목록 (현재 국면 포함, 반복 판정용).
+    pub history: Vec<u64>,
+    /// 증분 Zobrist 해시. 보드를 직접 수정했다면 `zobrist()`로 다시 계산해야 합니다.
+    pub hash: u64,
+    /// 진영별 점유 칸 (`Color` 순서: 백, 흑, 중립). 보드를 직접 수정했다면 `rebuild_occupancy()`로 다시 계산해야 합니다.
+    pub occupancy: [SquareSet; 3],
+    /// 킹(로열 기물)이 있는 칸. 진영별 목록은 `occupancy`와 겹쳐서 얻습니다 (`kings_of`).
+    pub kings: SquareSet,
+    /// 폰이 있는 칸 (`pawns_of`).
+    pub pawns: SquareSet,
     pub dp: HashMap<Position, Vec<ChessMove<'a>>>,
 }
 
-impl<'a, const MACHO: bool, const IMPRISONED: bool, const SIZE: usize> Board<'a, MACHO, IMPRISONED, SIZE> {
-    pub fn from_str(placement: &str, script: &'a ChessemblyCompiled) -> Board<'a, MACHO, IMPRISONED, 8> {
+impl<'a, const SIZE: usize> Board<'a, SIZE> {
+    pub fn from_str(placement: &str, script: &'a ChessemblyCompiled) -> Board<'a, 8> {
         let mut ret = Board {
             dp: HashMap::new(),
             board: [[PieceSpan::Empty; 8]; 8],
+            width: 8,
+            height: 8,
             board_state: BothBoardState {
                 black: BoardState {
                     castling_oo: true,
                     castling_ooo: true,
                     enpassant: Vec::new(),
                     register: HashMap::new(),
+                    checks: 0,
+                    reserve: Vec::new(),
                 },
                 white: BoardState {
                     castling_oo: true,
                     castling_ooo: true,
                     enpassant: Vec::new(),
                     register: HashMap::new(),
+                    checks: 0,
+                    reserve: Vec::new(),
                 },
             },
             script: script,
+            rules: &STANDARD_RULES,
             turn: Color::White,
+            phase: 0,
+            moves_left: 1,
+            roll: None,
+            markers: Vec::new(),
             status: BoardStatus::Ongoing,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            history: Vec::new(),
+            hash: 0,
+            occupancy: [SquareSet::EMPTY; 3],
+            kings: SquareSet::EMPTY,
+            pawns: SquareSet::EMPTY,
         };
         for i in 0..8 {
             for j in 0..8 {
@@ -80,112 +192,161 @@ impl<'a, const MACHO: bool, const IMPRISONED: bool, const SIZE: usize> Board<'a,
                     _ => continue,
                 };
 
-                ret.board[i][j] = PieceSpan::Piece(Piece {
-                    piece_type: piece.0,
-                    color: piece.1,
-                });
+                ret.board[i][j] = PieceSpan::Piece(Piece::new(piece.0, piece.1, &script.pieces));
             }
         }
+        ret.rebuild_occupancy();
+        ret.hash = ret.zobrist();
+        ret.history.push(ret.hash);
         ret
     }
 
-    pub fn empty(script: &'a ChessemblyCompiled) -> Board<'a, MACHO, IMPRISONED, SIZE> {
+    pub fn empty(script: &'a ChessemblyCompiled) -> Board<'a, SIZE> {
         Board {
             dp: HashMap::new(),
             board: [[PieceSpan::Empty; SIZE]; SIZE],
+            width: SIZE as u8,
+            height: SIZE as u8,
             board_state: BothBoardState {
                 black: BoardState {
                     castling_oo: true,
                     castling_ooo: true,
                     enpassant: Vec::new(),
                     register: HashMap::new(),
+                    checks: 0,
+                    reserve: Vec::new(),
                 },
                 white: BoardState {
                     castling_oo: true,
                     castling_ooo: true,
                     enpassant: Vec::new(),
                     register: HashMap::new(),
+                    checks: 0,
+                    reserve: Vec::new(),
                 },
             },
             script,
+            rules: &STANDARD_RULES,
             turn: Color::White,
+            phase: 0,
+            moves_left: 1,
+            roll: None,
+            markers: Vec::new(),
             status: BoardStatus::Ongoing,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            history: Vec::new(),
+            hash: 0,
+            occupancy: [SquareSet::EMPTY; 3],
+            kings: SquareSet::EMPTY,
+            pawns: SquareSet::EMPTY,
         }
     }
 
-    pub fn new(script: &'a ChessemblyCompiled) -> Board<'a, MACHO, IMPRISONED, 8> {
-        Board {
+    /// `width`×`height` 크기의 빈 보드. 두 값 모두 `SIZE` 이하여야 합니다.
+    pub fn empty_sized(script: &'a ChessemblyCompiled, width: u8, height: u8) -> Board<'a, SIZE> {
+        assert!(width as usize <= SIZE && height as usize <= SIZE, "board {}x{} exceeds capacity {}", width, height, SIZE);
+        let mut ret = Self::empty(script);
+        ret.width = width;
+        ret.height = height;
+        ret
+    }
+
+    pub fn new(script: &'a ChessemblyCompiled) -> Board<'a, 8> {
+        let mut ret = Board {
             dp: HashMap::new(),
             board: [
                 [
-                    PieceSpan::Piece(Piece { color: Color::Black, piece_type: "rook" }),
-                    PieceSpan::Piece(Piece { color: Color::Black, piece_type: "knight" }),
-                    PieceSpan::Piece(Piece { color: Color::Black, piece_type: "bishop" }),
-                    PieceSpan::Piece(Piece { color: Color::Black, piece_type: "queen" }),
-                    PieceSpan::Piece(Piece { color: Color::Black, piece_type: "king" }),
-                    PieceSpan::Piece(Piece { color: Color::Black, piece_type: "bishop" }),
-                    PieceSpan::Piece(Piece { color: Color::Black, piece_type: "knight" }),
-                    PieceSpan::Piece(Piece { color: Color::Black, piece_type: "rook" }),
+                    PieceSpan::Piece(Piece { color: Color::Black, piece_type: "rook", id: ids::ROOK }),
+                    PieceSpan::Piece(Piece { color: Color::Black, piece_type: "knight", id: ids::KNIGHT }),
+                    PieceSpan::Piece(Piece { color: Color::Black, piece_type: "bishop", id: ids::BISHOP }),
+                    PieceSpan::Piece(Piece { color: Color::Black, piece_type: "queen", id: ids::QUEEN }),
+                    PieceSpan::Piece(Piece { color: Color::Black, piece_type: "king", id: ids::KING }),
+                    PieceSpan::Piece(Piece { color: Color::Black, piece_type: "bishop", id: ids::BISHOP }),
+                    PieceSpan::Piece(Piece { color: Color::Black, piece_type: "knight", id: ids::KNIGHT }),
+                    PieceSpan::Piece(Piece { color: Color::Black, piece_type: "rook", id: ids::ROOK }),
                 ],
                 [
-                    PieceSpan::Piece(Piece { color: Color::Black, piece_type: "pawn" }),
-                    PieceSpan::Piece(Piece { color: Color::Black, piece_type: "pawn" }),
-                    PieceSpan::Piece(Piece { color: Color::Black, piece_type: "pawn" }),
-                    PieceSpan::Piece(Piece { color: Color::Black, piece_type: "pawn" }),
-                    PieceSpan::Piece(Piece { color: Color::Black, piece_type: "pawn" }),
-                    PieceSpan::Piece(Piece { color: Color::Black, piece_type: "pawn" }),
-                    PieceSpan::Piece(Piece { color: Color::Black, piece_type: "pawn" }),
-                    PieceSpan::Piece(Piece { color: Color::Black, piece_type: "pawn" }),
+                    PieceSpan::Piece(Piece { color: Color::Black, piece_type: "pawn", id: ids::PAWN }),
+                    PieceSpan::Piece(Piece { color: Color::Black, piece_type: "pawn", id: ids::PAWN }),
+                    PieceSpan::Piece(Piece { color: Color::Black, piece_type: "pawn", id: ids::PAWN }),
+                    PieceSpan::Piece(Piece { color: Color::Black, piece_type: "pawn", id: ids::PAWN }),
+                    PieceSpan::Piece(Piece { color: Color::Black, piece_type: "pawn", id: ids::PAWN }),
+                    PieceSpan::Piece(Piece { color: Color::Black, piece_type: "pawn", id: ids::PAWN }),
+                    PieceSpan::Piece(Piece { color: Color::Black, piece_type: "pawn", id: ids::PAWN }),
+                    PieceSpan::Piece(Piece { color: Color::Black, piece_type: "pawn", id: ids::PAWN }),
                 ],
                 [PieceSpan::Empty, PieceSpan::Empty, PieceSpan::Empty, PieceSpan::Empty, PieceSpan::Empty, PieceSpan::Empty, PieceSpan::Empty, PieceSpan::Empty],
                 [PieceSpan::Empty, PieceSpan::Empty, PieceSpan::Empty, PieceSpan::Empty, PieceSpan::Empty, PieceSpan::Empty, PieceSpan::Empty, PieceSpan::Empty],
                 [PieceSpan::Empty, PieceSpan::Empty, PieceSpan::Empty, PieceSpan::Empty, PieceSpan::Empty, PieceSpan::Empty, PieceSpan::Empty, PieceSpan::Empty],
                 [PieceSpan::Empty, PieceSpan::Empty, PieceSpan::Empty, PieceSpan::Empty, PieceSpan::Empty, PieceSpan::Empty, PieceSpan::Empty, PieceSpan::Empty],
                 [
-                    PieceSpan::Piece(Piece { color: Color::White, piece_type: "pawn" }),
-                    PieceSpan::Piece(Piece { color: Color::White, piece_type: "pawn" }),
-                    PieceSpan::Piece(Piece { color: Color::White, piece_type: "pawn" }),
-                    PieceSpan::Piece(Piece { color: Color::White, piece_type: "pawn" }),
-                    PieceSpan::Piece(Piece { color: Color::White, piece_type: "pawn" }),
-                    PieceSpan::Piece(Piece { color: Color::White, piece_type: "pawn" }),
-                    PieceSpan::Piece(Piece { color: Color::White, piece_type: "pawn" }),
-                    PieceSpan::Piece(Piece { color: Color::White, piece_type: "pawn" }),
+                    PieceSpan::Piece(Piece { color: Color::White, piece_type: "pawn", id: ids::PAWN }),
+                    PieceSpan::Piece(Piece { color: Color::White, piece_type: "pawn", id: ids::PAWN }),
+                    PieceSpan::Piece(Piece { color: Color::White, piece_type: "pawn", id: ids::PAWN }),
+                    PieceSpan::Piece(Piece { color: Color::White, piece_type: "pawn", id: ids::PAWN }),
+                    PieceSpan::Piece(Piece { color: Color::White, piece_type: "pawn", id: ids::PAWN }),
+                    PieceSpan::Piece(Piece { color: Color::White, piece_type: "pawn", id: ids::PAWN }),
+                    PieceSpan::Piece(Piece { color: Color::White, piece_type: "pawn", id: ids::PAWN }),
+                    PieceSpan::Piece(Piece { color: Color::White, piece_type: "pawn", id: ids::PAWN }),
                 ],
                 [
-                    PieceSpan::Piece(Piece { color: Color::White, piece_type: "rook" }),
-                    PieceSpan::Piece(Piece { color: Color::White, piece_type: "knight" }),
-                    PieceSpan::Piece(Piece { color: Color::White, piece_type: "bishop" }),
-                    PieceSpan::Piece(Piece { color: Color::White, piece_type: "queen" }),
-                    PieceSpan::Piece(Piece { color: Color::White, piece_type: "king" }),
-                    PieceSpan::Piece(Piece { color: Color::White, piece_type: "bishop" }),
-                    PieceSpan::Piece(Piece { color: Color::White, piece_type: "knight" }),
-                    PieceSpan::Piece(Piece { color: Color::White, piece_type: "rook" }),
+                    PieceSpan::Piece(Piece { color: Color::White, piece_type: "rook", id: ids::ROOK }),
+                    PieceSpan::Piece(Piece { color: Color::White, piece_type: "knight", id: ids::KNIGHT }),
+                    PieceSpan::Piece(Piece { color: Color::White, piece_type: "bishop", id: ids::BISHOP }),
+                    PieceSpan::Piece(Piece { color: Color::White, piece_type: "queen", id: ids::QUEEN }),
+                    PieceSpan::Piece(Piece { color: Color::White, piece_type: "king", id: ids::KING }),
+                    PieceSpan::Piece(Piece { color: Color::White, piece_type: "bishop", id: ids::BISHOP }),
+                    PieceSpan::Piece(Piece { color: Color::White, piece_type: "knight", id: ids::KNIGHT }),
+                    PieceSpan::Piece(Piece { color: Color::White, piece_type: "rook", id: ids::ROOK }),
                 ],
             ],
+            width: 8,
+            height: 8,
             board_state: BothBoardState {
                 black: BoardState {
                     castling_oo: true,
                     castling_ooo: true,
                     enpassant: Vec::new(),
                     register: HashMap::new(),
+                    checks: 0,
+                    reserve: Vec::new(),
                 },
                 white: BoardState {
                     castling_oo: true,
                     castling_ooo: true,
                     enpassant: Vec::new(),
                     register: HashMap::new(),
+                    checks: 0,
+                    reserve: Vec::new(),
                 },
             },
             script,
+            rules: &STANDARD_RULES,
             turn: Color::White,
+            phase: 0,
+            moves_left: 1,
+            roll: None,
+            markers: Vec::new(),
             status: BoardStatus::Ongoing,
-        }
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            history: Vec::new(),
+            hash: 0,
+            occupancy: [SquareSet::EMPTY; 3],
+            kings: SquareSet::EMPTY,
+            pawns: SquareSet::EMPTY,
+        };
+        ret.rebuild_occupancy();
+        ret.hash = ret.zobrist();
+        ret.history.push(ret.hash);
+        ret
     }
 
     pub fn to_string(&self) -> String {
         let mut ret = String::new();
-        for j in 0..(SIZE as u8) {
-            for i in 0..(SIZE as u8) {
+        for j in 0..self.height {
+            for i in 0..self.width {
                 let Some(color) = self.color_on(&[i, j].into()) else {
                     ret.push(' ');
                     continue;
@@ -221,152 +382,671 @@ impl<'a, const MACHO: bool, const IMPRISONED: bool, const SIZE: usize> Board<'a,
     }
 
     #[inline]
-    pub fn clone_without_dp(&self) -> Board<'a, MACHO, IMPRISONED, SIZE> {
+    pub fn clone_without_dp(&self) -> Board<'a, SIZE> {
         Board {
             board: self.board.clone(),
+            width: self.width,
+            height: self.height,
             board_state: self.board_state.clone(),
             turn: self.turn,
+            phase: self.phase,
+            moves_left: self.moves_left,
+            roll: self.roll,
+            markers: self.markers.clone(),
             script: self.script,
+            rules: self.rules,
             status: self.status,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            history: self.history.clone(),
+            hash: self.hash,
+            occupancy: self.occupancy,
+            kings: self.kings,
+            pawns: self.pawns,
             dp: HashMap::new()
         }
     }
 
-    pub fn run_node_unit(ret: &mut Board<'a, MACHO, IMPRISONED, SIZE>, node: &ChessMoveUnit<'a>) {
+    /// 수 단위 하나를 보드에 적용합니다. 캡처·폰 이동·`irreversible` 선언이면 true를 반환합니다.
+    pub fn run_node_unit(ret: &mut Board<'a, SIZE>, node: &ChessMoveUnit<'a>) -> bool {
+        let mut irreversible = match node.move_type {
+            MoveType::Castling | MoveType::Shift => false,
+            MoveType::Place => true,
+            _ => {
+                (node.take != node.from && ret.color_on(&node.take).is_some())
+                    || ret.piece_id_on(&node.from) == Some(ids::PAWN)
+            }
+        };
+
         if node.move_type == MoveType::Castling {
-            ret.board[node.move_to.1 as usize].swap(node.move_to.0 as usize, node.from.0 as usize);
+            let y = node.move_to.1;
+            ret.swap_squares(&node.move_to, &node.from);
             if node.from.0 < node.move_to.0 { // O-O
-                ret.board[node.move_to.1 as usize].swap(7, 5);
+                ret.swap_squares(&(ret.width - 1, y), &(node.move_to.0 - 1, y));
             }
             else if node.from.0 > node.move_to.0 { // O-O-O
-                ret.board[node.move_to.1 as usize].swap(0, 3);
+                ret.swap_squares(&(0, y), &(node.move_to.0 + 1, y));
             }
         }
+        else if node.move_type == MoveType::Place {
+            let piece_type = node.transition.unwrap_or("pawn");
+            let color = ret.turn;
+            ret.take_from_reserve(color, piece_type);
+            ret.set_square(&node.move_to, PieceSpan::Piece(Piece::new(piece_type, color, &ret.script.pieces)));
+        }
         else if node.move_type == MoveType::Shift {
-            let shifter = node
-                .transition
-                .as_ref()
-                .map(|x| {
-                    PieceSpan::Piece(Piece {
-                        piece_type: x,
-                        color: match &ret.board[node.from.1 as usize][node.from.0 as usize] {
-                            PieceSpan::Empty => Color::White,
-                            PieceSpan::Piece(piece) => piece.color,
-                        },
-                    })
-                })
-                .unwrap_or(ret.board[node.from.1 as usize][node.from.0 as usize].clone());
-            ret.board[node.from.1 as usize][node.from.0 as usize] = ret.board[node.move_to.1 as usize][node.move_to.0 as usize].clone();
-            ret.board[node.move_to.1 as usize][node.move_to.0 as usize] = shifter;
+            let shifter = ret.transitioned(node);
+            ret.set_square(&node.from, ret.board[node.move_to.1 as usize][node.move_to.0 as usize]);
+            ret.set_square(&node.move_to, shifter);
         }
         else {
-            ret.board[node.take.1 as usize][node.take.0 as usize] = PieceSpan::Empty;
-            ret.board[node.move_to.1 as usize][node.move_to.0 as usize] = node
-                .transition
-                .as_ref()
-                .map(|x| {
-                    PieceSpan::Piece(Piece {
-                        piece_type: x,
-                        color: match &ret.board[node.from.1 as usize][node.from.0 as usize] {
-                            PieceSpan::Empty => Color::White,
-                            PieceSpan::Piece(piece) => piece.color,
-                        },
-                    })
-                })
-                .unwrap_or(ret.board[node.from.1 as usize][node.from.0 as usize].clone());
-            ret.board[node.from.1 as usize][node.from.0 as usize] = PieceSpan::Empty;
+            ret.set_square(&node.take, PieceSpan::Empty);
+            let mover = ret.transitioned(node);
+            ret.set_square(&node.move_to, mover);
+            ret.set_square(&node.from, PieceSpan::Empty);
+        }
+
+        if let Some(marks) = &node.marks {
+            ret.apply_marks(marks);
         }
 
         if let Some(state_changes) = &node.state_change {
             for (key, n) in state_changes {
                 if key == &"castling-oo" {
-                    if ret.turn == Color::White {
-                        ret.board_state.white.castling_oo = *n > 0;
-                    } else if ret.turn == Color::Black {
-                        ret.board_state.black.castling_oo = *n > 0;
-                    }
+                    ret.set_castling(ret.turn, false, *n > 0);
                 } else if key == &"castling-ooo" {
-                    if ret.turn == Color::White {
-                        ret.board_state.white.castling_ooo = *n > 0;
-                    } else if ret.turn == Color::Black {
-                        ret.board_state.black.castling_ooo = *n > 0;
-                    }
+                    ret.set_castling(ret.turn, true, *n > 0);
                 } else if key == &"en-passant" {
-                    if ret.turn == Color::White {
-                        ret.board_state.black.enpassant.push(node.move_to);
-                    } else if ret.turn == Color::Black {
-                        ret.board_state.white.enpassant.push(node.move_to);
-                    }
+                    let enemy = ret.turn.invert();
+                    ret.hash ^= zobrist::enpassant_key(enemy, &node.move_to);
+                    ret.state_mut(enemy).enpassant.push(node.move_to);
+                } else if key == &"irreversible" {
+                    irreversible = true;
+                }
+            }
+        }
+        irreversible
+    }
+
+    /// 한 칸의 Zobrist 키. 빈 칸이면 0.
+    #[inline]
+    fn square_key(&self, position: &Position) -> u64 {
+        match &self.board[position.1 as usize][position.0 as usize] {
+            PieceSpan::Piece(piece) => {
+                zobrist::piece_key(zobrist::piece_hash(piece.id, piece.piece_type), piece.color, position)
+            }
+            PieceSpan::Empty => 0,
+        }
+    }
+
+    /// 해시와 점유 칸·기물 목록을 갱신하며 칸의 내용을 바꿉니다.
+    #[inline]
+    pub fn set_square(&mut self, position: &Position, span: PieceSpan<'a>) {
+        self.hash ^= self.square_key(position);
+        if let PieceSpan::Piece(piece) = self.board[position.1 as usize][position.0 as usize] {
+            self.occupancy[piece.color as usize].remove(position);
+            self.kings.remove(position);
+            self.pawns.remove(position);
+        }
+        self.board[position.1 as usize][position.0 as usize] = span;
+        if let PieceSpan::Piece(piece) = span {
+            self.list_piece(position, &piece);
+        }
+        self.hash ^= self.square_key(position);
+    }
+
+    #[inline]
+    fn list_piece(&mut self, position: &Position, piece: &Piece) {
+        self.occupancy[piece.color as usize].insert(position);
+        match piece.id {
+            ids::KING => self.kings.insert(position),
+            ids::PAWN => self.pawns.insert(position),
+            _ => {}
+        }
+    }
+
+    /// `board`에서 점유 칸과 기물 목록을 처음부터 다시 계산합니다.
+    pub fn rebuild_occupancy(&mut self) {
+        self.occupancy = [SquareSet::EMPTY; 3];
+        self.kings = SquareSet::EMPTY;
+        self.pawns = SquareSet::EMPTY;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if let PieceSpan::Piece(piece) = self.board[y as usize][x as usize] {
+                    self.list_piece(&(x, y), &piece);
+                }
+            }
+        }
+    }
+
+    /// `color` 기물이 있는 칸.
+    #[inline]
+    pub fn pieces_of(&self, color: Color) -> SquareSet {
+        self.occupancy[color as usize]
+    }
+
+    /// `color`의 킹이 있는 칸.
+    #[inline]
+    pub fn kings_of(&self, color: Color) -> SquareSet {
+        self.kings & self.occupancy[color as usize]
+    }
+
+    /// `color`의 폰이 있는 칸.
+    #[inline]
+    pub fn pawns_of(&self, color: Color) -> SquareSet {
+        self.pawns & self.occupancy[color as usize]
+    }
+
+    /// 기물이 있는 모든 칸.
+    #[inline]
+    pub fn occupied(&self) -> SquareSet {
+        self.occupancy[0] | self.occupancy[1] | self.occupancy[2]
+    }
+
+    /// 보드 중앙의 칸 (8×8에서는 d4/e4/d5/e5). 변의 길이가 홀수면 그 축은 한 줄입니다.
+    pub fn center_squares(&self) -> SquareSet {
+        let (w, h) = (self.width, self.height);
+        let xs = if w % 2 == 0 { w / 2 - 1..=w / 2 } else { w / 2..=w / 2 };
+        let ys = if h % 2 == 0 { h / 2 - 1..=h / 2 } else { h / 2..=h / 2 };
+        ys.flat_map(|y| xs.clone().map(move |x| (x, y))).collect()
+    }
+
+    /// `color` 기물이 들어갈 수 없는 칸 (`is_blocked_for`의 집합판).
+    #[inline]
+    pub fn blockers_for(&self, color: Color) -> SquareSet {
+        match color {
+            Color::Neutral => self.occupied(),
+            _ if self.rules.neutral_capture => self.occupancy[color as usize],
+            _ => self.occupancy[color as usize] | self.occupancy[Color::Neutral as usize],
+        }
+    }
+
+    /// 평평한 정사각 보드인지. 비트보드 이동 생성은 이 경우에만 씁니다.
+    #[inline]
+    pub fn is_flat(&self) -> bool {
+        !self.rules.interpreted && self.rules.topology == Topology::Flat && self.rules.grid == Grid::Square
+    }
+
+    #[inline]
+    fn swap_squares(&mut self, a: &Position, b: &Position) {
+        let span_a = self.board[a.1 as usize][a.0 as usize];
+        let span_b = self.board[b.1 as usize][b.0 as usize];
+        self.set_square(a, span_b);
+        self.set_square(b, span_a);
+    }
+
+    /// `from`의 기물에 `transition`을 적용한 결과 (없으면 그대로).
+    #[inline]
+    fn transitioned(&self, node: &ChessMoveUnit<'a>) -> PieceSpan<'a> {
+        let span = self.board[node.from.1 as usize][node.from.0 as usize];
+        match node.transition {
+            Some(piece_type) => PieceSpan::Piece(Piece::new(
+                piece_type,
+                match span {
+                    PieceSpan::Empty => Color::White,
+                    PieceSpan::Piece(piece) => piece.color,
+                },
+                &self.script.pieces,
+            )),
+            None => span,
+        }
+    }
+
+    #[inline]
+    pub fn state(&self, color: Color) -> &BoardState<'a> {
+        if color == Color::White { &self.board_state.white } else { &self.board_state.black }
+    }
+
+    #[inline]
+    fn state_mut(&mut self, color: Color) -> &mut BoardState<'a> {
+        if color == Color::White { &mut self.board_state.white } else { &mut self.board_state.black }
+    }
+
+    fn set_castling(&mut self, color: Color, long: bool, value: bool) {
+        let state = self.state_mut(color);
+        let flag = if long { &mut state.castling_ooo } else { &mut state.castling_oo };
+        if *flag != value {
+            *flag = value;
+            self.hash ^= zobrist::castling_key(color, long);
+        }
+    }
+
+    /// 기물 배치와 `BothBoardState` 전체, 차례로부터 Zobrist 해시를 처음부터 계산합니다.
+    pub fn zobrist(&self) -> u64 {
+        let mut hash = 0;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                hash ^= self.square_key(&(x, y));
+            }
+        }
+        if self.turn == Color::Black {
+            hash ^= zobrist::TURN_KEY;
+        }
+        hash ^= zobrist::phase_key(self.phase);
+        hash ^= zobrist::moves_left_key(self.moves_left);
+        hash ^= zobrist::roll_key(self.roll);
+        for marker in &self.markers {
+            hash ^= zobrist::marker_key(marker.name, marker.owner, &marker.position, marker.expires);
+        }
+        for (color, state) in [(Color::White, &self.board_state.white), (Color::Black, &self.board_state.black)] {
+            if state.castling_oo {
+                hash ^= zobrist::castling_key(color, false);
+            }
+            if state.castling_ooo {
+                hash ^= zobrist::castling_key(color, true);
+            }
+            for position in &state.enpassant {
+                hash ^= zobrist::enpassant_key(color, position);
+            }
+            hash ^= zobrist::checks_key(color, state.checks);
+            let mut counted: Vec<&str> = Vec::new();
+            for name in &state.reserve {
+                if !counted.contains(name) {
+                    counted.push(name);
+                    hash ^= zobrist::reserve_key(color, name, state.reserve_count(name));
                 }
             }
+            for (name, value) in &state.register {
+                hash ^= zobrist::register_key(color, name, *value);
+            }
         }
+        hash
     }
 
-    pub fn make_move_new_nc(&self, node: &ChessMove<'a>, decide: bool) -> Board<'a, MACHO, IMPRISONED, SIZE> {
+    pub fn make_move_new_nc(&self, node: &ChessMove<'a>, decide: bool) -> Board<'a, SIZE> {
         let mut ret = self.clone_without_dp();
+        ret.play(node, decide);
+        ret
+    }
 
-        match node {
-            ChessMove::Single(node_unit) => Self::run_node_unit(&mut ret, node_unit),
+    /// 보드를 복사하지 않고 수를 둡니다. 돌려받은 기록을 `unmake_move`에 넘기면 두기 전 국면으로 돌아갑니다.
+    pub fn make_move_in_place(&mut self, node: &ChessMove<'a>, decide: bool) -> MoveUndo<'a> {
+        let mut squares = Vec::new();
+        let mut places = false;
+        for unit in node.units() {
+            self.record_squares(unit, &mut squares);
+            places |= unit.move_type == MoveType::Place;
+        }
+        let side = |state: &BoardState<'a>| SideUndo {
+            castling_oo: state.castling_oo,
+            castling_ooo: state.castling_ooo,
+            enpassant: state.enpassant.clone(),
+            checks: state.checks,
+        };
+        let mut undo = MoveUndo {
+            squares,
+            sides: [side(&self.board_state.white), side(&self.board_state.black)],
+            reserves: places.then(|| [self.board_state.white.reserve.clone(), self.board_state.black.reserve.clone()]),
+            turn: self.turn,
+            phase: self.phase,
+            moves_left: self.moves_left,
+            roll: self.roll,
+            markers: self.markers.clone(),
+            status: self.status,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            history_len: self.history.len(),
+            cleared_history: None,
+            hash: self.hash,
+            dp: std::mem::take(&mut self.dp),
+        };
+        undo.cleared_history = self.play(node, decide);
+        undo
+    }
+
+    /// `make_move_in_place`로 둔 수를 되돌립니다. 기록은 가장 최근에 둔 수의 것이어야 합니다.
+    pub fn unmake_move(&mut self, undo: MoveUndo<'a>) {
+        for (position, span) in undo.squares.into_iter().rev() {
+            self.set_square(&position, span);
+        }
+        for (color, side) in [Color::White, Color::Black].into_iter().zip(undo.sides) {
+            let state = self.state_mut(color);
+            state.castling_oo = side.castling_oo;
+            state.castling_ooo = side.castling_ooo;
+            state.enpassant = side.enpassant;
+            state.checks = side.checks;
+        }
+        if let Some([white, black]) = undo.reserves {
+            self.board_state.white.reserve = white;
+            self.board_state.black.reserve = black;
+        }
+        self.turn = undo.turn;
+        self.phase = undo.phase;
+        self.moves_left = undo.moves_left;
+        self.roll = undo.roll;
+        self.markers = undo.markers;
+        self.status = undo.status;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.fullmove_number = undo.fullmove_number;
+        match undo.cleared_history {
+            Some(history) => self.history = history,
+            None => self.history.truncate(undo.history_len),
+        }
+        self.hash = undo.hash;
+        self.dp = undo.dp;
+    }
+
+    /// 수 단위 하나가 내용을 바꿀 수 있는 칸과 지금의 내용을 `squares`에 더합니다.
+    fn record_squares(&self, node: &ChessMoveUnit<'a>, squares: &mut Vec<(Position, PieceSpan<'a>)>) {
+        let mut record = |position: Position| {
+            if position.0 < self.width && position.1 < self.height {
+                squares.push((position, self.board[position.1 as usize][position.0 as usize]));
+            }
+        };
+        record(node.from);
+        record(node.take);
+        record(node.move_to);
+        if node.move_type == MoveType::Castling {
+            let y = node.move_to.1;
+            if node.from.0 < node.move_to.0 {
+                record((self.width - 1, y));
+                record((node.move_to.0.wrapping_sub(1), y));
+            } else if node.from.0 > node.move_to.0 {
+                record((0, y));
+                record((node.move_to.0 + 1, y));
+            }
+        }
+    }
+
+    /// 수를 보드에 그대로 적용합니다. `decide`이면 차례·단계를 넘기고 종국 여부까지 판정합니다.
+    /// 비가역 수로 반복 기록을 비웠다면 비우기 전 기록을 돌려줍니다.
+    fn play(&mut self, node: &ChessMove<'a>, decide: bool) -> Option<Vec<u64>> {
+        let script = self.script;
+
+        let irreversible = match node {
+            ChessMove::Single(node_unit) => Self::run_node_unit(self, node_unit),
             ChessMove::Multiple(node_units) => {
+                let mut irreversible = false;
                 for node_unit in node_units {
-                    Self::run_node_unit(&mut ret, node_unit);
+                    irreversible |= Self::run_node_unit(self, node_unit);
                 }
+                irreversible
             }
-        }
+        };
         
         if !decide {
-            return ret;
+            return None;
         }
 
-        if ret.turn == Color::White {
-            ret.board_state.white.enpassant.clear();
-        } else if ret.turn == Color::Black {
-            ret.board_state.black.enpassant.clear();
+        let mover = self.turn;
+        let mut cleared_history = None;
+        if irreversible {
+            self.halfmove_clock = 0;
+            cleared_history = Some(std::mem::take(&mut self.history));
+        } else if self.current_phase() == Phase::Move {
+            self.halfmove_clock = self.halfmove_clock.saturating_add(1);
         }
 
-        ret.turn = ret.turn.invert();
+        if self.advance_phase() && self.finish_move(mover) {
+            self.end_turn();
+            // 배치 단계에서 상대의 보관함이 비었으면 상대는 차례를 넘기고 남은 쪽이 이어서 놓습니다.
+            if self.is_placing() && self.state(self.turn).reserve.is_empty() {
+                self.hash ^= zobrist::moves_left_key(self.moves_left);
+                self.moves_left = 0;
+                self.end_turn();
+            }
+
+            if self.rules.counts_checks() && script.is_check(self, mover) {
+                let checks = self.state_mut(mover).checks;
+                self.hash ^= zobrist::checks_key(mover, checks) ^ zobrist::checks_key(mover, checks + 1);
+                self.state_mut(mover).checks += 1;
+            }
+        }
+        self.history.push(self.hash);
+        // 배치 단계에서는 아직 기물이 다 놓이지 않았으므로 승리 조건을 보지 않습니다.
+        if !self.is_placing() {
+            if let Some(winner) = self.check_win_conditions(mover) {
+                self.status = BoardStatus::Victory(winner);
+                return cleared_history;
+            }
+        }
 
-        let turn = ret.side_to_move();
-        if MACHO {
-            if !MoveGen::has_any_moves(&mut ret, turn, true) {
-                ret.status = BoardStatus::Checkmate;
+        let turn = self.side_to_move();
+        if self.rules.macho {
+            if !MoveGen::has_any_moves(self, turn, true) {
+                self.status = BoardStatus::Checkmate;
             }
             else {
                 let mut found_king = false;
-                for i in 0..(SIZE as u8) {
-                    for j in 0..(SIZE as u8) {
-                        if ret.color_on(&(j, i)) == Some(turn) {
-                            if ret.piece_on(&(j, i)).unwrap() == "king" {
+                for i in 0..self.height {
+                    for j in 0..self.width {
+                        if self.color_on(&(j, i)) == Some(turn) {
+                            if self.piece_id_on(&(j, i)) == Some(ids::KING) {
                                 found_king = true;
                                 if turn == Color::White && i == 0 {
-                                    ret.status = BoardStatus::Checkmate;
+                                    self.status = BoardStatus::Checkmate;
                                 }
-                                else if turn == Color::Black && i == 7 {
-                                    ret.status = BoardStatus::Checkmate;
+                                else if turn == Color::Black && i == self.height - 1 {
+                                    self.status = BoardStatus::Checkmate;
                                 }
                             }
                         }
                     }
                 }
                 if !found_king {
-                    ret.status = BoardStatus::Checkmate;
+                    self.status = BoardStatus::Checkmate;
                 }
             }
         }
         else {
-            if !MoveGen::has_any_moves(&mut ret, turn, true) {
-                if self.script.is_check(&mut ret, turn.invert()) {
-                    ret.status = BoardStatus::Checkmate;
+            if !MoveGen::has_any_moves(self, turn, true) {
+                if self.rules.checkmate && script.is_check(self, turn.invert()) {
+                    self.status = BoardStatus::Checkmate;
                 } else {
-                    ret.status = BoardStatus::Stalemate;
+                    self.status = match self.rules.stalemate {
+                        StalemateRule::Draw => BoardStatus::Stalemate,
+                        StalemateRule::Win => BoardStatus::Victory(turn),
+                        StalemateRule::Loss => BoardStatus::Victory(turn.invert()),
+                    };
                 }
             }
         }
+
+        if self.status == BoardStatus::Ongoing && self.is_draw() {
+            self.status = BoardStatus::Draw;
+        }
+        cleared_history
+    }
+
+    /// 다음 단계로 넘어갑니다. 둘 수 있는 수가 없는 단계(예: 오리가 없음)는 건너뛰며,
+    /// 마지막 단계를 지나 차례가 끝나면 true를 반환합니다. 차례는 바꾸지 않습니다.
+    fn advance_phase(&mut self) -> bool {
+        let count = self.rules.phase_count();
+        loop {
+            self.hash ^= zobrist::phase_key(self.phase);
+            self.phase += 1;
+            if self.phase >= count {
+                self.phase = 0;
+                return true;
+            }
+            self.hash ^= zobrist::phase_key(self.phase);
+            let turn = self.turn;
+            if MoveGen::has_any_moves(self, turn, false) {
+                return false;
+            }
+        }
+    }
+
+    /// 한 수(모든 단계)를 마친 뒤 남은 수를 줄입니다. 남은 수가 없거나
+    /// `check_ends_turn` 규칙에서 `mover`가 체크를 주었으면 true (차례 종료, 남은 수 0).
+    fn finish_move(&mut self, mover: Color) -> bool {
+        self.hash ^= zobrist::moves_left_key(self.moves_left);
+        self.moves_left = self.moves_left.saturating_sub(1);
+        let script = self.script;
+        if self.moves_left == 0 || (self.rules.check_ends_turn && script.is_check(self, mover)) {
+            self.moves_left = 0;
+            return true;
+        }
+        self.hash ^= zobrist::moves_left_key(self.moves_left);
+        false
+    }
+
+    /// 차례를 넘깁니다. 끝난 쪽의 앙파상 칸을 지우고, 다음 차례의 남은 수를 정하며
+    /// 주사위는 다시 굴리기 전 상태로 돌립니다. `moves_left`는 이미 0이어야 합니다.
+    fn end_turn(&mut self) {
+        let mover = self.turn;
+        for position in std::mem::take(&mut self.state_mut(mover).enpassant) {
+            self.hash ^= zobrist::enpassant_key(mover, &position);
+        }
+
+        self.turn = self.turn.invert();
+        self.hash ^= zobrist::TURN_KEY;
+
+        if mover == Color::Black {
+            self.fullmove_number += 1;
+        }
+        self.moves_left = self.turn_moves();
+        self.hash ^= zobrist::moves_left_key(self.moves_left);
+
+        self.hash ^= zobrist::roll_key(self.roll);
+        self.roll = None;
+
+        self.tick_markers();
+    }
+
+    /// 차례가 끝날 때 표식의 남은 플라이 수를 줄이고, 다 된 표식을 지웁니다.
+    fn tick_markers(&mut self) {
+        for marker in self.markers.iter().filter(|m| m.expires == Some(0)) {
+            self.hash ^= zobrist::marker_key(marker.name, marker.owner, &marker.position, marker.expires);
+        }
+        self.markers.retain(|m| m.expires != Some(0));
+        for marker in &mut self.markers {
+            if let Some(expires) = marker.expires {
+                self.hash ^= zobrist::marker_key(marker.name, marker.owner, &marker.position, marker.expires);
+                marker.expires = Some(expires - 1);
+                self.hash ^= zobrist::marker_key(marker.name, marker.owner, &marker.position, marker.expires);
+            }
+        }
+    }
+
+    /// 수에 딸린 표식 변경을 적용합니다. 같은 칸의 같은 이름 표식은 새 표식으로 바뀝니다.
+    fn apply_marks(&mut self, marks: &[MarkChange<'a>]) {
+        for change in marks {
+            let (name, position) = (change.name, change.position);
+            for marker in self.markers.iter().filter(|m| m.name == name && m.position == position) {
+                self.hash ^= zobrist::marker_key(marker.name, marker.owner, &marker.position, marker.expires);
+            }
+            self.markers.retain(|m| m.name != name || m.position != position);
+            if !change.remove {
+                let marker = Marker { name, position, owner: self.turn, expires: change.plies };
+                self.hash ^= zobrist::marker_key(marker.name, marker.owner, &marker.position, marker.expires);
+                self.markers.push(marker);
+            }
+        }
+    }
+
+    /// 배치 단계인지: `placement` 규칙에서 어느 쪽이든 보관함에 기물이 남아 있는 동안.
+    #[inline]
+    pub fn is_placing(&self) -> bool {
+        self.rules.placement && !(self.board_state.white.reserve.is_empty() && self.board_state.black.reserve.is_empty())
+    }
+
+    /// `color`가 `piece`를 `position`에 놓을 수 있는지. 빈 칸이어야 하며 스크립트의
+    /// `setup-<기물>` 구역, 없으면 `setup` 구역, 둘 다 없으면 자기 쪽 절반이어야 합니다.
+    pub fn can_place(&self, color: Color, piece: &str, position: &Position) -> bool {
+        if !self.is_playable(position) || self.color_on(position).is_some() {
+            return false;
+        }
+        let zones = &self.script.zones;
+        let piece_zone = format!("setup-{}", piece);
+        if zones.is_declared(&piece_zone) {
+            zones.contains(&piece_zone, color, position)
+        } else if zones.is_declared("setup") {
+            zones.contains("setup", color, position)
+        } else if color == Color::White {
+            position.1 >= self.height.div_ceil(2)
+        } else {
+            position.1 < self.height / 2
+        }
+    }
+
+    /// 보관함에서 `piece` 하나를 꺼냅니다.
+    fn take_from_reserve(&mut self, color: Color, piece: &str) {
+        let count = self.state(color).reserve_count(piece);
+        let Some(index) = self.state(color).reserve.iter().position(|x| *x == piece) else {
+            return;
+        };
+        self.state_mut(color).reserve.remove(index);
+        self.hash ^= zobrist::reserve_key(color, piece, count) ^ zobrist::reserve_key(color, piece, count - 1);
+    }
+
+    /// `position`에 `name` 표식이 있는지. `owner`가 주어지면 그 진영이 남긴 표식만 셉니다.
+    pub fn is_marked(&self, position: &Position, name: &str, owner: Option<Color>) -> bool {
+        self.markers
+            .iter()
+            .any(|m| m.position == *position && m.name == name && owner.is_none_or(|owner| m.owner == owner))
+    }
+
+    /// 이번 차례에 굴린 주사위 면이 허락하는 기물 종류.
+    #[inline]
+    pub fn rolled_piece(&self) -> Option<&'a str> {
+        self.roll.and_then(|face| self.rules.dice.get(face as usize).copied())
+    }
+
+    /// 우연 노드(주사위를 굴려야 하는 국면)인지.
+    #[inline]
+    pub fn awaits_roll(&self) -> bool {
+        !self.rules.dice.is_empty() && self.roll.is_none() && self.status == BoardStatus::Ongoing
+    }
+
+    /// 주사위를 `face` 면으로 굴립니다. 그 면으로 움직일 기물이 없으면 차례를 그대로 넘깁니다.
+    pub fn roll_dice(&mut self, face: u8) {
+        self.hash ^= zobrist::roll_key(self.roll) ^ zobrist::roll_key(Some(face));
+        self.roll = Some(face);
+        self.dp.clear();
+
+        let turn = self.turn;
+        if !MoveGen::has_any_moves(self, turn, true) {
+            self.hash ^= zobrist::moves_left_key(self.moves_left);
+            self.moves_left = 0;
+            self.end_turn();
+            self.history.push(self.hash);
+        }
+    }
+
+    /// 굴릴 수 있는 서로 다른 면과 그 확률 (같은 종류가 여러 면이면 확률을 합칩니다).
+    pub fn roll_outcomes(&self) -> Vec<(u8, f32)> {
+        let faces = &self.rules.dice;
+        let chance = 1.0 / faces.len() as f32;
+        let mut ret: Vec<(u8, f32)> = Vec::new();
+        for (face, piece) in faces.iter().enumerate() {
+            match ret.iter_mut().find(|(x, _)| faces[*x as usize] == *piece) {
+                Some(outcome) => outcome.1 += chance,
+                None => ret.push((face as u8, chance)),
+            }
+        }
         ret
     }
 
+    /// 현재 차례(`turn`, `fullmove_number` 기준)에 두는 수의 개수.
+    pub fn turn_moves(&self) -> u8 {
+        let index = self.fullmove_number.saturating_sub(1) * 2 + (self.turn == Color::Black) as u16;
+        self.rules.turn_length.moves(index)
+    }
+
+    #[inline]
+    pub fn current_phase(&self) -> Phase {
+        self.rules.phase(self.phase)
+    }
+
+    /// 50수 규칙 또는 동형 반복에 의한 무승부인지 확인합니다.
+    pub fn is_draw(&self) -> bool {
+        if self.rules.move_rule > 0 && self.halfmove_clock >= self.rules.move_rule {
+            return true;
+        }
+        if self.rules.repetition > 0 {
+            if let Some(current) = self.history.last() {
+                let count = self.history.iter().filter(|&key| key == current).count();
+                if count >= self.rules.repetition as usize {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
     #[inline]
-    pub fn make_move_new(&self, node: &ChessMove<'a>) -> Board<'a, MACHO, IMPRISONED, SIZE> {
+    pub fn make_move_new(&self, node: &ChessMove<'a>) -> Board<'a, SIZE> {
         self.make_move_new_nc(node, true)
     }
 
@@ -376,20 +1056,33 @@ impl<'a, const MACHO: bool, const IMPRISONED: bool, const SIZE: usize> Board<'a,
     }
 
     #[inline]
-    pub const fn piece_on(&self, position: &Position) -> Option<&str> {
-        if position.0 > (SIZE as u8) - 1 || position.1 > (SIZE as u8) - 1 {
+    pub const fn piece_on(&self, position: &Position) -> Option<&'a str> {
+        if position.0 >= self.width || position.1 >= self.height {
             return None;
         } else if let PieceSpan::Piece(piece) =
             &self.board[position.1 as usize][position.0 as usize]
         {
-            return Some(&piece.piece_type);
+            return Some(piece.piece_type);
+        }
+        None
+    }
+
+    /// 칸에 있는 기물의 인턴 ID. 이름이 필요 없는 곳에서는 `piece_on` 대신 이것을 씁니다.
+    #[inline]
+    pub const fn piece_id_on(&self, position: &Position) -> Option<PieceId> {
+        if position.0 >= self.width || position.1 >= self.height {
+            return None;
+        } else if let PieceSpan::Piece(piece) =
+            &self.board[position.1 as usize][position.0 as usize]
+        {
+            return Some(piece.id);
         }
         None
     }
 
     #[inline]
     pub const fn color_on(&self, position: &Position) -> Option<Color> {
-        if position.0 > (SIZE as u8) - 1 || position.1 > (SIZE as u8) - 1 {
+        if position.0 >= self.width || position.1 >= self.height {
             return None;
         } else if let PieceSpan::Piece(piece) =
             &self.board[position.1 as usize][position.0 as usize]
@@ -404,13 +1097,265 @@ impl<'a, const MACHO: bool, const IMPRISONED: bool, const SIZE: usize> Board<'a,
         self.turn
     }
 
+    /// `color` 기물이 `position`의 기물을 잡을 수 있는지 확인합니다.
+    /// 중립 기물은 잡지 못하며, 중립 기물은 `neutral_capture` 규칙일 때만 잡힙니다.
+    pub fn is_capturable_by(&self, position: &Position, color: Color) -> bool {
+        match self.color_on(position) {
+            None => false,
+            _ if color == Color::Neutral => false,
+            Some(Color::Neutral) => self.rules.neutral_capture,
+            Some(target) => target != color,
+        }
+    }
+
+    /// `color` 기물이 `position`에 들어갈 수 없는지 (아군 또는 잡을 수 없는 기물).
+    #[inline]
+    pub fn is_blocked_for(&self, position: &Position, color: Color) -> bool {
+        self.color_on(position).is_some() && !self.is_capturable_by(position, color)
+    }
+
+    /// `turn` 진영이 `position`의 기물을 움직일 수 있는지. 중립 기물은 `neutral_move` 규칙일 때
+    /// 차례인 쪽만 움직입니다 (상대의 위협 계산에는 들어가지 않습니다).
+    /// 차례인 쪽은 현재 단계(`Phase`)에 맞는 기물만 움직입니다.
+    /// 주사위를 굴렸다면 차례인 쪽은 굴린 종류의 기물만 움직입니다.
+    pub fn is_movable_by(&self, position: &Position, turn: Color) -> bool {
+        let phase = if turn == self.turn { self.current_phase() } else { Phase::Move };
+        if turn == self.turn && phase == Phase::Move {
+            if let Some(piece) = self.rolled_piece() {
+                if self.piece_on(position) != Some(piece) {
+                    return false;
+                }
+            }
+        }
+        match (self.color_on(position), phase) {
+            (Some(Color::Neutral), Phase::Neutral) => true,
+            (Some(Color::Neutral), Phase::Move) => self.rules.neutral_move && turn == self.turn,
+            (color, Phase::Move) => color == Some(turn),
+            _ => false,
+        }
+    }
+
+    /// 보드 범위 안에 있고 구멍이 아닌 칸인지 확인합니다.
+    #[inline]
+    pub const fn is_playable(&self, position: &Position) -> bool {
+        position.0 < self.width && position.1 < self.height && !self.rules.holes.contains(position)
+    }
+
+    /// 위상(`Rules::topology`)에 따라 좌표를 감쌉니다. 감싸지 않는 축은 그대로 둡니다.
+    #[inline]
+    pub fn wrap(&self, x: i16, y: i16) -> (i16, i16) {
+        let topology = self.rules.topology;
+        (
+            if topology.wraps_x() { x.rem_euclid(self.width as i16) } else { x },
+            if topology.wraps_y() { y.rem_euclid(self.height as i16) } else { y },
+        )
+    }
+
     #[inline]
     pub const fn get_width(&self) -> usize {
-        8
+        self.width as usize
     }
 
     #[inline]
     pub const fn get_height(&self) -> usize {
-        8
+        self.height as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `from`에서 `to`로 가는 합법 수를 둡니다.
+    fn play<'a>(board: &Board<'a, 8>, from: Position, to: Position) -> Board<'a, 8> {
+        let mut board = board.clone();
+        let node = MoveGen::new_legal(&mut board)
+            .into_iter()
+            .find(|node| node.get_source() == from && node.get_dest() == to)
+            .expect("legal move");
+        board.make_move_new(&node)
+    }
+
+    #[test]
+    fn threefold_repetition_draws() {
+        let compiled = ChessemblyCompiled::new();
+        let mut board = Board::<8>::new(&compiled);
+        // Nf3 Nf6 Ng1 Ng8을 두 번 돌면 시작 국면이 세 번째로 나옵니다.
+        for round in 0..2 {
+            for (from, to) in [((6, 7), (5, 5)), ((6, 0), (5, 2)), ((5, 5), (6, 7)), ((5, 2), (6, 0))] {
+                assert_eq!(board.status, BoardStatus::Ongoing, "round {}", round);
+                board = play(&board, from, to);
+            }
+        }
+        assert_eq!(board.history.len(), 9);
+        assert_eq!(board.status, BoardStatus::Draw);
+
+        let rules = Rules::parse("repetition(0)");
+        let mut board = Board::<8>::new(&compiled);
+        board.rules = &rules;
+        for _ in 0..2 {
+            for (from, to) in [((6, 7), (5, 5)), ((6, 0), (5, 2)), ((5, 5), (6, 7)), ((5, 2), (6, 0))] {
+                board = play(&board, from, to);
+            }
+        }
+        assert_eq!(board.status, BoardStatus::Ongoing);
+    }
+
+    #[test]
+    fn fifty_move_rule_draws() {
+        let compiled = ChessemblyCompiled::new();
+        let mut board = Board::<8>::new(&compiled);
+        board.halfmove_clock = 99;
+        // 나이트 수는 시계를 100으로 올려 무승부가 됩니다.
+        let knight = play(&board, (6, 7), (5, 5));
+        assert_eq!(knight.halfmove_clock, 100);
+        assert_eq!(knight.status, BoardStatus::Draw);
+        // 폰 수는 시계를 되돌립니다.
+        let pawn = play(&board, (4, 6), (4, 4));
+        assert_eq!(pawn.halfmove_clock, 0);
+        assert_eq!(pawn.status, BoardStatus::Ongoing);
+    }
+
+    #[test]
+    fn halfmove_clock_resets_on_capture_and_pawn_move() {
+        let compiled = ChessemblyCompiled::new();
+        let mut board = Board::<8>::new(&compiled);
+        // 1. e4 Nf6 2. Nc3 Nxe4 3. Nxe4 Nc6 4. d4
+        let expected = [
+            (((4, 6), (4, 4)), 0),
+            (((6, 0), (5, 2)), 1),
+            (((1, 7), (2, 5)), 2),
+            (((5, 2), (4, 4)), 0),
+            (((2, 5), (4, 4)), 0),
+            (((1, 0), (2, 2)), 1),
+            (((3, 6), (3, 4)), 0),
+        ];
+        for ((from, to), clock) in expected {
+            board = play(&board, from, to);
+            assert_eq!(board.halfmove_clock, clock, "{:?} -> {:?}", from, to);
+            // 비가역 수 뒤에는 반복 기록도 새로 시작합니다.
+            assert_eq!(board.history.len(), clock as usize + 1);
+        }
+    }
+
+    /// 표식 스크립트 보드: 트래퍼는 떠나는 칸에 두 플라이짜리 `trap`을 남기고 옆으로 한 칸 가며,
+    /// 시커는 오른쪽 칸에 `trap`이 있을 때만 그 칸으로 갑니다.
+    fn marker_board<'a>(compiled: &'a ChessemblyCompiled<'a>, rules: &'a Rules<'a>, pieces: &[(&'a str, Position, Color)]) -> Board<'a, 8> {
+        let mut board = Board::<8>::empty(compiled);
+        board.rules = rules;
+        for &(piece, (x, y), color) in pieces {
+            board.board[y as usize][x as usize] = PieceSpan::Piece(Piece::new(piece, color, &compiled.pieces));
+        }
+        board.rebuild_occupancy();
+        board.hash = board.zobrist();
+        board.history = vec![board.hash];
+        board
+    }
+
+    const MARKER_SCRIPT: &str = "piece(trapper) mark(trap, 0, 0, 2) move(1, 0); piece(seeker) if-marked(trap, 1, 0) move(1, 0);";
+
+    #[test]
+    fn markers_expire_and_stay_in_hash() {
+        let compiled = ChessemblyCompiled::from_script(MARKER_SCRIPT).unwrap();
+        let rules = Rules::parse("non-royal-king");
+        let mut board = marker_board(&compiled, &rules, &[("trapper", (1, 6), Color::White), ("trapper", (6, 1), Color::Black)]);
+        let trap = |position, owner, expires| Marker { name: "trap", position, owner, expires };
+        let plies = [
+            (((1, 6), (2, 6)), vec![trap((1, 6), Color::White, Some(1))]),
+            (((6, 1), (5, 1)), vec![trap((1, 6), Color::White, Some(0)), trap((6, 1), Color::Black, Some(1))]),
+            // 백의 첫 표식은 두 플라이가 지나 사라집니다.
+            (((2, 6), (3, 6)), vec![trap((6, 1), Color::Black, Some(0)), trap((2, 6), Color::White, Some(1))]),
+        ];
+        for ((from, to), markers) in plies {
+            board = play(&board, from, to);
+            assert_eq!(board.markers, markers);
+            // 증분 해시가 표식을 놓고, 줄이고, 지우는 것을 모두 따라가야 합니다.
+            assert_eq!(board.hash, board.zobrist());
+            let mut bare = board.clone();
+            bare.markers.clear();
+            assert_ne!(bare.zobrist(), board.hash);
+        }
+    }
+
+    #[test]
+    fn if_marked_needs_a_live_marker() {
+        let compiled = ChessemblyCompiled::from_script(MARKER_SCRIPT).unwrap();
+        let rules = Rules::parse("non-royal-king");
+        let board = marker_board(
+            &compiled,
+            &rules,
+            &[("seeker", (2, 6), Color::White), ("trapper", (3, 6), Color::White), ("trapper", (6, 1), Color::Black)],
+        );
+        let seeker_moves = |board: &Board<'_, 8>| {
+            let mut board = board.clone();
+            MoveGen::new_legal(&mut board).iter().filter(|m| m.get_source() == (2, 6)).map(|m| m.get_dest()).collect::<Vec<_>>()
+        };
+        assert!(seeker_moves(&board).is_empty());
+
+        let board = play(&board, (3, 6), (4, 6));
+        let board = play(&board, (6, 1), (5, 1));
+        assert!(board.is_marked(&(3, 6), "trap", Some(Color::White)));
+        assert_eq!(seeker_moves(&board), vec![(3, 6)]);
+
+        // 한 차례를 더 보내면 표식이 사라져 다시 못 갑니다.
+        let board = play(&board, (4, 6), (5, 6));
+        let board = play(&board, (5, 1), (4, 1));
+        assert!(!board.is_marked(&(3, 6), "trap", None));
+        assert!(seeker_moves(&board).is_empty());
+    }
+
+    #[test]
+    fn dice_roll_filters_moves() {
+        let compiled = ChessemblyCompiled::new();
+        let rules = Rules::parse("dice-chess");
+        let mut board = Board::<8>::new(&compiled);
+        board.rules = &rules;
+        board.hash = board.zobrist();
+        assert!(board.awaits_roll());
+        // 면 순서: 폰, 나이트, 비숍, 룩, 퀸, 킹
+        for (face, piece, count) in [(0, ids::PAWN, 16), (1, ids::KNIGHT, 4)] {
+            let mut rolled = board.clone();
+            rolled.roll_dice(face);
+            assert!(!rolled.awaits_roll());
+            assert_eq!(rolled.turn, Color::White);
+            let moves = MoveGen::new_legal(&mut rolled);
+            assert_eq!(moves.len(), count);
+            assert!(moves.iter().all(|m| rolled.piece_id_on(&m.get_source()) == Some(piece)));
+        }
+    }
+
+    #[test]
+    fn dice_turn_passes_when_rolled_piece_cannot_move() {
+        let compiled = ChessemblyCompiled::new();
+        let rules = Rules::parse("dice-chess");
+        let mut board = Board::<8>::new(&compiled);
+        board.rules = &rules;
+        board.hash = board.zobrist();
+        // 시작 국면에서 비숍은 움직일 수 없습니다.
+        board.roll_dice(2);
+        assert_eq!(board.turn, Color::Black);
+        assert!(board.awaits_roll());
+        assert_eq!(board.status, BoardStatus::Ongoing);
+        assert_eq!(board.hash, board.zobrist());
+    }
+
+    #[test]
+    fn placement_ignores_win_conditions() {
+        let compiled = ChessemblyCompiled::new();
+        for spec in ["placement; extinction(king)", "placement; bare-king", "placement; lose-all"] {
+            let rules = Rules::parse(spec);
+            let mut board = Board::<8>::empty(&compiled);
+            board.rules = &rules;
+            board.board_state.white.reserve = vec!["rook", "king"];
+            board.board_state.black.reserve = vec!["rook", "king"];
+            board.hash = board.zobrist();
+            while board.is_placing() {
+                let moves = MoveGen::placement_moves(&board, board.turn, None);
+                board = board.make_move_new(&moves[0]);
+                assert_eq!(board.status, BoardStatus::Ongoing, "{}", spec);
+            }
+            assert_eq!(board.pieces_of(Color::White).len(), 2);
+            assert_eq!(board.pieces_of(Color::Black).len(), 2);
+        }
     }
 }
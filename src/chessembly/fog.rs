@@ -0,0 +1,63 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use super::board::Board;
+use super::{ids, ChessMove, Color, MoveGen, PieceSpan, Position, SquareSet};
+
+// -----------------------------------------------------------------------------
+// 전장의 안개 (Dark chess)
+// 각 진영은 자기 기물이 있는 칸, 자기 기물의 수가 닿는 칸, 잡을 수 있는 상대 기물만 봅니다.
+// -----------------------------------------------------------------------------
+
+impl<'a, const SIZE: usize> Board<'a, SIZE> {
+    /// `color` 진영이 볼 수 있는 칸.
+    pub fn visible_squares(&mut self, color: Color) -> SquareSet {
+        let mut ret = self.pieces_of(color);
+        for node in MoveGen::get_all_moves::<SIZE>(self, color, false) {
+            let units = match &node {
+                ChessMove::Single(unit) => std::slice::from_ref(unit),
+                ChessMove::Multiple(units) => units.as_slice(),
+            };
+            for unit in units {
+                ret.insert(&unit.move_to);
+                if self.is_capturable_by(&unit.take, color) {
+                    ret.insert(&unit.take);
+                }
+            }
+        }
+        ret
+    }
+
+    /// `color` 진영이 아는 정보만 남긴 표본 국면. 보이지 않는 상대 기물을 보이지 않는 빈 칸에
+    /// 무작위로 다시 배치합니다 (폰은 첫·마지막 랭크 제외). 기물 구성은 그대로 둡니다.
+    pub fn sample_hidden<R: Rng>(&self, color: Color, rng: &mut R) -> Board<'a, SIZE> {
+        let mut ret = self.clone_without_dp();
+        let visible = ret.visible_squares(color);
+        ret.dp.clear();
+
+        let enemy = color.invert();
+        let mut hidden = Vec::new();
+        for (x, y) in (ret.pieces_of(enemy) & !visible).iter() {
+            hidden.push(ret.board[y as usize][x as usize]);
+            ret.board[y as usize][x as usize] = PieceSpan::Empty;
+        }
+        let mut free: Vec<Position> = (0..ret.height)
+            .flat_map(|y| (0..ret.width).map(move |x| (x, y)))
+            .filter(|sq| ret.is_playable(sq) && ret.color_on(sq).is_none() && !visible.contains(sq))
+            .collect();
+        free.shuffle(rng);
+
+        for span in hidden {
+            let is_pawn = matches!(span, PieceSpan::Piece(piece) if piece.id == ids::PAWN);
+            let last = ret.height - 1;
+            let Some(index) = free.iter().position(|sq| !is_pawn || (sq.1 != 0 && sq.1 != last)) else {
+                continue;
+            };
+            let (x, y) = free.swap_remove(index);
+            ret.board[y as usize][x as usize] = span;
+        }
+        ret.rebuild_occupancy();
+        ret.hash = ret.zobrist();
+        ret
+    }
+}
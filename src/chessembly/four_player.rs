@@ -0,0 +1,502 @@
+use std::sync::LazyLock;
+
+use super::board::Board;
+use super::rules::Rules;
+use super::{ids, ChessMove, ChessMoveUnit, ChessemblyCompiled, Color, MoveGen, MoveType, Piece, PieceId, PieceSpan, Position, SquareSet};
+
+// -----------------------------------------------------------------------------
+// 4인 체스 (14×14 십자 보드, 네 귀퉁이 3×3은 구멍)
+// 빨강(아래) → 파랑(왼쪽) → 노랑(위) → 초록(오른쪽) 순서로 시계 방향으로 둡니다.
+// 각 플레이어의 수는 그 플레이어가 아래쪽 백이 되도록 회전한 시점 보드(`Board<16>`)에서
+// 생성하므로 내장 기물과 Chessembly 기물을 그대로 씁니다.
+// 캐슬링·앙파상·레지스터는 지원하지 않습니다.
+// -----------------------------------------------------------------------------
+
+pub const FOUR_PLAYER_SIZE: u8 = 14;
+const LAST: u8 = FOUR_PLAYER_SIZE - 1;
+/// 폰이 퀸으로 승격하는 랭크 (자기 진영 기준 0부터, 즉 8번째 랭크).
+const PROMOTION_RANK: u8 = 7;
+const BACK_RANK: [&str; 8] = ["rook", "knight", "bishop", "queen", "king", "bishop", "knight", "rook"];
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+pub enum Player {
+    Red,
+    Blue,
+    Yellow,
+    Green,
+}
+
+impl Player {
+    /// 차례 순서.
+    pub const ALL: [Player; 4] = [Player::Red, Player::Blue, Player::Yellow, Player::Green];
+
+    #[inline]
+    pub const fn next(self) -> Player {
+        Player::ALL[(self as usize + 1) % 4]
+    }
+
+    /// 팀 번호. 마주 보는 플레이어끼리 한 팀입니다 (빨강·노랑 = 0, 파랑·초록 = 1).
+    #[inline]
+    pub const fn team(self) -> u8 {
+        self as u8 % 2
+    }
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Player::Red => "red",
+            Player::Blue => "blue",
+            Player::Yellow => "yellow",
+            Player::Green => "green",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Player> {
+        Player::ALL.into_iter().find(|p| p.as_str() == name.trim())
+    }
+
+    /// 절대 좌표를 이 플레이어의 시점 좌표(자기 진영이 아래, 전진이 위)로 회전합니다.
+    #[inline]
+    pub const fn to_view(self, (x, y): Position) -> Position {
+        match self {
+            Player::Red => (x, y),
+            Player::Blue => (y, LAST - x),
+            Player::Yellow => (LAST - x, LAST - y),
+            Player::Green => (LAST - y, x),
+        }
+    }
+
+    /// `to_view`의 역변환.
+    #[inline]
+    pub const fn from_view(self, (x, y): Position) -> Position {
+        match self {
+            Player::Red => (x, y),
+            Player::Blue => (LAST - y, x),
+            Player::Yellow => (LAST - x, LAST - y),
+            Player::Green => (y, LAST - x),
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum FourPlayerMode {
+    /// 2대2. 한 명이라도 체크메이트되면 상대 팀이 승리하고, 스테일메이트는 무승부입니다.
+    Teams,
+    /// 개인전. 체크메이트·스테일메이트·킹을 잃은 플레이어는 탈락하고 마지막 한 명이 승리합니다.
+    FreeForAll,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum FourPlayerStatus {
+    Ongoing,
+    TeamWins(u8),
+    PlayerWins(Player),
+    Draw,
+}
+
+/// 십자 보드 바깥의 네 귀퉁이.
+pub const fn cross_holes() -> SquareSet {
+    let mut ret = SquareSet::EMPTY;
+    let mut y = 0;
+    while y < FOUR_PLAYER_SIZE {
+        let mut x = 0;
+        while x < FOUR_PLAYER_SIZE {
+            if (x < 3 || x > LAST - 3) && (y < 3 || y > LAST - 3) {
+                ret.insert(&(x, y));
+            }
+            x += 1;
+        }
+        y += 1;
+    }
+    ret
+}
+
+const fn view_rules() -> Rules<'static> {
+    let mut ret = Rules::standard();
+    ret.holes = cross_holes();
+    ret
+}
+
+/// 시점 보드에 쓰는 규칙 (십자 보드 구멍).
+static VIEW_RULES: Rules<'static> = view_rules();
+
+/// 서버의 `Position` 헤더 형식으로 쓴 4인 체스 시작 배치.
+pub static FOUR_PLAYER_POSITION: LazyLock<String> = LazyLock::new(|| {
+    let placement = four_player_placement();
+    (0..FOUR_PLAYER_SIZE)
+        .map(|y| {
+            (0..FOUR_PLAYER_SIZE)
+                .map(|x| match placement.iter().find(|(_, position, _)| *position == (x, y)) {
+                    Some((piece, _, player)) => format!("{}:{}", piece, player.as_str()),
+                    None if VIEW_RULES.holes.contains(&(x, y)) => "#".to_string(),
+                    None => ".".to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+});
+
+/// 시작 배치 (기물, 절대 좌표, 플레이어). 모두 빨강의 배치를 회전한 것입니다.
+pub fn four_player_placement() -> Vec<(&'static str, Position, Player)> {
+    let mut ret = Vec::new();
+    for player in Player::ALL {
+        for (i, piece) in BACK_RANK.into_iter().enumerate() {
+            ret.push((piece, player.from_view((3 + i as u8, LAST)), player));
+            ret.push(("pawn", player.from_view((3 + i as u8, LAST - 1)), player));
+        }
+    }
+    ret
+}
+
+/// 칸마다 (기물 이름, 기물 ID, 주인).
+pub type FourPlayerSquare<'a> = Option<(&'a str, PieceId, Player)>;
+
+#[derive(Clone, Debug)]
+pub struct FourPlayerBoard<'a> {
+    pub board: [[FourPlayerSquare<'a>; FOUR_PLAYER_SIZE as usize]; FOUR_PLAYER_SIZE as usize],
+    pub turn: Player,
+    pub mode: FourPlayerMode,
+    /// 탈락한 플레이어 (`Player as usize` 인덱스). 탈락한 기물은 보드에 남아 장애물이 됩니다.
+    pub eliminated: [bool; 4],
+    pub status: FourPlayerStatus,
+    pub script: &'a ChessemblyCompiled<'a>,
+}
+
+impl<'a> FourPlayerBoard<'a> {
+    pub fn empty(script: &'a ChessemblyCompiled<'a>, mode: FourPlayerMode) -> FourPlayerBoard<'a> {
+        FourPlayerBoard {
+            board: [[None; FOUR_PLAYER_SIZE as usize]; FOUR_PLAYER_SIZE as usize],
+            turn: Player::Red,
+            mode,
+            eliminated: [false; 4],
+            status: FourPlayerStatus::Ongoing,
+            script,
+        }
+    }
+
+    pub fn new(script: &'a ChessemblyCompiled<'a>, mode: FourPlayerMode) -> FourPlayerBoard<'a> {
+        let mut ret = Self::empty(script, mode);
+        for (piece, position, player) in four_player_placement() {
+            ret.board[position.1 as usize][position.0 as usize] = Some((piece, script.pieces.id_of(piece), player));
+        }
+        ret
+    }
+
+    /// `piece:player` 토큰을 공백으로, 행을 '/'로 구분한 배치 문자열로 보드를 만듭니다.
+    pub fn from_position(script: &'a ChessemblyCompiled<'a>, position: &'a str, mode: FourPlayerMode) -> FourPlayerBoard<'a> {
+        let mut ret = Self::empty(script, mode);
+        for (y, line) in position.split('/').take(FOUR_PLAYER_SIZE as usize).enumerate() {
+            for (x, token) in line.split_whitespace().take(FOUR_PLAYER_SIZE as usize).enumerate() {
+                let Some((piece, player)) = token.split_once(':') else {
+                    continue;
+                };
+                if let Some(player) = Player::parse(player) {
+                    if !VIEW_RULES.holes.contains(&(x as u8, y as u8)) {
+                        ret.board[y][x] = Some((piece, script.pieces.id_of(piece), player));
+                    }
+                }
+            }
+        }
+        ret
+    }
+
+    pub fn to_position(&self) -> String {
+        (0..FOUR_PLAYER_SIZE)
+            .map(|y| {
+                (0..FOUR_PLAYER_SIZE)
+                    .map(|x| match self.board[y as usize][x as usize] {
+                        Some((piece, _, player)) => format!("{}:{}", piece, player.as_str()),
+                        None if VIEW_RULES.holes.contains(&(x, y)) => "#".to_string(),
+                        None => ".".to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    #[inline]
+    pub fn piece_on(&self, position: &Position) -> Option<(&'a str, Player)> {
+        self.board[position.1 as usize][position.0 as usize].map(|(piece, _, player)| (piece, player))
+    }
+
+    #[inline]
+    pub fn piece_id_on(&self, position: &Position) -> Option<(PieceId, Player)> {
+        self.board[position.1 as usize][position.0 as usize].map(|(_, id, player)| (id, player))
+    }
+
+    #[inline]
+    pub const fn is_active(&self, player: Player) -> bool {
+        !self.eliminated[player as usize]
+    }
+
+    /// 서로 잡을 수 있는 사이인지 (개인전은 다른 플레이어 모두, 팀전은 상대 팀).
+    #[inline]
+    pub fn is_enemy(&self, a: Player, b: Player) -> bool {
+        a != b && (self.mode == FourPlayerMode::FreeForAll || a.team() != b.team())
+    }
+
+    pub fn king_of(&self, player: Player) -> Option<Position> {
+        (0..FOUR_PLAYER_SIZE)
+            .flat_map(|y| (0..FOUR_PLAYER_SIZE).map(move |x| (x, y)))
+            .find(|position| self.piece_id_on(position) == Some((ids::KING, player)))
+    }
+
+    /// `player` 시점 보드: 자기 기물은 백, 살아 있는 적은 흑, 팀원과 탈락한 기물은 중립(장애물)입니다.
+    pub fn view(&self, player: Player) -> Board<'a, 16> {
+        let mut ret = Board::empty_sized(self.script, FOUR_PLAYER_SIZE, FOUR_PLAYER_SIZE);
+        ret.rules = &VIEW_RULES;
+        for state in [&mut ret.board_state.white, &mut ret.board_state.black] {
+            state.castling_oo = false;
+            state.castling_ooo = false;
+        }
+        for y in 0..FOUR_PLAYER_SIZE {
+            for x in 0..FOUR_PLAYER_SIZE {
+                let Some((piece_type, id, owner)) = self.board[y as usize][x as usize] else {
+                    continue;
+                };
+                let color = if owner == player {
+                    Color::White
+                } else if self.is_active(owner) && self.is_enemy(player, owner) {
+                    Color::Black
+                } else {
+                    Color::Neutral
+                };
+                let (vx, vy) = player.to_view((x, y));
+                ret.set_square(&(vx, vy), PieceSpan::Piece(Piece { piece_type, id, color }));
+            }
+        }
+        ret
+    }
+
+    /// `player`의 기물이 공격하는 칸 (절대 좌표).
+    pub fn attacks(&self, player: Player) -> SquareSet {
+        let mut view = self.view(player);
+        let mut ret = SquareSet::EMPTY;
+        for position in MoveGen::get_danger_zones_bit::<16>(&mut view, Color::White).iter() {
+            ret.insert(&player.from_view(position));
+        }
+        ret
+    }
+
+    /// 살아 있는 적 중 누군가 `player`의 킹을 공격하는지. 킹이 없으면 false.
+    pub fn is_in_check(&self, player: Player) -> bool {
+        let Some(king) = self.king_of(player) else {
+            return false;
+        };
+        Player::ALL
+            .into_iter()
+            .any(|other| self.is_active(other) && self.is_enemy(player, other) && self.attacks(other).contains(&king))
+    }
+
+    /// 킹 안전을 따지지 않은 `player`의 수 (절대 좌표).
+    pub fn pseudo_moves(&self, player: Player) -> Vec<ChessMove<'a>> {
+        let mut view = self.view(player);
+        let script = self.script;
+        let mut ret = Vec::new();
+        for vy in 0..FOUR_PLAYER_SIZE {
+            for vx in 0..FOUR_PLAYER_SIZE {
+                if view.color_on(&(vx, vy)) != Some(Color::White) {
+                    continue;
+                }
+                let is_pawn = view.piece_id_on(&(vx, vy)) == Some(ids::PAWN);
+                for node in script.get_moves::<16>(&mut view, &(vx, vy), false) {
+                    let node = match node {
+                        ChessMove::Single(unit) => ChessMove::Single(Self::unit_from_view(player, unit, is_pawn)),
+                        ChessMove::Multiple(units) => ChessMove::Multiple(
+                            units.into_iter().map(|unit| Self::unit_from_view(player, unit, is_pawn)).collect(),
+                        ),
+                    };
+                    if !ret.contains(&node) {
+                        ret.push(node);
+                    }
+                }
+            }
+        }
+        ret
+    }
+
+    /// 시점 좌표의 수 단위를 절대 좌표로 돌립니다. 폰이 승격 랭크에 닿으면 퀸으로 승격합니다.
+    fn unit_from_view(player: Player, unit: ChessMoveUnit<'a>, is_pawn: bool) -> ChessMoveUnit<'a> {
+        let transition = if is_pawn && LAST - unit.move_to.1 >= PROMOTION_RANK { Some("queen") } else { unit.transition };
+        ChessMoveUnit {
+            from: player.from_view(unit.from),
+            take: player.from_view(unit.take),
+            move_to: player.from_view(unit.move_to),
+            move_type: unit.move_type,
+            state_change: None,
+            transition,
+            marks: None,
+        }
+    }
+
+    /// 두고 나서 자기 킹이 공격받지 않는 `player`의 수.
+    pub fn legal_moves(&self, player: Player) -> Vec<ChessMove<'a>> {
+        self.pseudo_moves(player)
+            .into_iter()
+            .filter(|node| !self.apply(node).is_in_check(player))
+            .collect()
+    }
+
+    fn has_legal_move(&self, player: Player) -> bool {
+        self.pseudo_moves(player).iter().any(|node| !self.apply(node).is_in_check(player))
+    }
+
+    /// 보드에 수를 적용만 합니다 (차례·탈락 판정 없음).
+    pub fn apply(&self, node: &ChessMove<'a>) -> FourPlayerBoard<'a> {
+        let mut ret = self.clone();
+        match node {
+            ChessMove::Single(unit) => ret.run_unit(unit),
+            ChessMove::Multiple(units) => {
+                for unit in units {
+                    ret.run_unit(unit);
+                }
+            }
+        }
+        ret
+    }
+
+    fn run_unit(&mut self, unit: &ChessMoveUnit<'a>) {
+        let (fx, fy) = (unit.from.0 as usize, unit.from.1 as usize);
+        let mover = self.board[fy][fx].map(|(piece, id, player)| match unit.transition {
+            Some(transition) => (transition, self.script.pieces.id_of(transition), player),
+            None => (piece, id, player),
+        });
+        if unit.move_type == MoveType::Shift {
+            self.board[fy][fx] = self.board[unit.move_to.1 as usize][unit.move_to.0 as usize];
+        } else {
+            self.board[unit.take.1 as usize][unit.take.0 as usize] = None;
+            self.board[fy][fx] = None;
+        }
+        self.board[unit.move_to.1 as usize][unit.move_to.0 as usize] = mover;
+    }
+
+    /// 수를 두고 다음 플레이어로 차례를 넘깁니다. 차례를 받을 플레이어가 킹을 잃었거나
+    /// 둘 수가 없으면 모드에 따라 탈락시키거나 승부를 냅니다.
+    pub fn make_move(&self, node: &ChessMove<'a>) -> FourPlayerBoard<'a> {
+        let mut ret = self.apply(node);
+        let mut next = ret.turn;
+        loop {
+            next = next.next();
+            if ret.eliminated[next as usize] {
+                continue;
+            }
+            let has_king = ret.king_of(next).is_some();
+            if has_king && ret.has_legal_move(next) {
+                break;
+            }
+            match ret.mode {
+                FourPlayerMode::Teams => {
+                    ret.turn = next;
+                    ret.status = if has_king && !ret.is_in_check(next) {
+                        FourPlayerStatus::Draw
+                    } else {
+                        FourPlayerStatus::TeamWins(1 - next.team())
+                    };
+                    return ret;
+                }
+                FourPlayerMode::FreeForAll => {
+                    ret.eliminated[next as usize] = true;
+                    let mut alive = Player::ALL.into_iter().filter(|p| ret.is_active(*p));
+                    match (alive.next(), alive.next()) {
+                        (Some(winner), None) => {
+                            ret.turn = winner;
+                            ret.status = FourPlayerStatus::PlayerWins(winner);
+                            return ret;
+                        }
+                        (None, _) => {
+                            ret.status = FourPlayerStatus::Draw;
+                            return ret;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        ret.turn = next;
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 파랑 킹(0,7)이 빨강 룩들에 갇혀 움직일 수 없지만 체크는 아닌 국면.
+    /// 빨강의 (5,4) 룩은 (0,4)로 가면 파랑을 체크메이트합니다.
+    fn blue_boxed_in<'a>(script: &'a ChessemblyCompiled<'a>, mode: FourPlayerMode) -> FourPlayerBoard<'a> {
+        let mut board = FourPlayerBoard::empty(script, mode);
+        let pieces = [
+            ("king", (7, 13), Player::Red),
+            ("king", (0, 7), Player::Blue),
+            ("king", (6, 0), Player::Yellow),
+            ("king", (13, 10), Player::Green),
+            ("rook", (1, 3), Player::Red),
+            ("rook", (12, 6), Player::Red),
+            ("rook", (12, 8), Player::Red),
+            ("rook", (5, 4), Player::Red),
+        ];
+        for (piece, (x, y), player) in pieces {
+            board.board[y][x] = Some((piece, script.pieces.id_of(piece), player));
+        }
+        board
+    }
+
+    /// 차례인 플레이어가 `from`에서 `to`로 가는 합법 수를 둡니다.
+    fn play<'a>(board: &FourPlayerBoard<'a>, from: Position, to: Position) -> FourPlayerBoard<'a> {
+        let node = board
+            .legal_moves(board.turn)
+            .into_iter()
+            .find(|node| node.get_source() == from && node.get_dest() == to)
+            .expect("legal move");
+        board.make_move(&node)
+    }
+
+    #[test]
+    fn free_for_all_eliminates_player_without_moves() {
+        let compiled = ChessemblyCompiled::new();
+        let board = blue_boxed_in(&compiled, FourPlayerMode::FreeForAll);
+        let board = play(&board, (7, 13), (7, 12));
+        assert!(board.eliminated[Player::Blue as usize]);
+        assert_eq!(board.turn, Player::Yellow);
+        assert_eq!(board.status, FourPlayerStatus::Ongoing);
+        // 탈락한 기물은 보드에 남습니다.
+        assert_eq!(board.piece_on(&(0, 7)), Some(("king", Player::Blue)));
+    }
+
+    #[test]
+    fn free_for_all_skips_eliminated_players() {
+        let compiled = ChessemblyCompiled::new();
+        let mut board = blue_boxed_in(&compiled, FourPlayerMode::FreeForAll);
+        let turns = [
+            ((7, 13), (7, 12), Player::Yellow),
+            ((6, 0), (6, 1), Player::Green),
+            ((13, 10), (13, 9), Player::Red),
+            ((7, 12), (7, 13), Player::Yellow),
+        ];
+        for (from, to, next) in turns {
+            board = play(&board, from, to);
+            assert_eq!(board.turn, next);
+        }
+        assert_eq!(board.eliminated, [false, true, false, false]);
+    }
+
+    #[test]
+    fn teams_checkmate_wins_for_other_team() {
+        let compiled = ChessemblyCompiled::new();
+        let board = blue_boxed_in(&compiled, FourPlayerMode::Teams);
+        let board = play(&board, (5, 4), (0, 4));
+        assert_eq!(board.status, FourPlayerStatus::TeamWins(Player::Red.team()));
+        assert!(!board.eliminated.contains(&true));
+    }
+
+    #[test]
+    fn teams_stalemate_draws() {
+        let compiled = ChessemblyCompiled::new();
+        let board = blue_boxed_in(&compiled, FourPlayerMode::Teams);
+        let board = play(&board, (7, 13), (7, 12));
+        assert_eq!(board.status, FourPlayerStatus::Draw);
+    }
+}
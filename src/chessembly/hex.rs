@@ -0,0 +1,356 @@
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use super::board::Board;
+use super::rules::{Grid, HEX_RULES};
+use super::{
+    ids, ChessMove, ChessMoveUnit, ChessemblyCompiled, Color, DeltaPosition, MoveGen, MoveType, Piece, PieceId, PieceSpan, Position,
+    SquareSet, WallCollision,
+};
+
+// -----------------------------------------------------------------------------
+// 육각 보드 (Glinski 규칙)
+// 저장 좌표 (x, y) = 축 좌표 (q + R, r + R). 델타 (dx, dy)는 축 좌표 (dx, -dy)이며
+// (0, 1)이 백 기준 전진(북쪽)입니다. 흑은 Behavior::reflect_turn과 같이 점대칭으로 뒤집습니다.
+// -----------------------------------------------------------------------------
+
+/// 변을 공유하는 여섯 방향 (룩).
+pub const HEX_ORTHOGONAL: [DeltaPosition; 6] = [(0, 1), (1, 1), (1, 0), (0, -1), (-1, -1), (-1, 0)];
+/// 꼭짓점을 공유하는 여섯 방향 (비숍).
+pub const HEX_DIAGONAL: [DeltaPosition; 6] = [(1, 2), (2, 1), (1, -1), (-1, -2), (-2, -1), (-1, 1)];
+/// 나이트의 열두 도약.
+pub const HEX_KNIGHT: [DeltaPosition; 12] = [
+    (1, 3), (2, 3), (3, 2), (3, 1), (2, -1), (1, -2),
+    (-1, -3), (-2, -3), (-3, -2), (-3, -1), (-2, 1), (-1, 2),
+];
+
+const PAWN_FORWARD: DeltaPosition = (0, 1);
+/// 폰이 잡는 방향: 전진 방향 양옆으로 변을 공유하는 두 칸.
+const PAWN_CAPTURES: [DeltaPosition; 2] = [(-1, 0), (1, 1)];
+
+/// Glinski 백 기물의 축 좌표 (q, r). 흑은 세로 대칭 (q, -q - r)입니다.
+const GLINSKI_PIECES: [(&str, i8, i8); 9] = [
+    ("queen", -1, 5),
+    ("king", 1, 4),
+    ("rook", -3, 5),
+    ("rook", 3, 2),
+    ("knight", -2, 5),
+    ("knight", 2, 3),
+    ("bishop", 0, 5),
+    ("bishop", 0, 4),
+    ("bishop", 0, 3),
+];
+
+/// 백 기준 축 좌표로 바꿉니다. 흑은 세로 대칭을 적용합니다.
+#[inline]
+fn white_axial(position: &Position, radius: u8, color: Color) -> (i8, i8) {
+    let (q, r) = (position.0 as i8 - radius as i8, position.1 as i8 - radius as i8);
+    if color != Color::Black { (q, r) } else { (q, -q - r) }
+}
+
+#[inline]
+fn from_white_axial((q, r): (i8, i8), radius: u8, color: Color) -> Position {
+    let r = if color != Color::Black { r } else { -q - r };
+    ((q + radius as i8) as u8, (r + radius as i8) as u8)
+}
+
+/// 폰의 시작 칸(두 칸 전진이 가능한 칸): 가운데 파일에서 양쪽으로 내려가는 쐐기 모양.
+pub fn is_hex_pawn_start(position: &Position, radius: u8, color: Color) -> bool {
+    let (q, r) = white_axial(position, radius, color);
+    q.unsigned_abs() < radius && r == 1 - q.min(0)
+}
+
+/// 반지름 5 육각 보드의 Glinski 시작 배치 (기물, 저장 좌표, 색).
+pub fn glinski_placement() -> Vec<(&'static str, Position, Color)> {
+    let mut ret = Vec::new();
+    for color in [Color::White, Color::Black] {
+        for (piece, q, r) in GLINSKI_PIECES {
+            ret.push((piece, from_white_axial((q, r), 5, color), color));
+        }
+        for q in -4..=4 {
+            ret.push(("pawn", from_white_axial((q, 1 - q.min(0)), 5, color), color));
+        }
+    }
+    ret
+}
+
+/// 서버의 `Position` 헤더 형식으로 쓴 Glinski 시작 배치.
+pub static GLINSKI_POSITION: LazyLock<String> = LazyLock::new(|| {
+    let placement: HashMap<Position, (&str, Color)> =
+        glinski_placement().into_iter().map(|(piece, position, color)| (position, (piece, color))).collect();
+    (0..11u8)
+        .map(|y| {
+            (0..11u8)
+                .map(|x| match placement.get(&(x, y)) {
+                    Some((piece, color)) => format!("{}:{}", piece, color.as_str()),
+                    None if HEX_RULES.holes.contains(&(x, y)) => "#".to_string(),
+                    None => ".".to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+});
+
+impl<'a> Board<'a, 16> {
+    /// Glinski 육각 체스 시작 보드.
+    pub fn glinski(script: &'a ChessemblyCompiled) -> Board<'a, 16> {
+        let mut ret = Board::empty_sized(script, 11, 11);
+        ret.rules = &HEX_RULES;
+        // 육각 체스에는 캐슬링이 없습니다.
+        for state in [&mut ret.board_state.white, &mut ret.board_state.black] {
+            state.castling_oo = false;
+            state.castling_ooo = false;
+        }
+        for (piece_type, position, color) in glinski_placement() {
+            ret.board[position.1 as usize][position.0 as usize] = PieceSpan::Piece(Piece::new(piece_type, color, &script.pieces));
+        }
+        ret.rebuild_occupancy();
+        ret.hash = ret.zobrist();
+        ret.history.push(ret.hash);
+        ret
+    }
+}
+
+impl<'a> ChessemblyCompiled<'a> {
+    /// 육각 보드에서 내장 기물의 수를 생성합니다. 육각 규칙이 없는 기물이면 `None`.
+    pub fn generate_hex_moves<const SIZE: usize>(
+        &self,
+        board: &mut Board<'a, SIZE>,
+        position: &Position,
+        piece: PieceId,
+        check_danger: bool,
+    ) -> Option<Vec<ChessMove<'a>>> {
+        let mut ret = Vec::new();
+        match piece {
+            ids::PAWN => return Some(self.generate_hex_pawn_moves(board, position)),
+            ids::KING => {
+                let danger_zones = if check_danger && board.rules.royal_king {
+                    MoveGen::get_danger_zones_bit::<SIZE>(board, board.color_on(position).unwrap().invert())
+                } else {
+                    SquareSet::EMPTY
+                };
+                return Some(self.generate_hex_king_moves(board, position, danger_zones));
+            }
+            ids::ROOK => {
+                for delta in HEX_ORTHOGONAL {
+                    self.generate_ij_abs_take_move_slide(&mut ret, board, position, &delta);
+                }
+            }
+            ids::BISHOP => {
+                for delta in HEX_DIAGONAL {
+                    self.generate_ij_abs_take_move_slide(&mut ret, board, position, &delta);
+                }
+            }
+            ids::QUEEN => {
+                for delta in HEX_ORTHOGONAL.iter().chain(HEX_DIAGONAL.iter()) {
+                    self.generate_ij_abs_take_move_slide(&mut ret, board, position, delta);
+                }
+            }
+            ids::KNIGHT => {
+                for delta in HEX_KNIGHT {
+                    self.generate_ij_abs_take_move(&mut ret, board, position, &delta);
+                }
+            }
+            _ => return None,
+        }
+        Some(ret)
+    }
+
+    pub fn generate_hex_king_moves<const SIZE: usize>(
+        &self,
+        board: &mut Board<'a, SIZE>,
+        position: &Position,
+        danger_zones: SquareSet,
+    ) -> Vec<ChessMove<'a>> {
+        let mut ret = Vec::new();
+        if board.rules.imprisoned {
+            return ret;
+        }
+        let color = board.color_on(position).unwrap();
+        for delta in HEX_ORTHOGONAL.iter().chain(HEX_DIAGONAL.iter()) {
+            let mut anchor = *position;
+            if ChessemblyCompiled::move_anchor(&mut anchor, delta, board, color) != WallCollision::NoCollision {
+                continue;
+            }
+            if !board.is_blocked_for(&anchor, color) && !danger_zones.contains(&anchor) {
+                ret.push(ChessMove::Single(ChessMoveUnit {
+                    from: *position,
+                    take: anchor,
+                    move_to: anchor,
+                    move_type: MoveType::TakeMove,
+                    state_change: None,
+                    transition: None,
+                    marks: None,
+                }));
+            }
+        }
+        ret
+    }
+
+    /// Glinski 폰: 한 칸 전진, 시작 칸에서 두 칸 전진, 전진 방향 양옆 칸으로 캡처 (앙파상 포함).
+    /// 파일의 끝 칸에 도착하면 승격합니다.
+    pub fn generate_hex_pawn_moves<const SIZE: usize>(
+        &self,
+        board: &mut Board<'a, SIZE>,
+        position: &Position,
+    ) -> Vec<ChessMove<'a>> {
+        let mut ret = Vec::new();
+        let Grid::Hex(radius) = board.rules.grid else {
+            return ret;
+        };
+        let color = board.color_on(position).unwrap();
+        if color == Color::Neutral {
+            return ret;
+        }
+        let step = |from: &Position, delta: DeltaPosition| {
+            let delta = if color != Color::Black { delta } else { (-delta.0, -delta.1) };
+            let mut anchor = *from;
+            (ChessemblyCompiled::move_anchor(&mut anchor, &delta, board, color) == WallCollision::NoCollision)
+                .then_some(anchor)
+        };
+        let mut push = |move_to: Position, take: Position, move_type: MoveType, state_change: Option<Vec<(&'a str, u8)>>| {
+            if step(&move_to, PAWN_FORWARD).is_some() {
+                ret.push(ChessMove::Single(ChessMoveUnit {
+                    from: *position,
+                    take,
+                    move_to,
+                    move_type,
+                    state_change,
+                    transition: None,
+                    marks: None,
+                }));
+                return;
+            }
+            for transition in ["knight", "bishop", "rook", "queen"] {
+                ret.push(ChessMove::Single(ChessMoveUnit {
+                    from: *position,
+                    take,
+                    move_to,
+                    move_type,
+                    state_change: None,
+                    transition: Some(transition),
+                    marks: None,
+                }));
+            }
+        };
+
+        if let Some(step1) = step(position, PAWN_FORWARD).filter(|x| board.color_on(x).is_none()) {
+            push(step1, step1, MoveType::Move, None);
+            if is_hex_pawn_start(position, radius, color) {
+                if let Some(step2) = step(&step1, PAWN_FORWARD).filter(|x| board.color_on(x).is_none()) {
+                    push(step2, step2, MoveType::Move, Some(vec![("en-passant", 1)]));
+                }
+            }
+        }
+
+        let enpassant = match color {
+            Color::White => &board.board_state.white.enpassant,
+            _ => &board.board_state.black.enpassant,
+        };
+        for delta in PAWN_CAPTURES {
+            let Some(target) = step(position, delta) else {
+                continue;
+            };
+            match board.color_on(&target) {
+                Some(_) if board.is_capturable_by(&target, color) => push(target, target, MoveType::Take, None),
+                None => {
+                    // 두 칸 전진한 상대 폰은 지나친 칸의 한 칸 뒤에 있습니다.
+                    let Some(passed) = step(&target, (0, -1)) else {
+                        continue;
+                    };
+                    if enpassant.contains(&passed) && board.piece_id_on(&passed) == Some(ids::PAWN) {
+                        push(target, passed, MoveType::TakeJump, None);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 킹 둘을 귀퉁이에 두고 `pieces`를 놓은 Glinski 규칙 보드.
+    fn hex_board<'a>(compiled: &'a ChessemblyCompiled<'a>, pieces: &[(&'a str, Position, Color)], turn: Color) -> Board<'a, 16> {
+        let mut board = Board::empty_sized(compiled, 11, 11);
+        board.rules = &HEX_RULES;
+        board.turn = turn;
+        for state in [&mut board.board_state.white, &mut board.board_state.black] {
+            state.castling_oo = false;
+            state.castling_ooo = false;
+        }
+        let kings = [("king", (0, 10), Color::White), ("king", (10, 0), Color::Black)];
+        for &(piece, (x, y), color) in kings.iter().chain(pieces) {
+            board.board[y as usize][x as usize] = PieceSpan::Piece(Piece::new(piece, color, &compiled.pieces));
+        }
+        board.rebuild_occupancy();
+        board.hash = board.zobrist();
+        board.history = vec![board.hash];
+        board
+    }
+
+    fn moves_from<'a>(board: &mut Board<'a, 16>, from: Position) -> Vec<ChessMove<'a>> {
+        MoveGen::new_legal(board).into_iter().filter(|m| m.get_source() == from).collect()
+    }
+
+    #[test]
+    fn pawn_start_squares() {
+        let pawns: Vec<_> = glinski_placement().into_iter().filter(|(piece, _, _)| *piece == "pawn").collect();
+        assert_eq!(pawns.len(), 18);
+        for (_, position, color) in pawns {
+            assert!(is_hex_pawn_start(&position, 5, color), "{:?} {:?}", position, color);
+        }
+        // 한 칸 나간 칸은 시작 칸이 아닙니다 (백 e4 → e5, 흑 e7 → e6).
+        assert!(!is_hex_pawn_start(&from_white_axial((-1, 1), 5, Color::White), 5, Color::White));
+        assert!(!is_hex_pawn_start(&from_white_axial((-1, 1), 5, Color::Black), 5, Color::Black));
+
+        // 시작 국면의 e4 폰은 한 칸과 두 칸 전진이 모두 됩니다.
+        let compiled = ChessemblyCompiled::new();
+        let mut board = Board::glinski(&compiled);
+        assert_eq!(moves_from(&mut board, from_white_axial((-1, 2), 5, Color::White)).len(), 2);
+    }
+
+    #[test]
+    fn pawn_promotes_at_end_of_file() {
+        let compiled = ChessemblyCompiled::new();
+        for (color, from, to) in [(Color::White, (5, 1), (5, 0)), (Color::Black, (5, 9), (5, 10))] {
+            let mut board = hex_board(&compiled, &[("pawn", from, color)], color);
+            let moves = moves_from(&mut board, from);
+            let mut promotions: Vec<_> = moves.iter().map(|m| (m.get_dest(), *m.get_promotion())).collect();
+            promotions.sort();
+            let expected: Vec<_> = ["bishop", "knight", "queen", "rook"].into_iter().map(|p| (to, Some(p))).collect();
+            assert_eq!(promotions, expected, "{:?}", color);
+        }
+    }
+
+    #[test]
+    fn en_passant_for_both_colors() {
+        let compiled = ChessemblyCompiled::new();
+        // (두 칸 전진하는 폰의 색, 출발, 도착, 지나친 칸, 잡는 폰)
+        for (color, from, to, passed, capturer) in [
+            (Color::White, (5, 6), (5, 4), (5, 5), (4, 5)),
+            (Color::Black, (5, 4), (5, 6), (5, 5), (6, 5)),
+        ] {
+            let mut board = hex_board(&compiled, &[("pawn", from, color), ("pawn", capturer, color.invert())], color);
+            let double = moves_from(&mut board, from).into_iter().find(|m| m.get_dest() == to).expect("double step");
+            let mut board = board.make_move_new(&double);
+            assert_eq!(board.state(color.invert()).enpassant, vec![to]);
+
+            let capture = moves_from(&mut board, capturer)
+                .into_iter()
+                .find(|m| m.get_dest() == passed)
+                .expect("en passant");
+            let ChessMove::Single(unit) = &capture else { panic!("{:?}", capture) };
+            assert_eq!((unit.move_type, unit.take), (MoveType::TakeJump, to));
+            let board = board.make_move_new(&capture);
+            assert_eq!(board.color_on(&to), None);
+            assert_eq!(board.color_on(&passed), Some(color.invert()));
+        }
+    }
+}
@@ -2,32 +2,43 @@ use std::cmp::Ordering;
 
 use super::ChessemblyCompiled;
 use crate::chessembly::{
-    Behavior, ChessMove, Color, DeltaPosition, MoveType, Position, WallCollision, board::Board, ChessMoveUnit
+    Behavior, ChessMove, Color, DeltaPosition, MoveType, Position, WallCollision, board::Board, ChessMoveUnit, SquareSet, rules::Topology, attacks, attacks::NativeAttack, ids, PieceId
 };
 
 impl<'a> ChessemblyCompiled<'a> {
-    pub fn generate_pawn_moves<const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(
+    pub fn generate_pawn_moves<const SIZE: usize>(
         &self,
-        board: &mut Board<'a, MACHO, IMPRISONED, SIZE>,
+        board: &mut Board<'a, SIZE>,
         position: &Position,
     ) -> Vec<ChessMove<'a>> {
         let mut ret = Vec::new();
         let color = board.color_on(position).unwrap();
-        let rank = if color == Color::White { (SIZE as u8) - 2 } else { 1 };
+        // 중립 폰은 전진 방향이 없으므로 움직이지 않습니다.
+        if color == Color::Neutral {
+            return ret;
+        }
+        let macho = board.rules.macho;
+        let height = board.get_height() as u8;
+        let rank = if color == Color::White { height - 2 } else { 1 };
         let step1 = if color == Color::White {
             position.1 - 1
         } else {
             position.1 + 1
         };
-        let promotion = if color == Color::White { 1 } else { (SIZE as u8) - 2 };
-        let wall = if color == Color::White { 0 } else { (SIZE as u8) - 1 };
+        let promotion = if color == Color::White { 1 } else { height - 2 };
+        let wall = if color == Color::White { 0 } else { height - 1 };
+        // 좌우가 이어진 보드에서는 가장자리 파일의 폰도 반대편 파일로 대각선 이동합니다.
+        let width = board.get_width() as u8;
+        let wraps = board.rules.topology.wraps_x() && width > 1;
+        let left = if position.0 > 0 { Some(position.0 - 1) } else if wraps { Some(width - 1) } else { None };
+        let right = if position.0 + 1 < width { Some(position.0 + 1) } else if wraps { Some(0) } else { None };
         
         if position.1 == wall {
             return ret;
         }
 
-        if board.color_on(&(position.0, step1)) == None {
-            if (position.1 == promotion) && !MACHO {
+        if board.is_playable(&(position.0, step1)) && board.color_on(&(position.0, step1)) == None {
+            if (position.1 == promotion) && !macho {
                 ret.push(ChessMove::Single(ChessMoveUnit {
                     from: *position,
                     take: (position.0, step1),
@@ -35,6 +46,7 @@ impl<'a> ChessemblyCompiled<'a> {
                     move_type: MoveType::Move,
                     state_change: None,
                     transition: Some("knight"),
+                    marks: None,
                 }));
                 ret.push(ChessMove::Single(ChessMoveUnit {
                     from: *position,
@@ -43,6 +55,7 @@ impl<'a> ChessemblyCompiled<'a> {
                     move_type: MoveType::Move,
                     state_change: None,
                     transition: Some("bishop"),
+                    marks: None,
                 }));
                 ret.push(ChessMove::Single(ChessMoveUnit {
                     from: *position,
@@ -51,6 +64,7 @@ impl<'a> ChessemblyCompiled<'a> {
                     move_type: MoveType::Move,
                     state_change: None,
                     transition: Some("rook"),
+                    marks: None,
                 }));
                 ret.push(ChessMove::Single(ChessMoveUnit {
                     from: *position,
@@ -59,6 +73,7 @@ impl<'a> ChessemblyCompiled<'a> {
                     move_type: MoveType::Move,
                     state_change: None,
                     transition: Some("queen"),
+                    marks: None,
                 }));
             } else {
                 ret.push(ChessMove::Single(ChessMoveUnit {
@@ -68,153 +83,168 @@ impl<'a> ChessemblyCompiled<'a> {
                     move_type: MoveType::Move,
                     state_change: None,
                     transition: None,
+                    marks: None,
                 }));
             }
             if position.1 == rank {
-                let step2 = if color == Color::White { 4 } else { 3 };
-                if board.color_on(&(position.0, step2)) == None {
+                let step2 = if color == Color::White { position.1 - 2 } else { position.1 + 2 };
+                if board.is_playable(&(position.0, step2)) && board.color_on(&(position.0, step2)) == None {
                     ret.push(ChessMove::Single(ChessMoveUnit {
                         from: *position,
                         take: (position.0, step2),
                         move_to: (position.0, step2),
                         move_type: MoveType::Move,
-                        state_change: Some(vec![("enpassant", 1 as u8)]),
+                        state_change: Some(vec![("en-passant", 1 as u8)]),
                         transition: None,
+                        marks: None,
                     }));
                 }
             }
         }
 
+        // 상대 폰이 두 칸 전진해 도착하는 랭크
         if position.1 == match color {
             Color::White => 3,
-            Color::Black => 4
+            _ => height - 4
         } {
             let board_state = match color {
                 Color::White => &board.board_state.white,
-                Color::Black => &board.board_state.black
+                _ => &board.board_state.black
             };
-            if position.0 > 0 {
-                if board_state.enpassant.contains(&(position.0 - 1, position.1)) {
-                    if MACHO {
+            if let Some(left) = left {
+                if board_state.enpassant.contains(&(left, position.1)) {
+                    if macho {
                         ret.clear();
                     }
                     ret.push(ChessMove::Single(ChessMoveUnit {
                         from: *position,
-                        move_to: (position.0 - 1, step1),
-                        take: (position.0 - 1, position.1),
+                        move_to: (left, step1),
+                        take: (left, position.1),
                         move_type: MoveType::TakeJump,
                         state_change: None,
-                        transition: None
+                        transition: None,
+                        marks: None,
                     }));
                 }
             }
-            if position.0 < 7 {
-                if board_state.enpassant.contains(&(position.0 + 1, position.1)) {
-                    if MACHO {
+            if let Some(right) = right {
+                if board_state.enpassant.contains(&(right, position.1)) {
+                    if macho {
                         ret.clear();
                     }
                     ret.push(ChessMove::Single(ChessMoveUnit {
                         from: *position,
-                        move_to: (position.0 + 1, step1),
-                        take: (position.0 + 1, position.1),
+                        move_to: (right, step1),
+                        take: (right, position.1),
                         move_type: MoveType::TakeJump,
                         state_change: None,
-                        transition: None
+                        transition: None,
+                        marks: None,
                     }));
                 }
             }
         }
 
-        if position.0 > 0 {
-            if board.color_on(&(position.0 - 1, step1)) == Some(color.invert()) {
-                if position.1 == promotion && !MACHO {
+        if let Some(left) = left {
+            if board.is_capturable_by(&(left, step1), color) {
+                if position.1 == promotion && !macho {
                     ret.push(ChessMove::Single(ChessMoveUnit {
                         from: *position,
-                        take: (position.0 - 1, step1),
-                        move_to: (position.0 - 1, step1),
+                        take: (left, step1),
+                        move_to: (left, step1),
                         move_type: MoveType::Take,
                         state_change: None,
                         transition: Some("knight"),
+                        marks: None,
                     }));
                     ret.push(ChessMove::Single(ChessMoveUnit {
                         from: *position,
-                        take: (position.0 - 1, step1),
-                        move_to: (position.0 - 1, step1),
+                        take: (left, step1),
+                        move_to: (left, step1),
                         move_type: MoveType::Take,
                         state_change: None,
                         transition: Some("bishop"),
+                        marks: None,
                     }));
                     ret.push(ChessMove::Single(ChessMoveUnit {
                         from: *position,
-                        take: (position.0 - 1, step1),
-                        move_to: (position.0 - 1, step1),
+                        take: (left, step1),
+                        move_to: (left, step1),
                         move_type: MoveType::Take,
                         state_change: None,
                         transition: Some("rook"),
+                        marks: None,
                     }));
                     ret.push(ChessMove::Single(ChessMoveUnit {
                         from: *position,
-                        take: (position.0 - 1, step1),
-                        move_to: (position.0 - 1, step1),
+                        take: (left, step1),
+                        move_to: (left, step1),
                         move_type: MoveType::Take,
                         state_change: None,
                         transition: Some("queen"),
+                        marks: None,
                     }));
                 } else {
                     ret.push(ChessMove::Single(ChessMoveUnit {
                         from: *position,
-                        take: (position.0 - 1, step1),
-                        move_to: (position.0 - 1, step1),
+                        take: (left, step1),
+                        move_to: (left, step1),
                         move_type: MoveType::Take,
                         state_change: None,
                         transition: None,
+                        marks: None,
                     }));
                 }
             }
         }
-        if position.0 < board.get_width() as u8 - 1 {
-            if board.color_on(&(position.0 + 1, step1)) == Some(color.invert()) {
-                if position.1 == promotion && !MACHO {            
+        if let Some(right) = right {
+            if board.is_capturable_by(&(right, step1), color) {
+                if position.1 == promotion && !macho {            
                     ret.push(ChessMove::Single(ChessMoveUnit {
                         from: *position,
-                        take: (position.0 + 1, step1),
-                        move_to: (position.0 + 1, step1),
+                        take: (right, step1),
+                        move_to: (right, step1),
                         move_type: MoveType::Take,
                         state_change: None,
                         transition: Some("knight"),
+                        marks: None,
                     }));
                     ret.push(ChessMove::Single(ChessMoveUnit {
                         from: *position,
-                        take: (position.0 + 1, step1),
-                        move_to: (position.0 + 1, step1),
+                        take: (right, step1),
+                        move_to: (right, step1),
                         move_type: MoveType::Take,
                         state_change: None,
                         transition: Some("bishop"),
+                        marks: None,
                     }));
                     ret.push(ChessMove::Single(ChessMoveUnit {
                         from: *position,
-                        take: (position.0 + 1, step1),
-                        move_to: (position.0 + 1, step1),
+                        take: (right, step1),
+                        move_to: (right, step1),
                         move_type: MoveType::Take,
                         state_change: None,
                         transition: Some("rook"),
+                        marks: None,
                     }));
                     ret.push(ChessMove::Single(ChessMoveUnit {
                         from: *position,
-                        take: (position.0 + 1, step1),
-                        move_to: (position.0 + 1, step1),
+                        take: (right, step1),
+                        move_to: (right, step1),
                         move_type: MoveType::Take,
                         state_change: None,
                         transition: Some("queen"),
+                        marks: None,
                     }));
                 } else {
                     ret.push(ChessMove::Single(ChessMoveUnit {
                         from: *position,
-                        take: (position.0 + 1, step1),
-                        move_to: (position.0 + 1, step1),
+                        take: (right, step1),
+                        move_to: (right, step1),
                         move_type: MoveType::Take,
                         state_change: None,
                         transition: None,
+                        marks: None,
                     }));
                 }
             }
@@ -223,15 +253,16 @@ impl<'a> ChessemblyCompiled<'a> {
         ret
     }
 
-    pub fn generate_king_moves<const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(
+    pub fn generate_king_moves<const SIZE: usize>(
         &self,
-        board: &mut Board<'a, MACHO, IMPRISONED, SIZE>,
+        board: &mut Board<'a, SIZE>,
         position: &Position,
-        danger_zones: u64,
+        danger_zones: SquareSet,
     ) -> Vec<ChessMove<'a>> {
-        if IMPRISONED {
+        if board.rules.imprisoned {
             return Vec::new();
         }
+        let macho = board.rules.macho;
 
         let state_transition = vec![("castling-oo", 0), ("castling-ooo", 0)];
         let mut ret = Vec::new();
@@ -241,27 +272,24 @@ impl<'a> ChessemblyCompiled<'a> {
                 if i == 0 && j == 0 {
                     continue;
                 }
-                if ChessemblyCompiled::wall_collision(
-                    position,
+                let mut anchor = *position;
+                if ChessemblyCompiled::move_anchor(
+                    &mut anchor,
                     &(i, j),
                     board,
                     board.color_on(position).unwrap(),
                 ) == WallCollision::NoCollision
                 {
-                    if board.color_on(&((position.0 as i8 + i) as u8, (position.1 as i8 - j) as u8))
-                        != board.color_on(position)
-                    {
-                        if MACHO || !ChessemblyCompiled::is_danger_bit(danger_zones, (position.0 as i8 + i) as u8, (position.1 as i8 - j) as u8) {
+                    if !board.is_blocked_for(&anchor, board.color_on(position).unwrap()) {
+                        if macho || !ChessemblyCompiled::is_danger_bit(danger_zones, anchor.0, anchor.1) {
                             ret.push(ChessMove::Single(ChessMoveUnit {
                                 from: *position,
-                                take: ((position.0 as i8 + i) as u8, (position.1 as i8 - j) as u8),
-                                move_to: (
-                                    (position.0 as i8 + i) as u8,
-                                    (position.1 as i8 - j) as u8,
-                                ),
+                                take: anchor,
+                                move_to: anchor,
                                 move_type: MoveType::TakeMove,
                                 state_change: Some(state_transition.clone()),
                                 transition: None,
+                                marks: None,
                             }));
                         }
                     }
@@ -269,33 +297,39 @@ impl<'a> ChessemblyCompiled<'a> {
             }
         }
 
-        if MACHO {
+        let color = board.color_on(position).unwrap();
+        // 중립 킹과 왕족이 아닌 킹은 캐슬링하지 않습니다.
+        if macho || color == Color::Neutral || !board.rules.royal_king {
             return ret;
         }
 
-        let color = board.color_on(position).unwrap();
-
         let castling_oo = if color == Color::White { board.board_state.white.castling_oo } else { board.board_state.black.castling_oo };
         let castling_ooo = if color == Color::White { board.board_state.white.castling_ooo } else { board.board_state.black.castling_ooo };
+        // 킹과 룩 사이가 모두 비어 있어야 합니다. O-O는 킹이 (width - 2), O-O-O는 2번 파일로 이동합니다.
+        let width = board.get_width() as u8;
+        let is_clear = |board: &Board<'a, SIZE>, from: u8, to: u8| {
+            (from..to).all(|x| board.is_playable(&(x, position.1)) && board.color_on(&(x, position.1)).is_none())
+        };
         if castling_oo {
-            if board.piece_on(&(7, position.1)) == Some("rook") && board.color_on(&(7, position.1)) == Some(color) {
-                if board.color_on(&(6, position.1)) == None && board.color_on(&(5, position.1)) == None {
+            if board.piece_id_on(&(width - 1, position.1)) == Some(ids::ROOK) && board.color_on(&(width - 1, position.1)) == Some(color) {
+                if position.0 < width - 2 && is_clear(board, position.0 + 1, width - 1) {
                     if !ChessemblyCompiled::is_danger_bit(danger_zones, position.0, position.1) {
                         ret.push(ChessMove::Single(ChessMoveUnit {
                             from: *position,
-                            take: (6, position.1),
-                            move_to: (6, position.1),
+                            take: (width - 2, position.1),
+                            move_to: (width - 2, position.1),
                             move_type: MoveType::Castling,
                             state_change: Some(state_transition.clone()),
                             transition: None,
+                            marks: None,
                         }));
                     }
                 }
             }
         }
         if castling_ooo {
-            if board.piece_on(&(0, position.1)) == Some("rook") && board.color_on(&(0, position.1)) == Some(color) {
-                if board.color_on(&(1, position.1)) == None && board.color_on(&(2, position.1)) == None && board.color_on(&(3, position.1)) == None {
+            if board.piece_id_on(&(0, position.1)) == Some(ids::ROOK) && board.color_on(&(0, position.1)) == Some(color) {
+                if position.0 > 2 && is_clear(board, 1, position.0) {
                     if !ChessemblyCompiled::is_danger_bit(danger_zones, position.0, position.1) {
                         ret.push(ChessMove::Single(ChessMoveUnit {
                             from: *position,
@@ -304,6 +338,7 @@ impl<'a> ChessemblyCompiled<'a> {
                             move_type: MoveType::Castling,
                             state_change: Some(state_transition),
                             transition: None,
+                            marks: None,
                         }));
                     }
                 }
@@ -313,19 +348,38 @@ impl<'a> ChessemblyCompiled<'a> {
         ret
     }
     
-    pub fn generate_ij_abs_take_move<const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(
+    pub fn generate_ij_abs_take_move<const SIZE: usize>(
         &self,
         moves: &mut Vec<ChessMove<'a>>,
-        board: &mut Board<'a, MACHO, IMPRISONED, SIZE>,
+        board: &mut Board<'a, SIZE>,
         position: &Position,
         delta: &DeltaPosition,
     ) -> bool {
         let mut anchor = *position;
         let color = board.color_on(position).unwrap();
+        // 평평한 보드에서는 도약 테이블과 점유 칸으로 바로 판정합니다.
+        if board.is_flat() {
+            let Some(anchor) = attacks::leap(position, delta).filter(|x| board.is_playable(x)) else {
+                return false;
+            };
+            if board.blockers_for(color).contains(&anchor) {
+                return false;
+            }
+            moves.push(ChessMove::Single(ChessMoveUnit {
+                from: *position,
+                take: anchor,
+                move_to: anchor,
+                move_type: MoveType::TakeMove,
+                state_change: None,
+                transition: None,
+                marks: None,
+            }));
+            return !board.occupied().contains(&anchor);
+        }
         let wc = ChessemblyCompiled::move_anchor(&mut anchor, delta, board, color);
         if wc == WallCollision::NoCollision {
             let color_on = board.color_on(&anchor);
-            if color_on == Some(color) {
+            if board.is_blocked_for(&anchor, color) {
                 false
             }
             else if color_on == None {
@@ -335,7 +389,8 @@ impl<'a> ChessemblyCompiled<'a> {
                     move_to: anchor,
                     move_type: MoveType::TakeMove,
                     state_change: None,
-                    transition: None
+                    transition: None,
+                    marks: None,
                 }));
                 true
             }
@@ -346,7 +401,8 @@ impl<'a> ChessemblyCompiled<'a> {
                     move_to: anchor,
                     move_type: MoveType::TakeMove,
                     state_change: None,
-                    transition: None
+                    transition: None,
+                    marks: None,
                 }));
                 false
             }
@@ -356,23 +412,127 @@ impl<'a> ChessemblyCompiled<'a> {
         }
     }
 
-    pub fn generate_ij_abs_take_move_slide<const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(
+    pub fn generate_ij_abs_take_move_slide<const SIZE: usize>(
         &self,
         moves: &mut Vec<ChessMove<'a>>,
-        board: &mut Board<'a, MACHO, IMPRISONED, SIZE>,
+        board: &mut Board<'a, SIZE>,
         position: &Position,
         delta: &DeltaPosition,
     ) {
-        let mut sliding_delta = *delta;
-        while self.generate_ij_abs_take_move(moves, board, position, &sliding_delta) {
-            sliding_delta.0 += delta.0;
-            sliding_delta.1 += delta.1;
+        // 한 칸씩 앵커를 옮깁니다. 감싸는 보드에서는 자기 칸(아군)으로 돌아오면 멈추며,
+        // 최대 칸 수만큼만 반복해 무한 루프를 막습니다.
+        let color = board.color_on(position).unwrap();
+        // 평평한 보드에서는 광선 테이블로 닿는 칸을 한 번에 구하고 바깥쪽 순서로 넣습니다.
+        if board.is_flat() {
+            let holes = board.rules.holes;
+            if let Some(ray) = attacks::slide(position, delta, board.occupied() | holes) {
+                let targets = ray & SquareSet::rect(board.width, board.height) & !holes & !board.blockers_for(color);
+                let mut push = |anchor: Position| moves.push(ChessMove::Single(ChessMoveUnit {
+                    from: *position,
+                    take: anchor,
+                    move_to: anchor,
+                    move_type: MoveType::TakeMove,
+                    state_change: None,
+                    transition: None,
+                    marks: None,
+                }));
+                if attacks::is_ascending(delta) {
+                    targets.iter().for_each(&mut push);
+                } else {
+                    targets.iter_rev().for_each(&mut push);
+                }
+                return;
+            }
+        }
+        let mut anchor = *position;
+        // 감싸는 보드에서는 반대 방향 슬라이드가 같은 칸에 닿을 수 있어 중복을 거릅니다.
+        let wraps = board.rules.topology != Topology::Flat;
+        for _ in 0..board.get_width() * board.get_height() {
+            if ChessemblyCompiled::move_anchor(&mut anchor, delta, board, color) != WallCollision::NoCollision {
+                break;
+            }
+            let color_on = board.color_on(&anchor);
+            if board.is_blocked_for(&anchor, color) {
+                break;
+            }
+            if !(wraps && moves.iter().any(|x| x.get_dest() == anchor && x.get_source() == *position)) {
+                moves.push(ChessMove::Single(ChessMoveUnit {
+                    from: *position,
+                    take: anchor,
+                    move_to: anchor,
+                    move_type: MoveType::TakeMove,
+                    state_change: None,
+                    transition: None,
+                    marks: None,
+                }));
+            }
+            if color_on.is_some() {
+                break;
+            }
+        }
+    }
+
+    /// 평평한 보드에서 점유 칸만으로 공격 칸이 정해지는 기본 기물이면 그 공격 방식.
+    /// 기본 기물이 아니거나 가둠 규칙의 킹·마초 규칙의 폰처럼 따로 다뤄야 하면 None입니다.
+    pub fn native_attack_at<const SIZE: usize>(board: &Board<'a, SIZE>, position: &Position) -> Option<NativeAttack> {
+        if !board.is_flat() {
+            return None;
+        }
+        match board.piece_id_on(position)? {
+            ids::KING if board.rules.imprisoned => None,
+            ids::PAWN => (!board.rules.macho).then_some(NativeAttack::Pawn),
+            piece => attacks::native_pattern(piece).map(|(leaps, slides)| NativeAttack::Pattern(leaps, slides)),
         }
     }
 
-    pub fn generate_bishop_moves<const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(
+    /// 평평한 보드에서 기본 기물이 잡을 수 있는 칸 (점유 칸과 공격 테이블로 계산합니다).
+    /// `native_attack_at`이 None인 기물이면 None입니다.
+    pub fn native_attacks<const SIZE: usize>(board: &Board<'a, SIZE>, position: &Position) -> Option<SquareSet> {
+        let color = board.color_on(position)?;
+        let (leaps, slides) = match Self::native_attack_at(board, position)? {
+            NativeAttack::Pawn => return Some(Self::pawn_attacks(board, position, color)),
+            NativeAttack::Pattern(leaps, slides) => (leaps, slides),
+        };
+        let holes = board.rules.holes;
+        let mut ret = SquareSet::EMPTY;
+        for delta in leaps {
+            if let Some(target) = attacks::leap(position, delta) {
+                ret.insert(&target);
+            }
+        }
+        for delta in slides {
+            ret |= attacks::slide(position, delta, board.occupied() | holes)?;
+        }
+        Some(ret & SquareSet::rect(board.width, board.height) & !holes & !board.blockers_for(color))
+    }
+
+    /// 폰이 잡을 수 있는 칸: 앞 대각선의 잡을 수 있는 기물과 앙파상으로 잡히는 폰 (`generate_pawn_moves`의 잡는 수).
+    fn pawn_attacks<const SIZE: usize>(board: &Board<'a, SIZE>, position: &Position, color: Color) -> SquareSet {
+        let mut ret = SquareSet::EMPTY;
+        let height = board.height;
+        let (wall, step1, ep_rank) = match color {
+            Color::White => (0, position.1.wrapping_sub(1), 3),
+            Color::Black => (height - 1, position.1 + 1, height.wrapping_sub(4)),
+            Color::Neutral => return ret,
+        };
+        if position.1 == wall {
+            return ret;
+        }
+        let files = [position.0.checked_sub(1), Some(position.0 + 1).filter(|x| *x < board.width)];
+        for x in files.into_iter().flatten() {
+            if board.is_capturable_by(&(x, step1), color) {
+                ret.insert(&(x, step1));
+            }
+            if position.1 == ep_rank && board.state(color).enpassant.contains(&(x, position.1)) {
+                ret.insert(&(x, position.1));
+            }
+        }
+        ret
+    }
+
+    pub fn generate_bishop_moves<const SIZE: usize>(
         &self,
-        board: &mut Board<'a, MACHO, IMPRISONED, SIZE>,
+        board: &mut Board<'a, SIZE>,
         position: &Position,
     ) -> Vec<ChessMove<'a>> {
         let mut moves = Vec::new();
@@ -383,51 +543,64 @@ impl<'a> ChessemblyCompiled<'a> {
         moves
     }
 
-    pub fn generate_rook_moves<const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(
+    pub fn generate_rook_moves<const SIZE: usize>(
         &self,
-        board: &mut Board<'a, MACHO, IMPRISONED, SIZE>,
+        board: &mut Board<'a, SIZE>,
         position: &Position,
     ) -> Vec<ChessMove<'a>> {
-        let state_change = match (position.0.cmp(&0), position.0.cmp(&7), position.1.cmp(&0), position.1.cmp(&7), board.color_on(position).unwrap()) {
+        let (last_x, last_y) = (board.get_width() as u8 - 1, board.get_height() as u8 - 1);
+        let state_change = match (position.0.cmp(&0), position.0.cmp(&last_x), position.1.cmp(&0), position.1.cmp(&last_y), board.color_on(position).unwrap()) {
             (Ordering::Equal, _, _, Ordering::Equal, Color::White) => Some(("castling-ooo", 0)),
             (_, Ordering::Equal, _, Ordering::Equal, Color::White) => Some(("castling-oo", 0)),
             (Ordering::Equal, _, Ordering::Equal, _, Color::Black) => Some(("castling-ooo", 0)),
             (_, Ordering::Equal, Ordering::Equal, _, Color::Black) => Some(("castling-oo", 0)),
             (_, _, _, _, _) => None,
         };
-        if let Some(state_transition) = state_change {
-            ChessemblyCompiled {
-                chains: vec![
-                    vec![Behavior::SetState(state_transition), Behavior::TakeMove((1, 0)), Behavior::Repeat(1)],
-                    vec![Behavior::SetState(state_transition), Behavior::TakeMove((-1, 0)), Behavior::Repeat(1)],
-                    vec![Behavior::SetState(state_transition), Behavior::TakeMove((0, 1)), Behavior::Repeat(1)],
-                    vec![Behavior::SetState(state_transition), Behavior::TakeMove((0, -1)), Behavior::Repeat(1)],
-                ],
-            }.generate_moves::<MACHO, IMPRISONED, SIZE>(board, position, false).unwrap()
+        if board.is_flat() {
+            // 체인과 같은 순서로 넣습니다 (흑은 방향이 뒤집힙니다).
+            let sign = if board.color_on(position) == Some(Color::Black) { -1 } else { 1 };
+            let mut moves = Vec::new();
+            for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+                self.generate_ij_abs_take_move_slide(&mut moves, board, position, &(dx * sign, dy * sign));
+            }
+            if let Some(state_transition) = state_change {
+                for node in moves.iter_mut() {
+                    if let ChessMove::Single(unit) = node {
+                        unit.state_change = Some(vec![state_transition]);
+                    }
+                }
+            }
+            moves
+        }
+        else if let Some(state_transition) = state_change {
+            ChessemblyCompiled::from_chains(vec![
+                vec![Behavior::SetState(state_transition), Behavior::TakeMove((1, 0)), Behavior::Repeat(1)],
+                vec![Behavior::SetState(state_transition), Behavior::TakeMove((-1, 0)), Behavior::Repeat(1)],
+                vec![Behavior::SetState(state_transition), Behavior::TakeMove((0, 1)), Behavior::Repeat(1)],
+                vec![Behavior::SetState(state_transition), Behavior::TakeMove((0, -1)), Behavior::Repeat(1)],
+            ]).generate_moves::<SIZE>(board, position, false).unwrap()
         }
         else {
-            ChessemblyCompiled {
-                chains: vec![
-                    vec![Behavior::TakeMove((1, 0)), Behavior::Repeat(1)],
-                    vec![Behavior::TakeMove((-1, 0)), Behavior::Repeat(1)],
-                    vec![Behavior::TakeMove((0, 1)), Behavior::Repeat(1)],
-                    vec![Behavior::TakeMove((0, -1)), Behavior::Repeat(1)],
-                ],
-            }.generate_moves::<MACHO, IMPRISONED, SIZE>(board, position, false).unwrap()
+            ChessemblyCompiled::from_chains(vec![
+                vec![Behavior::TakeMove((1, 0)), Behavior::Repeat(1)],
+                vec![Behavior::TakeMove((-1, 0)), Behavior::Repeat(1)],
+                vec![Behavior::TakeMove((0, 1)), Behavior::Repeat(1)],
+                vec![Behavior::TakeMove((0, -1)), Behavior::Repeat(1)],
+            ]).generate_moves::<SIZE>(board, position, false).unwrap()
         }
     }
 
-    pub fn generate_knight_moves<const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(
+    pub fn generate_knight_moves<const SIZE: usize>(
         &self,
-        board: &mut Board<'a, MACHO, IMPRISONED, SIZE>,
+        board: &mut Board<'a, SIZE>,
         position: &Position,
     ) -> Vec<ChessMove<'a>> {
-        self.generate_ij_moves::<MACHO, IMPRISONED, SIZE>(board, position, 2, 1)
+        self.generate_ij_moves::<SIZE>(board, position, 2, 1)
     }
 
-    pub fn generate_queen_moves<const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(
+    pub fn generate_queen_moves<const SIZE: usize>(
         &self,
-        board: &mut Board<'a, MACHO, IMPRISONED, SIZE>,
+        board: &mut Board<'a, SIZE>,
         position: &Position,
     ) -> Vec<ChessMove<'a>> {
         let mut moves = Vec::new();
@@ -442,9 +615,9 @@ impl<'a> ChessemblyCompiled<'a> {
         moves
     }
 
-    pub fn generate_dozer_moves<const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(
+    pub fn generate_dozer_moves<const SIZE: usize>(
         &self,
-        board: &mut Board<'a, MACHO, IMPRISONED, SIZE>,
+        board: &mut Board<'a, SIZE>,
         position: &Position,
     ) -> Vec<ChessMove<'a>> {
         let mut moves = Vec::new();
@@ -465,19 +638,19 @@ impl<'a> ChessemblyCompiled<'a> {
         moves
     }
 
-    pub fn generate_bouncing_bishop_moves<const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(
+    pub fn generate_bouncing_bishop_moves<const SIZE: usize>(
         &self,
-        board: &mut Board<'a, MACHO, IMPRISONED, SIZE>,
+        board: &mut Board<'a, SIZE>,
         position: &Position,
     ) -> Vec<ChessMove<'a>> {
         let fs = ChessemblyCompiled::from_script("do take-move(1, 1) while edge(1, 1) { take-move(-1, 1) repeat(1) } { take-move(1, -1) repeat(1) }; do take-move(-1, 1) while edge(-1, 1) { take-move(1, 1) repeat(1) } { take-move(-1, -1) repeat(1) }; do take-move(1, -1) while edge(1, -1) { take-move(1, 1) repeat(1) } { take-move(-1, -1) repeat(1) }; do take-move(-1, -1) while edge(-1, -1) { take-move(1, -1) repeat(1) } { take-move(-1, 1) repeat(1) };").unwrap();
-        let ret = fs.generate_moves::<MACHO, IMPRISONED, SIZE>(board, position, false).unwrap();
+        let ret = fs.generate_moves::<SIZE>(board, position, false).unwrap();
         ret
     }
 
-    pub fn generate_alfil_moves<const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(
+    pub fn generate_alfil_moves<const SIZE: usize>(
         &self,
-        board: &mut Board<'a, MACHO, IMPRISONED, SIZE>,
+        board: &mut Board<'a, SIZE>,
         position: &Position,
     ) -> Vec<ChessMove<'a>> {
         let mut ret = Vec::new();
@@ -488,9 +661,9 @@ impl<'a> ChessemblyCompiled<'a> {
         ret
     }
 
-    pub fn generate_ij_moves<const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(
+    pub fn generate_ij_moves<const SIZE: usize>(
         &self,
-        board: &mut Board<'a, MACHO, IMPRISONED, SIZE>,
+        board: &mut Board<'a, SIZE>,
         position: &Position,
         i: i8,
         j: i8,
@@ -507,9 +680,9 @@ impl<'a> ChessemblyCompiled<'a> {
         ret
     }
 
-    pub fn generate_bard_moves<const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(
+    pub fn generate_bard_moves<const SIZE: usize>(
         &self,
-        board: &mut Board<'a, MACHO, IMPRISONED, SIZE>,
+        board: &mut Board<'a, SIZE>,
         position: &Position,
     ) -> Vec<ChessMove<'a>> {
         let mut ret = Vec::new();
@@ -524,28 +697,26 @@ impl<'a> ChessemblyCompiled<'a> {
         ret
     }
     
-    pub fn generate_wasp_moves<const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(
+    pub fn generate_wasp_moves<const SIZE: usize>(
         &self,
-        board: &mut Board<'a, MACHO, IMPRISONED, SIZE>,
+        board: &mut Board<'a, SIZE>,
         position: &Position,
     ) -> Vec<ChessMove<'a>> {
-        ChessemblyCompiled {
-            chains: vec![
-                vec![Behavior::TakeMove((0, 1)), Behavior::Repeat(1)],
-                vec![Behavior::Move((1, -1)), Behavior::Repeat(1)],
-                vec![Behavior::Move((-1, -1)), Behavior::Repeat(1)],
-            ],
-        }
-            .generate_moves::<MACHO, IMPRISONED, SIZE>(board, position, false)
+        ChessemblyCompiled::from_chains(vec![
+            vec![Behavior::TakeMove((0, 1)), Behavior::Repeat(1)],
+            vec![Behavior::Move((1, -1)), Behavior::Repeat(1)],
+            vec![Behavior::Move((-1, -1)), Behavior::Repeat(1)],
+        ])
+            .generate_moves::<SIZE>(board, position, false)
             .unwrap()
     }
 
-    pub fn generate_amazon_moves<const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(
+    pub fn generate_amazon_moves<const SIZE: usize>(
         &self,
-        board: &mut Board<'a, MACHO, IMPRISONED, SIZE>,
+        board: &mut Board<'a, SIZE>,
         position: &Position,
     ) -> Vec<ChessMove<'a>> {
-        let mut moves = self.generate_knight_moves::<MACHO, IMPRISONED, SIZE>(board, position);
+        let mut moves = self.generate_knight_moves::<SIZE>(board, position);
         self.generate_ij_abs_take_move_slide(&mut moves, board, position, &(1, 0));
         self.generate_ij_abs_take_move_slide(&mut moves, board, position, &(-1, 0));
         self.generate_ij_abs_take_move_slide(&mut moves, board, position, &(0, 1));
@@ -557,12 +728,12 @@ impl<'a> ChessemblyCompiled<'a> {
         moves
     }
 
-    pub fn generate_centaur_moves<const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(
+    pub fn generate_centaur_moves<const SIZE: usize>(
         &self,
-        board: &mut Board<'a, MACHO, IMPRISONED, SIZE>,
+        board: &mut Board<'a, SIZE>,
         position: &Position,
     ) -> Vec<ChessMove<'a>> {
-        let mut moves = self.generate_knight_moves::<MACHO, IMPRISONED, SIZE>(board, position);
+        let mut moves = self.generate_knight_moves::<SIZE>(board, position);
         self.generate_ij_abs_take_move(&mut moves, board, position, &(1, 0));
         self.generate_ij_abs_take_move(&mut moves, board, position, &(-1, 0));
         self.generate_ij_abs_take_move(&mut moves, board, position, &(0, 1));
@@ -574,12 +745,12 @@ impl<'a> ChessemblyCompiled<'a> {
         moves
     }
 
-    pub fn generate_archbishop_moves<const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(
+    pub fn generate_archbishop_moves<const SIZE: usize>(
         &self,
-        board: &mut Board<'a, MACHO, IMPRISONED, SIZE>,
+        board: &mut Board<'a, SIZE>,
         position: &Position,
     ) -> Vec<ChessMove<'a>> {
-        let mut moves = self.generate_knight_moves::<MACHO, IMPRISONED, SIZE>(board, position);
+        let mut moves = self.generate_knight_moves::<SIZE>(board, position);
         self.generate_ij_abs_take_move_slide(&mut moves, board, position, &(1, 1));
         self.generate_ij_abs_take_move_slide(&mut moves, board, position, &(1, -1));
         self.generate_ij_abs_take_move_slide(&mut moves, board, position, &(-1, 1));
@@ -587,12 +758,12 @@ impl<'a> ChessemblyCompiled<'a> {
         moves
     }
 
-    pub fn generate_chancellor_moves<const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(
+    pub fn generate_chancellor_moves<const SIZE: usize>(
         &self,
-        board: &mut Board<'a, MACHO, IMPRISONED, SIZE>,
+        board: &mut Board<'a, SIZE>,
         position: &Position,
     ) -> Vec<ChessMove<'a>> {
-        let mut moves = self.generate_knight_moves::<MACHO, IMPRISONED, SIZE>(board, position);
+        let mut moves = self.generate_knight_moves::<SIZE>(board, position);
         self.generate_ij_abs_take_move_slide(&mut moves, board, position, &(1, 0));
         self.generate_ij_abs_take_move_slide(&mut moves, board, position, &(-1, 0));
         self.generate_ij_abs_take_move_slide(&mut moves, board, position, &(0, 1));
@@ -600,158 +771,152 @@ impl<'a> ChessemblyCompiled<'a> {
         moves
     }
 
-    pub fn generate_cannon_moves<const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(
+    pub fn generate_cannon_moves<const SIZE: usize>(
         &self,
-        board: &mut Board<'a, MACHO, IMPRISONED, SIZE>,
+        board: &mut Board<'a, SIZE>,
         position: &Position,
     ) -> Vec<ChessMove<'a>> {
-        ChessemblyCompiled {
-            chains: vec![
-                vec![
-                    Behavior::Do,
-                    Behavior::Take((1, 0)),
-                    Behavior::Enemy((0, 0)),
-                    Behavior::Not,
-                    Behavior::While,
-                    Behavior::Jump((1, 0)),
-                    Behavior::Repeat(1),
-                ],
-                vec![
-                    Behavior::Do,
-                    Behavior::Take((-1, 0)),
-                    Behavior::Enemy((0, 0)),
-                    Behavior::Not,
-                    Behavior::While,
-                    Behavior::Jump((-1, 0)),
-                    Behavior::Repeat(1),
-                ],
-                vec![
-                    Behavior::Do,
-                    Behavior::Take((0, 1)),
-                    Behavior::Enemy((0, 0)),
-                    Behavior::Not,
-                    Behavior::While,
-                    Behavior::Jump((0, 1)),
-                    Behavior::Repeat(1),
-                ],
-                vec![
-                    Behavior::Do,
-                    Behavior::Take((0, -1)),
-                    Behavior::Enemy((0, 0)),
-                    Behavior::Not,
-                    Behavior::While,
-                    Behavior::Jump((0, -1)),
-                    Behavior::Repeat(1),
-                ],
-                vec![
-                    Behavior::Do,
-                    Behavior::Peek((1, 0)),
-                    Behavior::While,
-                    Behavior::Friendly((0, 0)),
-                    Behavior::Move((1, 0)),
-                    Behavior::Repeat(1),
-                ],
-                vec![
-                    Behavior::Do,
-                    Behavior::Peek((-1, 0)),
-                    Behavior::While,
-                    Behavior::Friendly((0, 0)),
-                    Behavior::Move((-1, 0)),
-                    Behavior::Repeat(1),
-                ],
-                vec![
-                    Behavior::Do,
-                    Behavior::Peek((0, 1)),
-                    Behavior::While,
-                    Behavior::Friendly((0, 0)),
-                    Behavior::Move((0, 1)),
-                    Behavior::Repeat(1),
-                ],
-                vec![
-                    Behavior::Do,
-                    Behavior::Peek((0, -1)),
-                    Behavior::While,
-                    Behavior::Friendly((0, 0)),
-                    Behavior::Move((0, -1)),
-                    Behavior::Repeat(1),
-                ],
+        ChessemblyCompiled::from_chains(vec![
+            vec![
+                Behavior::Do,
+                Behavior::Take((1, 0)),
+                Behavior::Enemy((0, 0)),
+                Behavior::Not,
+                Behavior::While,
+                Behavior::Jump((1, 0)),
+                Behavior::Repeat(1),
             ],
-        }
-            .generate_moves::<MACHO, IMPRISONED, SIZE>(board, position, false)
+            vec![
+                Behavior::Do,
+                Behavior::Take((-1, 0)),
+                Behavior::Enemy((0, 0)),
+                Behavior::Not,
+                Behavior::While,
+                Behavior::Jump((-1, 0)),
+                Behavior::Repeat(1),
+            ],
+            vec![
+                Behavior::Do,
+                Behavior::Take((0, 1)),
+                Behavior::Enemy((0, 0)),
+                Behavior::Not,
+                Behavior::While,
+                Behavior::Jump((0, 1)),
+                Behavior::Repeat(1),
+            ],
+            vec![
+                Behavior::Do,
+                Behavior::Take((0, -1)),
+                Behavior::Enemy((0, 0)),
+                Behavior::Not,
+                Behavior::While,
+                Behavior::Jump((0, -1)),
+                Behavior::Repeat(1),
+            ],
+            vec![
+                Behavior::Do,
+                Behavior::Peek((1, 0)),
+                Behavior::While,
+                Behavior::Friendly((0, 0)),
+                Behavior::Move((1, 0)),
+                Behavior::Repeat(1),
+            ],
+            vec![
+                Behavior::Do,
+                Behavior::Peek((-1, 0)),
+                Behavior::While,
+                Behavior::Friendly((0, 0)),
+                Behavior::Move((-1, 0)),
+                Behavior::Repeat(1),
+            ],
+            vec![
+                Behavior::Do,
+                Behavior::Peek((0, 1)),
+                Behavior::While,
+                Behavior::Friendly((0, 0)),
+                Behavior::Move((0, 1)),
+                Behavior::Repeat(1),
+            ],
+            vec![
+                Behavior::Do,
+                Behavior::Peek((0, -1)),
+                Behavior::While,
+                Behavior::Friendly((0, 0)),
+                Behavior::Move((0, -1)),
+                Behavior::Repeat(1),
+            ],
+        ])
+            .generate_moves::<SIZE>(board, position, false)
             .unwrap()
     }
 
-    pub fn generate_tempest_rook_moves<const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(
+    pub fn generate_tempest_rook_moves<const SIZE: usize>(
         &self,
-        board: &mut Board<'a, MACHO, IMPRISONED, SIZE>,
+        board: &mut Board<'a, SIZE>,
         position: &Position,
     ) -> Vec<ChessMove<'a>> {
-        ChessemblyCompiled {
-            chains: vec![
-                vec![
-                    Behavior::TakeMove((1, 1)),
-                    Behavior::TakeMove((1, 0)),
-                    Behavior::Repeat(1),
-                ],
-                vec![
-                    Behavior::TakeMove((1, 1)),
-                    Behavior::TakeMove((0, 1)),
-                    Behavior::Repeat(1),
-                ],
-                vec![
-                    Behavior::TakeMove((1, -1)),
-                    Behavior::TakeMove((1, 0)),
-                    Behavior::Repeat(1),
-                ],
-                vec![
-                    Behavior::TakeMove((1, -1)),
-                    Behavior::TakeMove((0, -1)),
-                    Behavior::Repeat(1),
-                ],
-                vec![
-                    Behavior::TakeMove((-1, 1)),
-                    Behavior::TakeMove((-1, 0)),
-                    Behavior::Repeat(1),
-                ],
-                vec![
-                    Behavior::TakeMove((-1, 1)),
-                    Behavior::TakeMove((0, 1)),
-                    Behavior::Repeat(1),
-                ],
-                vec![
-                    Behavior::TakeMove((-1, -1)),
-                    Behavior::TakeMove((-1, 0)),
-                    Behavior::Repeat(1),
-                ],
-                vec![
-                    Behavior::TakeMove((-1, -1)),
-                    Behavior::TakeMove((0, -1)),
-                    Behavior::Repeat(1),
-                ],
+        ChessemblyCompiled::from_chains(vec![
+            vec![
+                Behavior::TakeMove((1, 1)),
+                Behavior::TakeMove((1, 0)),
+                Behavior::Repeat(1),
             ],
-        }
-            .generate_moves::<MACHO, IMPRISONED, SIZE>(board, position, false)
+            vec![
+                Behavior::TakeMove((1, 1)),
+                Behavior::TakeMove((0, 1)),
+                Behavior::Repeat(1),
+            ],
+            vec![
+                Behavior::TakeMove((1, -1)),
+                Behavior::TakeMove((1, 0)),
+                Behavior::Repeat(1),
+            ],
+            vec![
+                Behavior::TakeMove((1, -1)),
+                Behavior::TakeMove((0, -1)),
+                Behavior::Repeat(1),
+            ],
+            vec![
+                Behavior::TakeMove((-1, 1)),
+                Behavior::TakeMove((-1, 0)),
+                Behavior::Repeat(1),
+            ],
+            vec![
+                Behavior::TakeMove((-1, 1)),
+                Behavior::TakeMove((0, 1)),
+                Behavior::Repeat(1),
+            ],
+            vec![
+                Behavior::TakeMove((-1, -1)),
+                Behavior::TakeMove((-1, 0)),
+                Behavior::Repeat(1),
+            ],
+            vec![
+                Behavior::TakeMove((-1, -1)),
+                Behavior::TakeMove((0, -1)),
+                Behavior::Repeat(1),
+            ],
+        ])
+            .generate_moves::<SIZE>(board, position, false)
             .unwrap()
     }
 
-    pub fn generate_chameleon_moves<const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(
+    pub fn generate_chameleon_moves<const SIZE: usize>(
         &self,
-        board: &mut Board<'a, MACHO, IMPRISONED, SIZE>,
+        board: &mut Board<'a, SIZE>,
         position: &Position,
     ) -> Vec<ChessMove<'a>> {
-        let mut moves = ChessemblyCompiled {
-            chains: vec![
-                vec![Behavior::Move((1, 0))],
-                vec![Behavior::Move((-1, 0))],
-                vec![Behavior::Move((0, 1))],
-                vec![Behavior::Move((0, -1))],
-                vec![Behavior::Move((1, 1))],
-                vec![Behavior::Move((1, -1))],
-                vec![Behavior::Move((-1, 1))],
-                vec![Behavior::Move((-1, -1))],
-            ],
-        }
-            .generate_moves::<MACHO, IMPRISONED, SIZE>(board, position, false)
+        let mut moves = ChessemblyCompiled::from_chains(vec![
+            vec![Behavior::Move((1, 0))],
+            vec![Behavior::Move((-1, 0))],
+            vec![Behavior::Move((0, 1))],
+            vec![Behavior::Move((0, -1))],
+            vec![Behavior::Move((1, 1))],
+            vec![Behavior::Move((1, -1))],
+            vec![Behavior::Move((-1, 1))],
+            vec![Behavior::Move((-1, -1))],
+        ])
+            .generate_moves::<SIZE>(board, position, false)
             .unwrap();
 
         let catch_list = [(2, 2), (2, -2), (-2, 2), (-2, -2)];
@@ -759,56 +924,61 @@ impl<'a> ChessemblyCompiled<'a> {
             let mut anchor = position.clone();
             let wc = ChessemblyCompiled::move_anchor(&mut anchor, &catch_delta, board, board.color_on(position).unwrap());
             if wc == WallCollision::NoCollision {
-                if board.color_on(&anchor) == Some(board.color_on(position).unwrap().invert()) {
-                    match board.piece_on(&anchor).unwrap() {
-                        "pawn" => {
+                if board.is_capturable_by(&anchor, board.color_on(position).unwrap()) {
+                    match board.piece_id_on(&anchor).unwrap() {
+                        ids::PAWN => {
                             moves.push(ChessMove::Single(ChessMoveUnit {
                                 from: *position,
                                 take: anchor.clone(),
                                 move_to: *position,
                                 move_type: MoveType::Catch,
                                 state_change: None,
-                                transition: Some("mirrored-pawn")
+                                transition: Some("mirrored-pawn"),
+                                marks: None,
                             }));
                         },
-                        "queen" => {
+                        ids::QUEEN => {
                             moves.push(ChessMove::Single(ChessMoveUnit {
                                 from: *position,
                                 take: anchor.clone(),
                                 move_to: *position,
                                 move_type: MoveType::Catch,
                                 state_change: None,
-                                transition: Some("mirrored-queen")
+                                transition: Some("mirrored-queen"),
+                                marks: None,
                             }));
                         },
-                        "bishop" => {
+                        ids::BISHOP => {
                             moves.push(ChessMove::Single(ChessMoveUnit {
                                 from: *position,
                                 take: anchor.clone(),
                                 move_to: *position,
                                 move_type: MoveType::Catch,
                                 state_change: None,
-                                transition: Some("mirrored-bishop")
+                                transition: Some("mirrored-bishop"),
+                                marks: None,
                             }));
                         },
-                        "knight" => {
+                        ids::KNIGHT => {
                             moves.push(ChessMove::Single(ChessMoveUnit {
                                 from: *position,
                                 take: anchor.clone(),
                                 move_to: *position,
                                 move_type: MoveType::Catch,
                                 state_change: None,
-                                transition: Some("mirrored-knight")
+                                transition: Some("mirrored-knight"),
+                                marks: None,
                             }));
                         },
-                        "rook" => {
+                        ids::ROOK => {
                             moves.push(ChessMove::Single(ChessMoveUnit {
                                 from: *position,
                                 take: anchor.clone(),
                                 move_to: *position,
                                 move_type: MoveType::Catch,
                                 state_change: None,
-                                transition: Some("mirrored-rook")
+                                transition: Some("mirrored-rook"),
+                                marks: None,
                             }));
                         },
                         _ => {}
@@ -819,38 +989,36 @@ impl<'a> ChessemblyCompiled<'a> {
         moves
     }
 
-    pub fn generate_pseudo_pawn_moves<const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(
+    pub fn generate_pseudo_pawn_moves<const SIZE: usize>(
         &self,
-        board: &mut Board<'a, MACHO, IMPRISONED, SIZE>,
+        board: &mut Board<'a, SIZE>,
         position: &Position,
     ) -> Vec<ChessMove<'a>> {
-        ChessemblyCompiled {
-            chains: vec![
-                vec![Behavior::Move((0, 1))],
-                vec![Behavior::Take((1, 1))],
-                vec![Behavior::Take((-1, 1))],
-            ],
-        }
-            .generate_moves::<MACHO, IMPRISONED, SIZE>(board, position, false)
+        ChessemblyCompiled::from_chains(vec![
+            vec![Behavior::Move((0, 1))],
+            vec![Behavior::Take((1, 1))],
+            vec![Behavior::Take((-1, 1))],
+        ])
+            .generate_moves::<SIZE>(board, position, false)
             .unwrap()
     }
 
-    pub fn generate_beacon_moves<const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(
+    pub fn generate_beacon_moves<const SIZE: usize>(
         &self,
-        board: &mut Board<'a, MACHO, IMPRISONED, SIZE>,
+        board: &mut Board<'a, SIZE>,
         position: &Position,
     ) -> Vec<ChessMove<'a>> {
         let color = board.color_on(position).unwrap();
         let mut moves = Vec::new();
-        for i in 0..8 {
-            for j in 0..8 {
+        for i in 0..board.get_height() as u8 {
+            for j in 0..board.get_width() as u8 {
                 let Some(target_color) = board.color_on(&(j, i)) else { continue; };
                 if target_color == color {
-                    let target_piece = board.piece_on(&(j, i)).unwrap();
-                    if target_piece == "pawn" {
+                    let target_piece = board.piece_id_on(&(j, i)).unwrap();
+                    if target_piece == ids::PAWN {
                         continue;
                     }
-                    else if target_piece == "beacon" {
+                    else if target_piece == ids::BEACON {
                         continue;
                     }
                     moves.push(ChessMove::Single(ChessMoveUnit {
@@ -859,7 +1027,8 @@ impl<'a> ChessemblyCompiled<'a> {
                         move_to: (j, i),
                         move_type: MoveType::Shift,
                         state_change: None,
-                        transition: None
+                        transition: None,
+                        marks: None,
                     }));
                 }
             }
@@ -867,9 +1036,9 @@ impl<'a> ChessemblyCompiled<'a> {
         moves
     }
 
-    pub fn generate_pseudo_rook_moves<const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(
+    pub fn generate_pseudo_rook_moves<const SIZE: usize>(
         &self,
-        board: &mut Board<'a, MACHO, IMPRISONED, SIZE>,
+        board: &mut Board<'a, SIZE>,
         position: &Position,
     ) -> Vec<ChessMove<'a>> {
         let mut moves = Vec::new();
@@ -880,38 +1049,38 @@ impl<'a> ChessemblyCompiled<'a> {
         moves
     }
     
-    pub fn generate_windmill_rook_moves<const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(
+    pub fn generate_windmill_rook_moves<const SIZE: usize>(
         &self,
-        board: &mut Board<'a, MACHO, IMPRISONED, SIZE>,
+        board: &mut Board<'a, SIZE>,
         position: &Position,
     ) -> Vec<ChessMove<'a>> {
         let fs = ChessemblyCompiled::from_script("piece(windmill-rook) transition(windmill-bishop) { take-move(1, 0) repeat(1) } { take-move(0, 1) repeat(1) } { take-move(-1, 0) repeat(1) } { take-move(0, -1) repeat(1) };").unwrap();
-        let ret = fs.generate_moves::<MACHO, IMPRISONED, SIZE>(board, position, false).unwrap();
+        let ret = fs.generate_moves::<SIZE>(board, position, false).unwrap();
         ret
     }
 
-    pub fn generate_windmill_bishop_moves<const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(
+    pub fn generate_windmill_bishop_moves<const SIZE: usize>(
         &self,
-        board: &mut Board<'a, MACHO, IMPRISONED, SIZE>,
+        board: &mut Board<'a, SIZE>,
         position: &Position,
     ) -> Vec<ChessMove<'a>> {
         let fs = ChessemblyCompiled::from_script("piece(windmill-bishop) transition(windmill-rook) { take-move(1, 1) repeat(1) } { take-move(-1, 1) repeat(1) } { take-move(1, -1) repeat(1) } { take-move(-1, -1) repeat(1) };").unwrap();
-        let ret = fs.generate_moves::<MACHO, IMPRISONED, SIZE>(board, position, false).unwrap();
+        let ret = fs.generate_moves::<SIZE>(board, position, false).unwrap();
         ret
     }
 
-    pub fn generate_mirrored_moves<const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(
+    pub fn generate_mirrored_moves<const SIZE: usize>(
         &self,
-        board: &mut Board<'a, MACHO, IMPRISONED, SIZE>,
+        board: &mut Board<'a, SIZE>,
         position: &Position,
-        piece: &str,
+        piece: PieceId,
     ) -> Vec<ChessMove<'a>> {
         let moves = match piece {
-            "mirrored-pawn" => self.generate_pseudo_pawn_moves::<MACHO, IMPRISONED, SIZE>(board, position),
-            "mirrored-bishop" => self.generate_bishop_moves::<MACHO, IMPRISONED, SIZE>(board, position),
-            "mirrored-rook" => self.generate_pseudo_rook_moves::<MACHO, IMPRISONED, SIZE>(board, position),
-            "mirrored-knight" => self.generate_knight_moves::<MACHO, IMPRISONED, SIZE>(board, position),
-            "mirrored-queen" => self.generate_queen_moves::<MACHO, IMPRISONED, SIZE>(board, position),
+            ids::MIRRORED_PAWN => self.generate_pseudo_pawn_moves::<SIZE>(board, position),
+            ids::MIRRORED_BISHOP => self.generate_bishop_moves::<SIZE>(board, position),
+            ids::MIRRORED_ROOK => self.generate_pseudo_rook_moves::<SIZE>(board, position),
+            ids::MIRRORED_KNIGHT => self.generate_knight_moves::<SIZE>(board, position),
+            ids::MIRRORED_QUEEN => self.generate_queen_moves::<SIZE>(board, position),
             _ => Vec::new()
         };
         let enemy_color = board.color_on(position).unwrap().invert();
@@ -923,46 +1092,51 @@ impl<'a> ChessemblyCompiled<'a> {
 
             let take_color = board.color_on(&node.take);
             if take_color == Some(enemy_color) {
-                ChessMove::Single(match board.piece_on(&node.take).unwrap() {
-                    "pawn" => ChessMoveUnit {
+                ChessMove::Single(match board.piece_id_on(&node.take).unwrap() {
+                    ids::PAWN => ChessMoveUnit {
                         from: node.from,
                         take: node.take,
                         move_to: node.move_to,
                         move_type: node.move_type,
                         state_change: None,
-                        transition: Some("mirrored-pawn")
+                        transition: Some("mirrored-pawn"),
+                        marks: None,
                     },
-                    "bishop" => ChessMoveUnit {
+                    ids::BISHOP => ChessMoveUnit {
                         from: node.from,
                         take: node.take,
                         move_to: node.move_to,
                         move_type: node.move_type,
                         state_change: None,
-                        transition: Some("mirrored-bishop")
+                        transition: Some("mirrored-bishop"),
+                        marks: None,
                     },
-                    "rook" => ChessMoveUnit {
+                    ids::ROOK => ChessMoveUnit {
                         from: node.from,
                         take: node.take,
                         move_to: node.move_to,
                         move_type: node.move_type,
                         state_change: None,
-                        transition: Some("mirrored-rook")
+                        transition: Some("mirrored-rook"),
+                        marks: None,
                     },
-                    "knight" => ChessMoveUnit {
+                    ids::KNIGHT => ChessMoveUnit {
                         from: node.from,
                         take: node.take,
                         move_to: node.move_to,
                         move_type: node.move_type,
                         state_change: None,
-                        transition: Some("mirrored-knight")
+                        transition: Some("mirrored-knight"),
+                        marks: None,
                     },
-                    "queen" => ChessMoveUnit {
+                    ids::QUEEN => ChessMoveUnit {
                         from: node.from,
                         take: node.take,
                         move_to: node.move_to,
                         move_type: node.move_type,
                         state_change: None,
-                        transition: Some("mirrored-queen")
+                        transition: Some("mirrored-queen"),
+                        marks: None,
                     },
                     _ => node
                 })
@@ -972,4 +1146,29 @@ impl<'a> ChessemblyCompiled<'a> {
             }
         }).collect()
     }
+
+    /// 오리(덕 체스): 비어 있는 아무 칸으로나 옮겨지며 잡지 않습니다.
+    pub fn generate_duck_moves<const SIZE: usize>(
+        &self,
+        board: &mut Board<'a, SIZE>,
+        position: &Position,
+    ) -> Vec<ChessMove<'a>> {
+        let mut ret = Vec::new();
+        for y in 0..board.height {
+            for x in 0..board.width {
+                if board.is_playable(&(x, y)) && board.color_on(&(x, y)).is_none() {
+                    ret.push(ChessMove::Single(ChessMoveUnit {
+                        from: *position,
+                        take: (x, y),
+                        move_to: (x, y),
+                        move_type: MoveType::Move,
+                        state_change: None,
+                        transition: None,
+                        marks: None,
+                    }));
+                }
+            }
+        }
+        ret
+    }
 }
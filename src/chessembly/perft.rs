@@ -0,0 +1,151 @@
+use super::board::Board;
+use super::rules::Rules;
+use super::{ChessemblyCompiled, Color, MoveGen, Piece, PieceSpan};
+
+fn perft<const SIZE: usize>(board: &mut Board<SIZE>, depth: u8) -> u64 {
+    let moves = MoveGen::new_legal(board);
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+    let mut nodes = 0;
+    for node in &moves {
+        let undo = board.make_move_in_place(node, true);
+        nodes += perft(board, depth - 1);
+        board.unmake_move(undo);
+    }
+    nodes
+}
+
+/// FEN(배치, 차례, 캐슬링)으로 보드를 만듭니다. 앙파상 칸은 없다고 봅니다.
+fn from_fen<'a>(fen: &str, compiled: &'a ChessemblyCompiled<'a>) -> Board<'a, 8> {
+    let mut fields = fen.split(' ');
+    let placement = fields.next().unwrap().split('/')
+        .map(|rank| rank.chars().map(|c| c.to_digit(10).map_or(c.to_string(), |n| ".".repeat(n as usize))).collect::<String>())
+        .collect::<Vec<_>>()
+        .join("/");
+    let mut board = Board::<8>::from_str(&placement, compiled);
+    board.turn = if fields.next() == Some("b") { Color::Black } else { Color::White };
+    let castling = fields.next().unwrap_or("-");
+    board.board_state.white.castling_oo = castling.contains('K');
+    board.board_state.white.castling_ooo = castling.contains('Q');
+    board.board_state.black.castling_oo = castling.contains('k');
+    board.board_state.black.castling_ooo = castling.contains('q');
+    board.hash = board.zobrist();
+    board.history = vec![board.hash];
+    board
+}
+
+/// 비트보드 경로와 체인 해석 경로(`interpreted` 규칙) 모두에서 깊이별 노드 수를 맞춰 봅니다.
+fn check(fen: &str, expected: &[u64]) {
+    let compiled = ChessemblyCompiled::new();
+    for rules in [Rules::parse(""), Rules::parse("interpreted")] {
+        let mut board = from_fen(fen, &compiled);
+        board.rules = &rules;
+        for (depth, nodes) in expected.iter().enumerate() {
+            assert_eq!(perft(&mut board, depth as u8 + 1), *nodes, "{} depth {} (interpreted: {})", fen, depth + 1, rules.interpreted);
+        }
+    }
+}
+
+#[test]
+fn start_position() {
+    check("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq", &[20, 400, 8902, 197281]);
+}
+
+#[test]
+fn kiwipete() {
+    check("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq", &[48, 2039, 97862]);
+}
+
+#[test]
+fn rook_endgame() {
+    check("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w -", &[14, 191, 2812, 43238]);
+}
+
+#[test]
+fn promotions() {
+    check("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ", &[44, 1486, 62379]);
+}
+
+#[test]
+fn glinski() {
+    // Glinski 시작 국면에서 백의 첫 수는 51가지입니다. 가운데 폰이 마주 보고 있어 둘째 플라이는 51×51보다 적습니다.
+    let compiled = ChessemblyCompiled::new();
+    let mut board = Board::glinski(&compiled);
+    for (depth, nodes) in [(1, 51), (2, 2586)] {
+        assert_eq!(perft(&mut board, depth), nodes, "glinski depth {}", depth);
+    }
+}
+
+/// `make_move_in_place`가 바꿀 수 있는 보드 상태 전부.
+fn snapshot<'a>(board: &Board<'a, 8>) -> impl PartialEq + std::fmt::Debug + use<'a> {
+    (
+        (board.board, board.board_state.clone(), board.markers.clone()),
+        (board.hash, board.history.clone()),
+        (board.turn, board.phase, board.moves_left, board.roll, board.status),
+        (board.halfmove_clock, board.fullmove_number),
+        (board.occupancy, board.kings, board.pawns),
+    )
+}
+
+/// 모든 수를 `depth`까지 두고 되돌리며, 되돌린 뒤의 보드가 두기 전과 같은지 확인합니다.
+fn walk(board: &mut Board<8>, depth: u8) {
+    if depth == 0 {
+        return;
+    }
+    for node in &MoveGen::new_legal(board) {
+        let before = snapshot(board);
+        let undo = board.make_move_in_place(node, true);
+        assert_eq!(board.hash, board.zobrist(), "{:?}", node);
+        walk(board, depth - 1);
+        board.unmake_move(undo);
+        assert_eq!(snapshot(board), before, "{:?}", node);
+    }
+}
+
+#[test]
+fn unmake_restores_board() {
+    let compiled = ChessemblyCompiled::new();
+    let rules = Rules::parse("");
+    // 캐슬링·앙파상·승급·캡처가 모두 나오는 국면들.
+    for fen in [
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ",
+    ] {
+        let mut board = from_fen(fen, &compiled);
+        board.rules = &rules;
+        board.halfmove_clock = 7;
+        board.fullmove_number = 12;
+        walk(&mut board, 3);
+    }
+}
+
+#[test]
+fn unmake_restores_reserve_and_markers() {
+    // 배치 단계: 보관함이 바뀝니다.
+    let compiled = ChessemblyCompiled::new();
+    let rules = Rules::parse("placement");
+    let mut board = Board::<8>::empty(&compiled);
+    board.rules = &rules;
+    board.board_state.white.reserve = vec!["rook", "king", "rook"];
+    board.board_state.black.reserve = vec!["king", "knight"];
+    board.hash = board.zobrist();
+    board.history = vec![board.hash];
+    walk(&mut board, 3);
+
+    // 표식: 수마다 떠난 칸에 두 플라이짜리 표식을 남기고, 표식 옆으로만 옆걸음을 칩니다.
+    let compiled = ChessemblyCompiled::from_script(
+        "piece(trapper) mark(trap, 0, 0, 2) move(0, 1); piece(trapper) if-marked(trap, 1, 0) move(-1, 0);",
+    )
+    .unwrap();
+    let rules = Rules::parse("non-royal-king");
+    let mut board = Board::<8>::empty(&compiled);
+    board.rules = &rules;
+    for (x, y, color) in [(1, 6, Color::White), (2, 6, Color::White), (5, 1, Color::Black), (6, 1, Color::Black)] {
+        board.board[y][x] = PieceSpan::Piece(Piece::new("trapper", color, &compiled.pieces));
+    }
+    board.rebuild_occupancy();
+    board.hash = board.zobrist();
+    board.history = vec![board.hash];
+    walk(&mut board, 4);
+}
@@ -0,0 +1,501 @@
+use super::{board::Board, ids, Color, GameResult, PieceId, Position, SquareSet};
+
+// -----------------------------------------------------------------------------
+// 변형 규칙: 승리 조건과 스테일메이트 판정 방식
+// -----------------------------------------------------------------------------
+
+/// 스테일메이트(둘 수 있는 수가 없음)를 당한 쪽 기준의 판정.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum StalemateRule {
+    Draw,
+    Win,
+    Loss,
+}
+
+/// 체크메이트 외에 추가로 적용되는 승리 조건.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum WinCondition<'a> {
+    /// `piece`가 `squares` 중 한 칸에 도달하면 승리 (King of the Hill).
+    ReachSquares { piece: &'a str, squares: Vec<Position> },
+    /// `piece`가 보드 중앙 칸에 도달하면 승리. 중앙은 판정 때 보드 크기로 정합니다.
+    ReachCenter { piece: &'a str },
+    /// `piece`가 스크립트에 선언된 `zone` 구역(자기 색 기준)에 도달하면 승리.
+    ReachZone { piece: &'a str, zone: &'a str },
+    /// 상대에게 N번 체크를 주면 승리 (Three-check).
+    Checks(u8),
+    /// `piece` 종류의 기물을 모두 잃으면 패배 (Extinction).
+    Extinction(&'a str),
+    /// 킹만 남으면 패배 (Bare king).
+    BareKing,
+    /// `piece`가 자기 진영 기준 `rank`(홈 랭크 = 0)에 도달하면 승리 (Racing Kings).
+    Race { piece: &'a str, rank: u8 },
+    /// 기물을 모두 잃으면 승리 (Antichess).
+    LoseAll,
+}
+
+/// 보드의 위상. 감싸는 축에서는 반대편 가장자리가 이어져 벽이 없습니다.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Topology {
+    Flat,
+    /// 좌우(파일)만 이어짐.
+    Cylinder,
+    /// 좌우와 위아래가 모두 이어짐.
+    Torus,
+}
+
+impl Topology {
+    #[inline]
+    pub const fn wraps_x(self) -> bool {
+        matches!(self, Topology::Cylinder | Topology::Torus)
+    }
+
+    #[inline]
+    pub const fn wraps_y(self) -> bool {
+        matches!(self, Topology::Torus)
+    }
+}
+
+/// 칸의 모양. 육각 보드는 축 좌표 (q, r)를 저장 격자의 (q + R, r + R)에 두고
+/// 육각형 밖의 칸은 구멍으로 막습니다. Chessembly 델타 (dx, dy)는 축 좌표 (dx, -dy)로 해석됩니다.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Grid {
+    Square,
+    /// 반지름 R인 육각 보드 (Glinski·McCooey: R = 5, 91칸).
+    Hex(u8),
+}
+
+impl Grid {
+    /// 보드 크기 헤더가 없을 때 쓰는 (가로, 세로).
+    pub const fn default_size(self) -> (u8, u8) {
+        match self {
+            Grid::Square => (8, 8),
+            Grid::Hex(radius) => (2 * radius + 1, 2 * radius + 1),
+        }
+    }
+}
+
+/// 반지름 `radius`인 육각 보드에서 (2R+1)×(2R+1) 저장 격자 중 육각형 밖의 칸.
+pub const fn hex_holes(radius: u8) -> SquareSet {
+    let mut ret = SquareSet::EMPTY;
+    let size = 2 * radius + 1;
+    let mut y = 0;
+    while y < size {
+        let mut x = 0;
+        while x < size {
+            // |q + r| > R  ⇔  x + y < R 또는 x + y > 3R
+            if x + y < radius || x + y > 3 * radius {
+                ret.insert(&(x, y));
+            }
+            x += 1;
+        }
+        y += 1;
+    }
+    ret
+}
+
+/// 한 차례를 이루는 단계. 마지막 단계를 마쳐야 차례가 넘어갑니다.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Phase {
+    /// 자기 기물을 움직이는 단계 (`neutral-move`면 중립 기물 포함).
+    Move,
+    /// 중립 기물만 움직이는 단계 (예: 덕 체스의 오리).
+    Neutral,
+}
+
+/// 한 차례에 두는 수의 개수.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TurnLength {
+    /// 매 차례 `moves`수. 백의 첫 차례만 `first`수입니다 (Marseillais: 1).
+    Fixed { moves: u8, first: u8 },
+    /// n번째 차례에 n수 (Progressive chess).
+    Progressive,
+}
+
+impl TurnLength {
+    /// 0부터 센 `index`번째 차례(백의 첫 차례 = 0)에 두는 수의 개수.
+    pub const fn moves(self, index: u16) -> u8 {
+        match self {
+            TurnLength::Fixed { first, .. } if index == 0 => first,
+            TurnLength::Fixed { moves, .. } => moves,
+            TurnLength::Progressive => if index >= u8::MAX as u16 { u8::MAX } else { index as u8 + 1 },
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Rules<'a> {
+    /// 체크메이트로 승부가 나는지 여부. false면 수가 없을 때 항상 `stalemate` 규칙을 따릅니다.
+    pub checkmate: bool,
+    pub stalemate: StalemateRule,
+    pub win_conditions: Vec<WinCondition<'a>>,
+    /// 무승부가 되는 무캡처 플라이 수. 0이면 비활성 (표준: 100플라이 = 50수).
+    pub move_rule: u16,
+    /// 무승부가 되는 동형 반복 횟수. 0이면 비활성 (표준: 3회).
+    pub repetition: u8,
+    /// 마초 체스: 전진하는 수와 옆으로의 캡처만 허용하는 변형.
+    pub macho: bool,
+    /// 킹이 움직일 수 없는 변형.
+    pub imprisoned: bool,
+    /// 존재하지 않는 칸(구멍). 보드 가장자리처럼 취급되며 기물을 둘 수 없습니다.
+    pub holes: SquareSet,
+    pub topology: Topology,
+    pub grid: Grid,
+    /// 양쪽 모두 중립 기물을 잡을 수 있는지.
+    pub neutral_capture: bool,
+    /// 양쪽 모두 자기 차례에 중립 기물을 움직일 수 있는지 (중립 기물의 스크립트를 따릅니다).
+    pub neutral_move: bool,
+    /// 한 차례의 단계 순서. 비어 있으면 `Phase::Move` 한 단계입니다.
+    pub phases: Vec<Phase>,
+    pub turn_length: TurnLength,
+    /// 체크를 주면 남은 수와 관계없이 차례가 끝나는지.
+    pub check_ends_turn: bool,
+    /// 잡을 수 있는 수가 있으면 반드시 잡아야 하는지.
+    pub compulsory_capture: bool,
+    /// 킹이 왕족인지. false면 킹도 평범한 기물이라 체크가 없고 캐슬링하지 않습니다.
+    pub royal_king: bool,
+    /// 전장의 안개: 각 진영은 자기 기물이 보는 칸만 압니다 (서버 응답과 엔진 모두).
+    pub fog: bool,
+    /// 주사위 면마다 움직일 수 있는 기물 종류. 비어 있으면 주사위를 쓰지 않습니다.
+    /// 매 차례 시작에 한 면을 굴려(같은 확률) 그 종류의 기물만 움직입니다.
+    pub dice: Vec<&'a str>,
+    /// 배치 단계: 보관함(`BoardState::reserve`)에 기물이 남아 있는 동안 양쪽이 번갈아
+    /// 기물을 놓습니다. 놓을 수 있는 칸은 스크립트의 `setup-<기물>` 또는 `setup` 구역이며,
+    /// 둘 다 없으면 자기 진영 쪽 절반입니다.
+    pub placement: bool,
+    /// 평평한 보드에서도 비트보드 경로 대신 체인 해석 경로로 수를 만듭니다 (두 경로를 맞춰 볼 때).
+    pub interpreted: bool,
+}
+
+/// 표준 체스 규칙: 체크메이트 승리, 스테일메이트 무승부.
+pub static STANDARD_RULES: Rules<'static> = Rules::standard();
+
+/// Glinski 육각 체스 (반지름 5) 규칙.
+pub static HEX_RULES: Rules<'static> = Rules::hex(5);
+
+impl<'a> Rules<'a> {
+    pub const fn standard() -> Rules<'a> {
+        Rules {
+            checkmate: true,
+            stalemate: StalemateRule::Draw,
+            win_conditions: Vec::new(),
+            move_rule: 100,
+            repetition: 3,
+            macho: false,
+            imprisoned: false,
+            holes: SquareSet::EMPTY,
+            topology: Topology::Flat,
+            grid: Grid::Square,
+            neutral_capture: false,
+            neutral_move: false,
+            phases: Vec::new(),
+            turn_length: TurnLength::Fixed { moves: 1, first: 1 },
+            check_ends_turn: false,
+            compulsory_capture: false,
+            royal_king: true,
+            fog: false,
+            dice: Vec::new(),
+            placement: false,
+            interpreted: false,
+        }
+    }
+
+    /// 반지름 `radius`인 육각 보드의 표준 규칙.
+    pub const fn hex(radius: u8) -> Rules<'a> {
+        let mut ret = Rules::standard();
+        ret.grid = Grid::Hex(radius);
+        ret.holes = hex_holes(radius);
+        ret
+    }
+
+    /// `Win-Conditions` 헤더 형식의 규칙 문자열을 해석합니다.
+    /// 항목은 ';'로 구분하며 Chessembly와 같은 `name(param, ...)` 형태를 씁니다.
+    ///
+    /// - `reach(king, 3/3, 4/3, 3/4, 4/4)`, `king-of-the-hill`, `reach-zone(king, hill)`
+    /// - `checks(3)`, `three-check`
+    /// - `extinction(king)`, `bare-king`, `race(king, 7)`
+    /// - `stalemate(win|loss|draw)`, `no-checkmate`
+    /// - `move-rule(100)`, `repetition(3)` (0이면 비활성)
+    /// - `macho`, `imprisoned`
+    /// - `holes(0/0, 7/0, ...)`
+    /// - `cylinder`, `torus`
+    /// - `hex(5)`: 반지름 R인 육각 보드 (R ≤ 7)
+    /// - `neutral-capture`, `neutral-move`
+    /// - `phases(move, neutral)`: 한 차례의 단계 순서
+    /// - `duck`: 덕 체스 (수를 둔 뒤 오리를 옮김, 킹을 잡으면 승리, 스테일메이트는 승리)
+    /// - `moves-per-turn(2)`, `check-ends-turn`
+    /// - `marseillais`: 백의 첫 차례만 1수, 이후 2수씩. 체크를 주면 차례가 끝남
+    /// - `progressive`: n번째 차례에 n수. 체크를 주면 차례가 끝남
+    /// - `compulsory-capture`, `non-royal-king`, `lose-all`
+    /// - `antichess`: 강제 캡처, 평범한 킹, 기물을 모두 잃거나 스테일메이트되면 승리
+    /// - `fog`, `dark-chess`: 전장의 안개 (킹은 평범한 기물이 되어 체크 없이 킹을 잡으면 승리)
+    /// - `dice(pawn, knight, ...)`: 주사위 면 목록
+    /// - `dice-chess`: 여섯 면 주사위 (폰·나이트·비숍·룩·퀸·킹), 체크 없이 킹을 잡으면 승리
+    /// - `placement`: 보관함의 기물을 번갈아 놓는 배치 단계로 시작
+    /// - `interpreted`: 비트보드 경로를 끄고 체인 해석 경로만 씀 (디버그용)
+    pub fn parse(spec: &'a str) -> Rules<'a> {
+        let mut rules = Rules::standard();
+        for entry in spec.split(';') {
+            let entry = entry.trim();
+            if entry.is_empty() {
+                continue;
+            }
+            let (cmd, params) = match entry.split_once('(') {
+                Some((cmd, rest)) => (cmd.trim(), rest.trim_end_matches(')')),
+                None => (entry, ""),
+            };
+            let params: Vec<&str> = params.split(',').map(|x| x.trim()).collect();
+            match cmd {
+                "reach" => {
+                    let squares = params
+                        .iter()
+                        .skip(1)
+                        .filter_map(|sq| sq.split_once('/'))
+                        .map(|(x, y)| (x.trim().parse().unwrap_or(0), y.trim().parse().unwrap_or(0)))
+                        .collect();
+                    rules.win_conditions.push(WinCondition::ReachSquares { piece: params[0], squares });
+                }
+                "reach-zone" => {
+                    rules.win_conditions.push(WinCondition::ReachZone {
+                        piece: params[0],
+                        zone: params.get(1).copied().unwrap_or(""),
+                    });
+                }
+                "king-of-the-hill" => {
+                    rules.win_conditions.push(WinCondition::ReachCenter { piece: "king" });
+                }
+                "checks" => {
+                    rules.win_conditions.push(WinCondition::Checks(params[0].parse().unwrap_or(3)));
+                }
+                "three-check" => rules.win_conditions.push(WinCondition::Checks(3)),
+                "extinction" => rules.win_conditions.push(WinCondition::Extinction(params[0])),
+                "bare-king" => rules.win_conditions.push(WinCondition::BareKing),
+                "race" => {
+                    rules.win_conditions.push(WinCondition::Race {
+                        piece: params[0],
+                        rank: params.get(1).and_then(|x| x.parse().ok()).unwrap_or(7),
+                    });
+                }
+                "stalemate" => {
+                    rules.stalemate = match params[0] {
+                        "win" => StalemateRule::Win,
+                        "loss" => StalemateRule::Loss,
+                        _ => StalemateRule::Draw,
+                    };
+                }
+                "no-checkmate" => rules.checkmate = false,
+                "move-rule" => rules.move_rule = params[0].parse().unwrap_or(100),
+                "repetition" => rules.repetition = params[0].parse().unwrap_or(3),
+                "macho" => rules.macho = true,
+                "imprisoned" => rules.imprisoned = true,
+                "cylinder" => rules.topology = Topology::Cylinder,
+                "torus" => rules.topology = Topology::Torus,
+                "neutral-capture" => rules.neutral_capture = true,
+                "neutral-move" => rules.neutral_move = true,
+                "phases" => {
+                    rules.phases = params
+                        .iter()
+                        .filter_map(|x| match *x {
+                            "move" => Some(Phase::Move),
+                            "neutral" => Some(Phase::Neutral),
+                            _ => None,
+                        })
+                        .collect();
+                }
+                "moves-per-turn" => {
+                    let moves = params[0].parse().unwrap_or(1).max(1);
+                    rules.turn_length = TurnLength::Fixed { moves, first: moves };
+                }
+                "check-ends-turn" => rules.check_ends_turn = true,
+                "marseillais" => {
+                    rules.turn_length = TurnLength::Fixed { moves: 2, first: 1 };
+                    rules.check_ends_turn = true;
+                }
+                "progressive" => {
+                    rules.turn_length = TurnLength::Progressive;
+                    rules.check_ends_turn = true;
+                }
+                "compulsory-capture" => rules.compulsory_capture = true,
+                "non-royal-king" => rules.royal_king = false,
+                "lose-all" => rules.win_conditions.push(WinCondition::LoseAll),
+                "antichess" => {
+                    rules.compulsory_capture = true;
+                    rules.royal_king = false;
+                    rules.checkmate = false;
+                    rules.stalemate = StalemateRule::Win;
+                    rules.win_conditions.push(WinCondition::LoseAll);
+                }
+                "fog" => rules.fog = true,
+                "dark-chess" => {
+                    rules.fog = true;
+                    rules.royal_king = false;
+                    rules.checkmate = false;
+                    rules.win_conditions.push(WinCondition::Extinction("king"));
+                }
+                "dice" => rules.dice = params.iter().copied().filter(|x| !x.is_empty()).collect(),
+                "dice-chess" => {
+                    rules.dice = vec!["pawn", "knight", "bishop", "rook", "queen", "king"];
+                    rules.royal_king = false;
+                    rules.checkmate = false;
+                    rules.win_conditions.push(WinCondition::Extinction("king"));
+                }
+                "placement" => rules.placement = true,
+                "interpreted" => rules.interpreted = true,
+                "duck" => {
+                    rules.phases = vec![Phase::Move, Phase::Neutral];
+                    rules.checkmate = false;
+                    rules.stalemate = StalemateRule::Win;
+                    rules.win_conditions.push(WinCondition::Extinction("king"));
+                }
+                "hex" => {
+                    let radius = params[0].parse().unwrap_or(5).min(7);
+                    rules.grid = Grid::Hex(radius);
+                    rules.holes |= hex_holes(radius);
+                }
+                "holes" => {
+                    for (x, y) in params.iter().filter_map(|sq| sq.split_once('/')) {
+                        let (x, y): (u8, u8) = (x.trim().parse().unwrap_or(0), y.trim().parse().unwrap_or(0));
+                        // 16×16 밖의 칸은 버립니다 (SquareSet 범위).
+                        if (x as usize) < super::MAX_BOARD_SIZE && (y as usize) < super::MAX_BOARD_SIZE {
+                            rules.holes.insert(&(x, y));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        // 안개 속에서는 보이지 않는 공격자로 체크를 판정하면 숨은 기물이 드러나므로 킹을 평범한 기물로 둡니다.
+        if rules.fog && rules.royal_king {
+            rules.royal_king = false;
+            rules.checkmate = false;
+            rules.win_conditions.push(WinCondition::Extinction("king"));
+        }
+        rules
+    }
+
+    /// 한 차례의 단계 수 (최소 1).
+    #[inline]
+    pub fn phase_count(&self) -> u8 {
+        self.phases.len().max(1) as u8
+    }
+
+    /// `index`번째 단계. 단계가 선언되지 않았으면 항상 `Phase::Move`.
+    #[inline]
+    pub fn phase(&self, index: u8) -> Phase {
+        self.phases.get(index as usize).copied().unwrap_or(Phase::Move)
+    }
+
+    #[inline]
+    pub fn counts_checks(&self) -> bool {
+        self.win_conditions.iter().any(|x| matches!(x, WinCondition::Checks(_)))
+    }
+}
+
+impl<'a, const SIZE: usize> Board<'a, SIZE> {
+    /// 킹·폰은 전용 집합으로 바로 세고, 나머지는 `color`의 기물 칸만 봅니다.
+    fn squares_of(&self, color: Color, piece: PieceId) -> SquareSet {
+        match piece {
+            ids::KING => self.kings_of(color),
+            ids::PAWN => self.pawns_of(color),
+            _ => self.pieces_of(color).iter().filter(|sq| self.piece_id_on(sq) == Some(piece)).collect(),
+        }
+    }
+
+    fn count_pieces(&self, color: Color, piece: Option<PieceId>) -> usize {
+        match piece {
+            Some(piece) => self.squares_of(color, piece).len(),
+            None => self.pieces_of(color).len(),
+        }
+    }
+
+    fn piece_reached(&self, color: Color, piece: PieceId, target: impl Fn(Position) -> bool) -> bool {
+        self.squares_of(color, piece).iter().any(target)
+    }
+
+    /// `color` 진영이 승리 조건을 달성했는지 확인합니다.
+    fn has_won(&self, color: Color) -> bool {
+        let enemy = color.invert();
+        // 조건의 기물 이름은 판정마다 한 번만 ID로 바꾸고, 칸은 ID로 비교합니다.
+        let id = |piece: &str| self.script.pieces.id_of(piece);
+        let state = if color == Color::White { &self.board_state.white } else { &self.board_state.black };
+        self.rules.win_conditions.iter().any(|condition| match condition {
+            WinCondition::ReachSquares { piece, squares } => {
+                self.piece_reached(color, id(piece), |sq| squares.contains(&sq))
+            }
+            WinCondition::ReachCenter { piece } => {
+                let center = self.center_squares();
+                self.piece_reached(color, id(piece), |sq| center.contains(&sq))
+            }
+            WinCondition::ReachZone { piece, zone } => {
+                self.piece_reached(color, id(piece), |sq| self.script.zones.contains(zone, color, &sq))
+            }
+            WinCondition::Checks(n) => state.checks >= *n,
+            WinCondition::Extinction(piece) => self.count_pieces(enemy, Some(id(piece))) == 0,
+            WinCondition::BareKing => {
+                self.count_pieces(enemy, None) == self.count_pieces(enemy, Some(ids::KING))
+            }
+            WinCondition::Race { piece, rank } => {
+                let height = self.get_height() as u8;
+                self.piece_reached(color, id(piece), |(_, y)| {
+                    if color == Color::White { height - 1 - y == *rank } else { y == *rank }
+                })
+            }
+            WinCondition::LoseAll => self.count_pieces(color, None) == 0,
+        })
+    }
+
+    /// 방금 수를 둔 `mover` 우선으로 승리 조건을 판정해 승자를 반환합니다.
+    pub fn check_win_conditions(&self, mover: Color) -> Option<Color> {
+        if self.rules.win_conditions.is_empty() {
+            return None;
+        }
+        if self.has_won(mover) {
+            Some(mover)
+        } else if self.has_won(mover.invert()) {
+            Some(mover.invert())
+        } else {
+            None
+        }
+    }
+
+    /// 종료된 국면의 결과를 `GameResult`로 변환합니다. 진행 중이면 `None`.
+    pub fn result(&self) -> Option<GameResult> {
+        use super::board::BoardStatus;
+        match self.status {
+            BoardStatus::Ongoing => None,
+            BoardStatus::Stalemate => Some(GameResult::Stalemate),
+            BoardStatus::Draw => Some(GameResult::DrawDeclared),
+            BoardStatus::Checkmate => Some(if self.turn == Color::White {
+                GameResult::BlackCheckmates
+            } else {
+                GameResult::WhiteCheckmates
+            }),
+            BoardStatus::Victory(Color::White) => Some(GameResult::WhiteWins),
+            BoardStatus::Victory(Color::Black) => Some(GameResult::BlackWins),
+            BoardStatus::Victory(Color::Neutral) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{ChessemblyCompiled, Piece, PieceSpan};
+    use super::*;
+
+    #[test]
+    fn king_of_the_hill_center_follows_board_size() {
+        let compiled = ChessemblyCompiled::new();
+        let rules = Rules::parse("king-of-the-hill");
+        // (보드 크기, 중앙 칸, 중앙이 아닌 칸)
+        for (size, center, off) in [(8, (4, 4), (2, 2)), (6, (2, 3), (1, 1)), (7, (3, 3), (2, 3))] {
+            for (square, won) in [(center, true), (off, false)] {
+                let mut board = Board::<8>::empty_sized(&compiled, size, size);
+                board.rules = &rules;
+                let king = Piece { piece_type: "king", id: ids::KING, color: Color::White };
+                board.board[square.1 as usize][square.0 as usize] = PieceSpan::Piece(king);
+                board.rebuild_occupancy();
+                let expected = won.then_some(Color::White);
+                assert_eq!(board.check_win_conditions(Color::White), expected, "{}x{} {:?}", size, size, square);
+            }
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use std::ops::{BitAnd, BitOr, BitOrAssign, BitXor, Not};
+
+use super::Position;
+
+/// 지원하는 보드의 최대 가로·세로 칸 수.
+pub const MAX_BOARD_SIZE: usize = 16;
+
+/// 최대 16×16 보드용 칸 집합. 칸 인덱스는 `16 * y + x` 입니다.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default, Hash)]
+pub struct SquareSet([u64; 4]);
+
+impl SquareSet {
+    pub const EMPTY: SquareSet = SquareSet([0; 4]);
+
+    #[inline]
+    pub const fn index(position: &Position) -> usize {
+        position.1 as usize * MAX_BOARD_SIZE + position.0 as usize
+    }
+
+    #[inline]
+    pub const fn insert(&mut self, position: &Position) {
+        let i = Self::index(position);
+        self.0[i >> 6] |= 1 << (i & 63);
+    }
+
+    #[inline]
+    pub const fn remove(&mut self, position: &Position) {
+        let i = Self::index(position);
+        self.0[i >> 6] &= !(1 << (i & 63));
+    }
+
+    #[inline]
+    pub const fn contains(&self, position: &Position) -> bool {
+        let i = Self::index(position);
+        self.0[i >> 6] & (1 << (i & 63)) != 0
+    }
+
+    /// 왼쪽 위 `width`×`height` 영역 전체.
+    pub const fn rect(width: u8, height: u8) -> SquareSet {
+        let row: u64 = if width as usize >= MAX_BOARD_SIZE { 0xFFFF } else { (1 << width) - 1 };
+        let mut ret = [0; 4];
+        let mut y = 0;
+        while y < height as usize && y < MAX_BOARD_SIZE {
+            ret[y / 4] |= row << ((y % 4) * MAX_BOARD_SIZE);
+            y += 1;
+        }
+        SquareSet(ret)
+    }
+
+    /// 인덱스가 가장 작은 칸.
+    #[inline]
+    pub fn first(&self) -> Option<Position> {
+        let word = self.0.iter().position(|x| *x != 0)?;
+        Some(Self::position(word * 64 + self.0[word].trailing_zeros() as usize))
+    }
+
+    /// 인덱스가 가장 큰 칸.
+    #[inline]
+    pub fn last(&self) -> Option<Position> {
+        let word = self.0.iter().rposition(|x| *x != 0)?;
+        Some(Self::position(word * 64 + 63 - self.0[word].leading_zeros() as usize))
+    }
+
+    #[inline]
+    const fn position(i: usize) -> Position {
+        ((i % MAX_BOARD_SIZE) as u8, (i / MAX_BOARD_SIZE) as u8)
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|x| *x == 0)
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.iter().map(|x| x.count_ones() as usize).sum()
+    }
+
+    /// 집합에 속한 칸을 인덱스 순서대로 순회합니다.
+    pub fn iter(&self) -> impl Iterator<Item = Position> + '_ {
+        self.0.iter().enumerate().flat_map(|(word, bits)| {
+            let mut bits = *bits;
+            std::iter::from_fn(move || {
+                if bits == 0 {
+                    return None;
+                }
+                let i = word * 64 + bits.trailing_zeros() as usize;
+                bits &= bits - 1;
+                Some(Self::position(i))
+            })
+        })
+    }
+
+    /// 집합에 속한 칸을 인덱스 역순으로 순회합니다.
+    pub fn iter_rev(&self) -> impl Iterator<Item = Position> + '_ {
+        self.0.iter().enumerate().rev().flat_map(|(word, bits)| {
+            let mut bits = *bits;
+            std::iter::from_fn(move || {
+                if bits == 0 {
+                    return None;
+                }
+                let top = 63 - bits.leading_zeros() as usize;
+                bits &= !(1 << top);
+                Some(Self::position(word * 64 + top))
+            })
+        })
+    }
+}
+
+impl BitOr for SquareSet {
+    type Output = SquareSet;
+
+    #[inline]
+    fn bitor(self, rhs: SquareSet) -> SquareSet {
+        SquareSet([self.0[0] | rhs.0[0], self.0[1] | rhs.0[1], self.0[2] | rhs.0[2], self.0[3] | rhs.0[3]])
+    }
+}
+
+impl BitOrAssign for SquareSet {
+    #[inline]
+    fn bitor_assign(&mut self, rhs: SquareSet) {
+        *self = *self | rhs;
+    }
+}
+
+impl BitAnd for SquareSet {
+    type Output = SquareSet;
+
+    #[inline]
+    fn bitand(self, rhs: SquareSet) -> SquareSet {
+        SquareSet([self.0[0] & rhs.0[0], self.0[1] & rhs.0[1], self.0[2] & rhs.0[2], self.0[3] & rhs.0[3]])
+    }
+}
+
+impl BitXor for SquareSet {
+    type Output = SquareSet;
+
+    #[inline]
+    fn bitxor(self, rhs: SquareSet) -> SquareSet {
+        SquareSet([self.0[0] ^ rhs.0[0], self.0[1] ^ rhs.0[1], self.0[2] ^ rhs.0[2], self.0[3] ^ rhs.0[3]])
+    }
+}
+
+impl Not for SquareSet {
+    type Output = SquareSet;
+
+    #[inline]
+    fn not(self) -> SquareSet {
+        SquareSet([!self.0[0], !self.0[1], !self.0[2], !self.0[3]])
+    }
+}
+
+impl FromIterator<Position> for SquareSet {
+    fn from_iter<I: IntoIterator<Item = Position>>(iter: I) -> SquareSet {
+        let mut ret = SquareSet::EMPTY;
+        for position in iter {
+            ret.insert(&position);
+        }
+        ret
+    }
+}
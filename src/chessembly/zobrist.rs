@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+
+use super::{Color, Position};
+
+// -----------------------------------------------------------------------------
+// Zobrist 해시: 기물(인턴 ID)·칸·차례·캐슬링·앙파상·체크 수·레지스터·단계·남은 수·주사위·칸 표식·보관함을 모두 포함
+// 키 테이블 대신 splitmix64로 (태그, 값) 조합마다 키를 즉석 생성하므로
+// 보드 크기나 스크립트 기물 수에 제한이 없습니다.
+// -----------------------------------------------------------------------------
+
+/// 엔진에 내장된 기물 이름. 이 순서대로 0번부터 ID를 받습니다.
+pub const BUILTIN_PIECES: [&str; 29] = [
+    "pawn", "king", "rook", "knight", "bishop", "queen",
+    "tempest-rook", "bouncing-bishop", "dozer", "alfil", "bard", "wasp",
+    "amazon", "chancellor", "archbishop", "centaur", "zebra", "giraffe", "camel",
+    "beacon", "chameleon",
+    "mirrored-pawn", "mirrored-bishop", "mirrored-rook", "mirrored-knight", "mirrored-queen",
+    "windmill-rook", "windmill-bishop",
+    "duck",
+];
+
+/// 인턴된 기물 종류. 보드 칸과 이동 생성·평가는 이름 대신 이 값을 씁니다.
+pub type PieceId = u16;
+
+/// 인터너에 없는 이름의 ID. 이런 기물은 이름으로만 구별합니다.
+pub const UNKNOWN_PIECE: PieceId = PieceId::MAX;
+
+/// 내장 기물의 ID (`BUILTIN_PIECES`의 순서).
+pub mod ids {
+    use super::PieceId;
+
+    pub const PAWN: PieceId = 0;
+    pub const KING: PieceId = 1;
+    pub const ROOK: PieceId = 2;
+    pub const KNIGHT: PieceId = 3;
+    pub const BISHOP: PieceId = 4;
+    pub const QUEEN: PieceId = 5;
+    pub const TEMPEST_ROOK: PieceId = 6;
+    pub const BOUNCING_BISHOP: PieceId = 7;
+    pub const DOZER: PieceId = 8;
+    pub const ALFIL: PieceId = 9;
+    pub const BARD: PieceId = 10;
+    pub const WASP: PieceId = 11;
+    pub const AMAZON: PieceId = 12;
+    pub const CHANCELLOR: PieceId = 13;
+    pub const ARCHBISHOP: PieceId = 14;
+    pub const CENTAUR: PieceId = 15;
+    pub const ZEBRA: PieceId = 16;
+    pub const GIRAFFE: PieceId = 17;
+    pub const CAMEL: PieceId = 18;
+    pub const BEACON: PieceId = 19;
+    pub const CHAMELEON: PieceId = 20;
+    pub const MIRRORED_PAWN: PieceId = 21;
+    pub const MIRRORED_BISHOP: PieceId = 22;
+    pub const MIRRORED_ROOK: PieceId = 23;
+    pub const MIRRORED_KNIGHT: PieceId = 24;
+    pub const MIRRORED_QUEEN: PieceId = 25;
+    pub const WINDMILL_ROOK: PieceId = 26;
+    pub const WINDMILL_BISHOP: PieceId = 27;
+    pub const DUCK: PieceId = 28;
+}
+
+/// 내장 기물이면 그 ID. 인터너 없이 정해집니다.
+pub fn builtin_id(name: &str) -> Option<PieceId> {
+    BUILTIN_PIECES.iter().position(|x| *x == name).map(|x| x as PieceId)
+}
+
+/// 기물 이름 → 정수 ID 인터너. `default()`는 내장 기물도 없는 빈 인터너입니다.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PieceRegistry<'a> {
+    ids: HashMap<&'a str, u16>,
+    names: Vec<&'a str>,
+}
+
+impl<'a> PieceRegistry<'a> {
+    pub fn new() -> PieceRegistry<'a> {
+        let mut ret = PieceRegistry { ids: HashMap::new(), names: Vec::new() };
+        for name in BUILTIN_PIECES {
+            ret.intern(name);
+        }
+        ret
+    }
+
+    /// 이름을 등록하고 ID를 반환합니다. 이미 있으면 기존 ID.
+    pub fn intern(&mut self, name: &'a str) -> u16 {
+        if let Some(id) = self.ids.get(name) {
+            return *id;
+        }
+        let id = self.names.len() as u16;
+        self.ids.insert(name, id);
+        self.names.push(name);
+        id
+    }
+
+    #[inline]
+    pub fn get(&self, name: &str) -> Option<u16> {
+        self.ids.get(name).copied()
+    }
+
+    /// 보드에 놓을 기물의 ID. 내장 기물은 항상 고정 ID이고, 등록되지 않은 이름은 `UNKNOWN_PIECE`입니다.
+    #[inline]
+    pub fn id_of(&self, name: &str) -> PieceId {
+        builtin_id(name).or_else(|| self.get(name)).unwrap_or(UNKNOWN_PIECE)
+    }
+
+    #[inline]
+    pub fn name(&self, id: u16) -> Option<&'a str> {
+        self.names.get(id as usize).copied()
+    }
+}
+
+/// 해시용 기물 키. 등록되지 않은 기물은 이름 자체의 해시로 대신합니다.
+#[inline]
+pub fn piece_hash(id: PieceId, name: &str) -> u64 {
+    if id == UNKNOWN_PIECE { fnv1a(name) | (1 << 63) } else { id as u64 }
+}
+
+#[inline]
+const fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[inline]
+fn fnv1a(s: &str) -> u64 {
+    let mut h: u64 = 0xCBF2_9CE4_8422_2325;
+    for b in s.bytes() {
+        h ^= b as u64;
+        h = h.wrapping_mul(0x0100_0000_01B3);
+    }
+    h
+}
+
+const TAG_PIECE: u64 = 1;
+const TAG_CASTLING: u64 = 2;
+const TAG_ENPASSANT: u64 = 3;
+const TAG_CHECKS: u64 = 4;
+const TAG_REGISTER: u64 = 5;
+const TAG_PHASE: u64 = 6;
+const TAG_MOVES_LEFT: u64 = 7;
+const TAG_ROLL: u64 = 8;
+const TAG_MARKER: u64 = 9;
+const TAG_RESERVE: u64 = 10;
+
+#[inline]
+fn key(tag: u64, a: u64, b: u64) -> u64 {
+    splitmix64(splitmix64(tag.wrapping_mul(0x0100_0000_01B3) ^ a) ^ b)
+}
+
+#[inline]
+fn square(position: &Position) -> u64 {
+    ((position.1 as u64) << 8) | position.0 as u64
+}
+
+/// 흑 차례일 때 XOR되는 키.
+pub const TURN_KEY: u64 = splitmix64(0x5EED);
+
+#[inline]
+pub fn piece_key(id: u64, color: Color, position: &Position) -> u64 {
+    key(TAG_PIECE, id.wrapping_mul(4) ^ color as u64, square(position))
+}
+
+#[inline]
+pub fn castling_key(color: Color, long: bool) -> u64 {
+    key(TAG_CASTLING, color as u64, long as u64)
+}
+
+#[inline]
+pub fn enpassant_key(color: Color, position: &Position) -> u64 {
+    key(TAG_ENPASSANT, color as u64, square(position))
+}
+
+#[inline]
+pub fn checks_key(color: Color, checks: u8) -> u64 {
+    if checks == 0 { 0 } else { key(TAG_CHECKS, color as u64, checks as u64) }
+}
+
+#[inline]
+pub fn register_key(color: Color, name: &str, value: u8) -> u64 {
+    if value == 0 { 0 } else { key(TAG_REGISTER, fnv1a(name) ^ color as u64, value as u64) }
+}
+
+/// 차례 안의 단계 키. 첫 단계(0)는 0입니다.
+#[inline]
+pub fn phase_key(phase: u8) -> u64 {
+    if phase == 0 { 0 } else { key(TAG_PHASE, phase as u64, 0) }
+}
+
+/// 차례 안에 남은 수의 키. 한 수만 남았으면(표준 체스) 0입니다.
+#[inline]
+pub fn moves_left_key(moves_left: u8) -> u64 {
+    if moves_left <= 1 { 0 } else { key(TAG_MOVES_LEFT, moves_left as u64, 0) }
+}
+
+/// 굴린 주사위 면의 키. 아직 굴리지 않았으면 0입니다.
+#[inline]
+pub fn roll_key(roll: Option<u8>) -> u64 {
+    match roll {
+        Some(face) => key(TAG_ROLL, face as u64, 0),
+        None => 0,
+    }
+}
+
+/// 칸 표식의 키. 이름·진영·칸·남은 플라이 수(`None`은 영구)를 모두 반영합니다.
+#[inline]
+pub fn marker_key(name: &str, owner: Color, position: &Position, expires: Option<u8>) -> u64 {
+    let expires = expires.map_or(0, |x| x as u64 + 1);
+    key(TAG_MARKER, fnv1a(name).wrapping_mul(4) ^ owner as u64, (square(position) << 16) | expires)
+}
+
+/// 보관함에 `name` 기물이 `count`개 있을 때의 키. 0개면 0입니다.
+#[inline]
+pub fn reserve_key(color: Color, name: &str, count: usize) -> u64 {
+    if count == 0 { 0 } else { key(TAG_RESERVE, fnv1a(name).wrapping_mul(4) ^ color as u64, count as u64) }
+}
@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+
+use super::{Color, Position, SquareSet};
+
+// -----------------------------------------------------------------------------
+// 이름 붙은 구역 (궁성, 강, 승격 구역 등)
+// 스크립트의 `declare-zone(name, color, x/y, x/y-x/y, ...)` 체인으로 선언하며
+// 색마다 따로 칸 집합을 가집니다. color는 white, black, both 중 하나입니다.
+// -----------------------------------------------------------------------------
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ZoneMap<'a> {
+    zones: HashMap<&'a str, [SquareSet; 2]>,
+}
+
+impl<'a> ZoneMap<'a> {
+    /// `declare-zone(...)`의 괄호 안 인자를 해석해 구역에 칸을 더합니다.
+    /// `x/y`는 한 칸, `x1/y1-x2/y2`는 두 칸을 꼭짓점으로 하는 직사각형입니다.
+    pub fn declare(&mut self, params: &'a str) {
+        let mut params = params.split(',').map(|x| x.trim());
+        let Some(name) = params.next().filter(|x| !x.is_empty()) else {
+            return;
+        };
+        let colors: &[Color] = match params.next() {
+            Some("white") => &[Color::White],
+            Some("black") => &[Color::Black],
+            _ => &[Color::White, Color::Black],
+        };
+        let parse = |sq: &str| {
+            let (x, y) = sq.split_once('/')?;
+            Some((x.trim().parse::<u8>().ok()?, y.trim().parse::<u8>().ok()?))
+        };
+        let mut squares = SquareSet::EMPTY;
+        for param in params {
+            let (from, to) = match param.split_once('-') {
+                Some((from, to)) => (parse(from), parse(to)),
+                None => (parse(param), parse(param)),
+            };
+            let (Some(from), Some(to)) = (from, to) else {
+                continue;
+            };
+            for y in from.1.min(to.1)..=from.1.max(to.1) {
+                for x in from.0.min(to.0)..=from.0.max(to.0) {
+                    if (x as usize) < super::MAX_BOARD_SIZE && (y as usize) < super::MAX_BOARD_SIZE {
+                        squares.insert(&(x, y));
+                    }
+                }
+            }
+        }
+        let zone = self.zones.entry(name).or_insert([SquareSet::EMPTY; 2]);
+        for color in colors {
+            zone[*color as usize] |= squares;
+        }
+    }
+
+    /// `color` 진영 기준으로 `position`이 `name` 구역에 속하는지 확인합니다. 없는 구역이나 중립 색이면 false.
+    #[inline]
+    pub fn contains(&self, name: &str, color: Color, position: &Position) -> bool {
+        self.zones.get(name).and_then(|zone| zone.get(color as usize)).is_some_and(|set| set.contains(position))
+    }
+
+    /// `name` 구역이 선언되었는지.
+    #[inline]
+    pub fn is_declared(&self, name: &str) -> bool {
+        self.zones.contains_key(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::board::{Board, BoardStatus};
+    use super::super::rules::Rules;
+    use super::super::{ChessemblyCompiled, MoveGen, Piece, PieceSpan};
+    use super::*;
+
+    const SCRIPT: &str = "declare-zone(home, white, 0/6-7/7); declare-zone(home, black, 0/0-7/1); \
+        declare-zone(goal, white, 0/0-7/0); declare-zone(goal, black, 0/7-7/7); declare-zone(middle, both, 3/3-4/4); \
+        piece(walker) zone(home) move(0, 1); piece(walker) in-zone(middle, 0, 1) move(0, 1);";
+
+    fn board_with<'a>(
+        compiled: &'a ChessemblyCompiled<'a>,
+        rules: &'a Rules<'a>,
+        pieces: &[(&'a str, Position, Color)],
+    ) -> Board<'a, 8> {
+        let mut board = Board::<8>::empty(compiled);
+        board.rules = rules;
+        for &(piece, (x, y), color) in pieces {
+            board.board[y as usize][x as usize] = PieceSpan::Piece(Piece::new(piece, color, &compiled.pieces));
+        }
+        board.rebuild_occupancy();
+        board.hash = board.zobrist();
+        board
+    }
+
+    #[test]
+    fn zones_are_per_color() {
+        let compiled = ChessemblyCompiled::from_script(SCRIPT).unwrap();
+        let zones = &compiled.zones;
+        assert!(zones.contains("home", Color::White, &(0, 7)));
+        assert!(!zones.contains("home", Color::Black, &(0, 7)));
+        assert!(zones.contains("home", Color::Black, &(7, 0)));
+        assert!(!zones.contains("home", Color::White, &(7, 0)));
+        for color in [Color::White, Color::Black] {
+            assert!(zones.contains("middle", color, &(4, 3)));
+            assert!(!zones.contains("middle", color, &(5, 3)));
+        }
+        assert!(!zones.contains("middle", Color::Neutral, &(4, 3)));
+        assert!(!zones.contains("nowhere", Color::White, &(4, 3)));
+    }
+
+    #[test]
+    fn zone_conditions_follow_mover_color() {
+        let compiled = ChessemblyCompiled::from_script(SCRIPT).unwrap();
+        let rules = Rules::parse("non-royal-king");
+        let can_move = |from: Position, color: Color| {
+            let mut board = board_with(&compiled, &rules, &[("walker", from, color)]);
+            board.turn = color;
+            !MoveGen::new_legal(&mut board).is_empty()
+        };
+        // zone: 자기 색의 home 안에서만 움직입니다.
+        assert!(can_move((3, 6), Color::White));
+        assert!(!can_move((3, 1), Color::White));
+        assert!(can_move((3, 1), Color::Black));
+        assert!(!can_move((3, 6), Color::Black));
+        // in-zone: 한 칸 앞이 middle일 때만 움직입니다. 백은 위로, 흑은 아래로 나아갑니다.
+        assert!(can_move((3, 5), Color::White));
+        assert!(!can_move((3, 2), Color::White));
+        assert!(can_move((3, 2), Color::Black));
+        assert!(!can_move((3, 5), Color::Black));
+    }
+
+    #[test]
+    fn reach_zone_wins() {
+        let compiled = ChessemblyCompiled::from_script(SCRIPT).unwrap();
+        let rules = Rules::parse("reach-zone(king, goal)");
+        // 백의 goal은 맨 윗줄, 흑의 goal은 맨 아랫줄입니다.
+        let board = board_with(&compiled, &rules, &[("king", (4, 0), Color::White), ("king", (4, 3), Color::Black)]);
+        assert_eq!(board.check_win_conditions(Color::White), Some(Color::White));
+        let board = board_with(&compiled, &rules, &[("king", (4, 7), Color::White), ("king", (4, 0), Color::Black)]);
+        assert_eq!(board.check_win_conditions(Color::White), None);
+        let board = board_with(&compiled, &rules, &[("king", (4, 4), Color::White), ("king", (4, 7), Color::Black)]);
+        assert_eq!(board.check_win_conditions(Color::White), Some(Color::Black));
+
+        // 수를 두어 도달해도 바로 끝납니다.
+        let mut board = board_with(&compiled, &rules, &[("king", (4, 1), Color::White), ("king", (4, 5), Color::Black)]);
+        let node = MoveGen::new_legal(&mut board).into_iter().find(|m| m.get_dest() == (4, 0)).unwrap();
+        assert_eq!(board.make_move_new(&node).status, BoardStatus::Victory(Color::White));
+    }
+}
@@ -5,9 +5,14 @@ pub mod game_logic {
     use crate::chessembly;
     use chessembly::board::Board;
     use chessembly::board::BoardStatus;
+    use chessembly::board::MoveUndo;
     use chessembly::ChessMove;
     use chessembly::MoveGen;
     use chessembly::Color;
+    use chessembly::ids;
+    use chessembly::hex::{HEX_DIAGONAL, HEX_KNIGHT, HEX_ORTHOGONAL};
+    use chessembly::rules::{Grid, WinCondition};
+    use chessembly::four_player::{FourPlayerBoard, FourPlayerMode, FourPlayerStatus, Player, FOUR_PLAYER_SIZE};
     use crate::engine_huristic::heuristics;
 
     /// 모든 게임의 '수'가 구현해야 하는 기본 트레이트.
@@ -34,9 +39,15 @@ pub mod game_logic {
     /// 이 트레이트만 구현하면 어떤 게임이든 우리 검색 알고리즘을 쓸 수 있습니다.
     pub trait GameState: Clone {
         type Move: GameMove;
+        /// `make_move_in_place`가 돌려주는 되돌리기 기록.
+        type Undo;
 
         fn get_legal_moves(&mut self) -> Vec<Self::Move>;
         fn make_move(&self, m: &Self::Move) -> Self;
+        /// 상태를 복사하지 않고 수를 둡니다. 검색은 이것과 `unmake_move`로 트리를 내려갔다 돌아옵니다.
+        fn make_move_in_place(&mut self, m: &Self::Move) -> Self::Undo;
+        /// 가장 최근에 `make_move_in_place`로 둔 수를 되돌립니다.
+        fn unmake_move(&mut self, undo: Self::Undo);
         fn is_terminal(&self) -> bool;
         fn evaluate(&mut self) -> i32;
 
@@ -53,6 +64,19 @@ pub mod game_logic {
             self.score_move(m) > 500
         }
 
+        /// 시끄러운 수(`is_capture`)만. 검색은 이것부터 탐색하고, quiescence search는 이것만 씁니다.
+        /// 기본 구현은 모든 합법 수를 걸러 냅니다. 수 생성 단계에서 나눌 수 있으면 오버라이드하세요.
+        fn get_loud_moves(&mut self) -> Vec<Self::Move> {
+            let moves = self.get_legal_moves();
+            moves.into_iter().filter(|m| self.is_capture(m)).collect()
+        }
+
+        /// 조용한 수(`is_capture`가 아닌 수)만. 시끄러운 수로 컷오프가 나지 않았을 때만 만듭니다.
+        fn get_quiet_moves(&mut self) -> Vec<Self::Move> {
+            let moves = self.get_legal_moves();
+            moves.into_iter().filter(|m| !self.is_capture(m)).collect()
+        }
+
         /// Delta pruning용 loud move 재료 이득 추정값 (센티폰).
         /// `i32::MAX`를 반환하면 해당 수는 항상 탐색됩니다(delta pruning 비활성).
         /// 구체적 게임 구현체에서 캡처·프로모션의 예상 재료 이득을 반환하도록 오버라이드하세요.
@@ -64,27 +88,65 @@ pub mod game_logic {
         /// 양수/0: 유리하거나 동등한 교환, 음수: 손해 교환.
         /// 기본값 `i32::MAX`는 항상 탐색 (SEE 프루닝 비활성).
         fn static_exchange_evaluation_move(&self, _m: &Self::Move) -> i32 { i32::MAX }
+
+        /// `next`에서도 같은 쪽이 수를 두는지 (여러 단계로 이루어진 차례).
+        /// true면 검색은 점수 부호를 뒤집지 않고 같은 창으로 자식 노드를 탐색합니다.
+        fn keeps_turn(&self, _next: &Self) -> bool { false }
+
+        /// `make_move_in_place`로 둔 뒤에도 같은 쪽이 두는지 (`keeps_turn`의 제자리판).
+        fn kept_turn(&self, _undo: &Self::Undo) -> bool { false }
+
+        /// 우연 노드(주사위 등)면 가능한 결과 국면과 확률 목록. 비어 있으면 수를 두는 노드입니다.
+        /// 결과 국면의 부호는 `keeps_turn`으로 정합니다 (expectimax 검색에서만 사용).
+        fn chance_outcomes(&self) -> Vec<(Self, f32)> { Vec::new() }
+    }
+
+    /// 세 명 이상이 돌아가며 두는 게임(4인 체스 등)의 상태 트레이트.
+    /// 점수는 플레이어마다 따로 매기므로 네가맥스 대신 paranoid 검색을 씁니다.
+    pub trait MultiPlayerState: Clone {
+        type Move: GameMove;
+
+        fn get_legal_moves(&mut self) -> Vec<Self::Move>;
+        fn make_move(&self, m: &Self::Move) -> Self;
+        fn is_terminal(&self) -> bool;
+        /// 차례인 플레이어 번호.
+        fn current_player(&self) -> usize;
+        /// `player` 시점의 평가 점수 (높을수록 유리).
+        fn evaluate_for(&mut self, player: usize) -> i32;
+        fn score_move(&self, m: &Self::Move) -> i32;
+        /// 두 플레이어가 같은 편인지. 기본값은 자기 자신만 같은 편 (개인전).
+        fn is_ally(&self, a: usize, b: usize) -> bool { a == b }
     }
 
     // -------------------------------------------------------------------------
     // Board 평가 헬퍼 메서드
     // (engine_huristic::heuristics의 순수 함수를 조합해 보드 전체를 평가합니다)
     // -------------------------------------------------------------------------
-    impl<'a, const MACHO: bool, const IMPRISONED: bool, const SIZE: usize> Board<'a, MACHO, IMPRISONED, SIZE> {
+    impl<'a, const SIZE: usize> Board<'a, SIZE> {
+        /// 칸에 있는 기물의 센티폰 가치. 내장 기물은 ID로 바로 찾고, 스크립트 기물만 이름을 봅니다.
+        #[inline]
+        fn piece_value_on(&self, position: &(u8, u8)) -> Option<i32> {
+            let piece = self.piece_id_on(position)?;
+            Some(heuristics::get_piece_value_id(piece).unwrap_or_else(|| heuristics::get_piece_value(self.piece_on(position).unwrap())))
+        }
+
         /// 모든 기물의 센티폰 가치 합산. 반환값: 백 절대 시점 (양수 = 백 우세).
         fn evaluate_material(&self) -> i32 {
             let mut score = 0;
-            for x in 0..SIZE as u8 {
-                for y in 0..SIZE as u8 {
-                    if let Some(piece) = self.piece_on(&(x, y)) {
-                        let is_white = self.color_on(&(x, y)) == Some(Color::White);
-                        let value = heuristics::get_piece_value(piece);
-                        let pst = heuristics::pst_bonus(piece, is_white, x, y);
-                        if is_white {
-                            score += value + pst;
-                        } else {
-                            score -= value + pst;
-                        }
+            // 중립 기물은 어느 쪽 점수에도 들어가지 않습니다.
+            for color in [Color::White, Color::Black] {
+                let is_white = color == Color::White;
+                for (x, y) in self.pieces_of(color).iter() {
+                    let piece = self.piece_id_on(&(x, y)).unwrap();
+                    let value = self.piece_value_on(&(x, y)).unwrap_or(0);
+                    // 흑은 실제 보드에서 먼저 미러링한 뒤 8×8 테이블 좌표로 환산합니다.
+                    let own_y = if is_white { y } else { self.get_height() as u8 - 1 - y };
+                    let (px, py) = self.std_square((x, own_y));
+                    let pst = heuristics::pst_bonus_id(piece, true, px, py);
+                    if is_white {
+                        score += value + pst;
+                    } else {
+                        score -= value + pst;
                     }
                 }
             }
@@ -97,12 +159,12 @@ pub mod game_logic {
             if let Some((kx, ky)) = self.find_king(Color::White) {
                 let shield = self.count_pawn_shield(kx, ky, Color::White);
                 score += heuristics::pawn_shield_bonus(shield);
-                score -= heuristics::king_center_penalty(kx);
+                score -= heuristics::king_center_penalty(self.std_square((kx, ky)).0);
             }
             if let Some((kx, ky)) = self.find_king(Color::Black) {
                 let shield = self.count_pawn_shield(kx, ky, Color::Black);
                 score -= heuristics::pawn_shield_bonus(shield);
-                score += heuristics::king_center_penalty(kx);
+                score += heuristics::king_center_penalty(self.std_square((kx, ky)).0);
             }
             score
         }
@@ -110,14 +172,12 @@ pub mod game_logic {
         /// 전방에 적 폰이 없는 폰에 랭크 기반 보너스. 반환값: 백 절대 시점.
         fn evaluate_passed_pawns(&self) -> i32 {
             let mut score = 0;
-            for x in 0..SIZE as u8 {
-                for y in 0..SIZE as u8 {
-                    if self.piece_on(&(x, y)) != Some("pawn") { continue; }
-                    let Some(color) = self.color_on(&(x, y)) else { continue; };
+            for color in [Color::White, Color::Black] {
+                for (x, y) in self.pawns_of(color).iter() {
                     if self.is_passed_pawn(x, y, color) {
-                        // 홈 랭크 기준 전진 수: 백 홈=y6, 흑 홈=y1
+                        // 홈 랭크 기준 전진 수: 백 홈=y(height-2), 흑 홈=y1
                         let ranks_advanced = if color == Color::White {
-                            6u8.saturating_sub(y)
+                            (self.get_height() as u8 - 2).saturating_sub(y)
                         } else {
                             y.saturating_sub(1)
                         };
@@ -129,15 +189,14 @@ pub mod game_logic {
             score
         }
 
-        /// d4/e4/d5/e5 점령 보너스. 반환값: 백 절대 시점.
+        /// 중앙 칸(8×8에서는 d4/e4/d5/e5) 점령 보너스. 반환값: 백 절대 시점.
         fn evaluate_center_control(&self) -> i32 {
             let mut score = 0;
-            for &x in &[3u8, 4u8] {
-                for &y in &[3u8, 4u8] {
-                    if let Some(color) = self.color_on(&(x, y)) {
-                        let bonus = heuristics::center_control_bonus(x, y);
-                        if color == Color::White { score += bonus; } else { score -= bonus; }
-                    }
+            for (x, y) in (self.center_squares() & self.occupied()).iter() {
+                if let Some(color) = self.color_on(&(x, y)).filter(|c| *c != Color::Neutral) {
+                    let (cx, cy) = self.std_square((x, y));
+                    let bonus = heuristics::center_control_bonus(cx, cy);
+                    if color == Color::White { score += bonus; } else { score -= bonus; }
                 }
             }
             score
@@ -155,17 +214,17 @@ pub mod game_logic {
 
         // --- 유틸리티 헬퍼 ------------------------------------------------------
 
+        /// 8×8 기준 휴리스틱 테이블을 쓰기 위해 실제 보드 좌표를 8×8 격자로 환산합니다.
+        fn std_square(&self, (x, y): (u8, u8)) -> (u8, u8) {
+            (
+                (x as usize * 8 / self.get_width()) as u8,
+                (y as usize * 8 / self.get_height()) as u8,
+            )
+        }
+
+        /// `color`의 킹. 여럿이면 가장 왼쪽 파일(같으면 위쪽)의 킹입니다.
         fn find_king(&self, color: Color) -> Option<(u8, u8)> {
-            for x in 0..SIZE as u8 {
-                for y in 0..SIZE as u8 {
-                    if self.piece_on(&(x, y)) == Some("king")
-                        && self.color_on(&(x, y)) == Some(color)
-                    {
-                        return Some((x, y));
-                    }
-                }
-            }
-            None
+            self.kings_of(color).iter().min()
         }
 
         /// 킹 앞 1~2랭크, 좌우 1파일 내의 아군 폰 수.
@@ -177,8 +236,8 @@ pub mod game_logic {
                 for dist in 1i8..=2 {
                     let nx = kx as i8 + dx;
                     let ny = ky as i8 + dy * dist;
-                    if nx < 0 || nx > (SIZE - 1) as i8 || ny < 0 || ny > (SIZE - 1) as i8 { continue; }
-                    if self.piece_on(&(nx as u8, ny as u8)) == Some("pawn")
+                    if nx < 0 || nx >= self.get_width() as i8 || ny < 0 || ny >= self.get_height() as i8 { continue; }
+                    if self.piece_id_on(&(nx as u8, ny as u8)) == Some(ids::PAWN)
                         && self.color_on(&(nx as u8, ny as u8)) == Some(color)
                     {
                         count += 1;
@@ -193,20 +252,13 @@ pub mod game_logic {
             let enemy = color.invert();
             let (y_start, y_end): (u8, u8) = match color {
                 Color::White => (0, py.saturating_sub(1)),
-                Color::Black => (py + 1, SIZE as u8 - 1),
+                Color::Black => (py + 1, self.get_height() as u8 - 1),
+                Color::Neutral => return false,
             };
-            // y_start > y_end 이면 범위가 비어 루프를 돌지 않습니다(u8 안전).
+            // y_start > y_end 이면 범위가 비어 있습니다(u8 안전).
             if y_start > y_end { return true; }
-            for x in px.saturating_sub(1)..=(px + 1).min(SIZE as u8 - 1) {
-                for y in y_start..=y_end {
-                    if self.piece_on(&(x, y)) == Some("pawn")
-                        && self.color_on(&(x, y)) == Some(enemy)
-                    {
-                        return false;
-                    }
-                }
-            }
-            true
+            let files = px.saturating_sub(1)..=(px + 1).min(self.get_width() as u8 - 1);
+            !self.pawns_of(enemy).iter().any(|(x, y)| files.contains(&x) && (y_start..=y_end).contains(&y))
         }
 
         /// `sq`를 공격하는 `color` 진영 기물의 (가치, 위치) 목록을 반환합니다.
@@ -214,79 +266,91 @@ pub mod game_logic {
         fn get_attackers_of(&self, sq: (u8, u8), color: Color) -> Vec<(i32, (u8, u8))> {
             let mut result = Vec::new();
             let (sx, sy) = (sq.0 as i8, sq.1 as i8);
+            // 위상에 맞춰 좌표를 감싼 뒤 둘 수 있는 칸이면 반환합니다.
+            let at = |x: i8, y: i8| {
+                let (x, y) = self.wrap(x as i16, y as i16);
+                (x >= 0 && y >= 0 && self.is_playable(&(x as u8, y as u8))).then_some((x as u8, y as u8))
+            };
+            let limit = self.get_width() * self.get_height();
+
+            // 저장 좌표 기준 오프셋 (y는 아래로 증가). 육각 보드는 축 좌표 방향을 씁니다.
+            let hex = matches!(self.rules.grid, Grid::Hex(_));
+            let axial = |deltas: &[(i8, i8)]| deltas.iter().map(|&(dx, dy)| (dx, -dy)).collect::<Vec<_>>();
+            let (pawns, knights, diagonals, orthogonals) = if hex {
+                let pawns = if color == Color::White { vec![(1, 0), (-1, 1)] } else { vec![(1, -1), (-1, 0)] };
+                (pawns, axial(&HEX_KNIGHT), axial(&HEX_DIAGONAL), axial(&HEX_ORTHOGONAL))
+            } else {
+                // 폰: 백은 아래(sy+1)에서, 흑은 위(sy-1)에서 대각 공격
+                let pawn_dy: i8 = if color == Color::White { 1 } else { -1 };
+                (
+                    vec![(-1, pawn_dy), (1, pawn_dy)],
+                    vec![(-2, -1), (-2, 1), (-1, -2), (-1, 2), (1, -2), (1, 2), (2, -1), (2, 1)],
+                    vec![(-1, -1), (-1, 1), (1, -1), (1, 1)],
+                    vec![(-1, 0), (1, 0), (0, -1), (0, 1)],
+                )
+            };
 
-            // 폰: 백은 아래(sy+1)에서, 흑은 위(sy-1)에서 대각 공격
-            let pawn_dy: i8 = if color == Color::White { 1 } else { -1 };
-            for &dx in &[-1i8, 1i8] {
-                let (px, py) = (sx + dx, sy + pawn_dy);
-                if px >= 0 && px < SIZE as i8 && py >= 0 && py < SIZE as i8 {
-                    let pos = (px as u8, py as u8);
-                    if self.piece_on(&pos) == Some("pawn") && self.color_on(&pos) == Some(color) {
+            // 폰
+            for &(dx, dy) in &pawns {
+                if let Some(pos) = at(sx + dx, sy + dy) {
+                    if self.piece_id_on(&pos) == Some(ids::PAWN) && self.color_on(&pos) == Some(color) {
                         result.push((100, pos));
                     }
                 }
             }
 
             // 나이트
-            for &(dx, dy) in &[(-2i8,-1i8),(-2,1),(-1,-2),(-1,2),(1,-2),(1,2),(2,-1),(2,1)] {
-                let (px, py) = (sx + dx, sy + dy);
-                if px >= 0 && px < SIZE as i8 && py >= 0 && py < SIZE as i8 {
-                    let pos = (px as u8, py as u8);
-                    if self.piece_on(&pos) == Some("knight") && self.color_on(&pos) == Some(color) {
+            for &(dx, dy) in &knights {
+                if let Some(pos) = at(sx + dx, sy + dy) {
+                    if self.piece_id_on(&pos) == Some(ids::KNIGHT) && self.color_on(&pos) == Some(color) {
                         result.push((320, pos));
                     }
                 }
             }
 
             // 비숍·퀀 대각선
-            for &(dx, dy) in &[(-1i8,-1i8),(-1,1),(1,-1),(1,1)] {
-                let (mut px, mut py) = (sx + dx, sy + dy);
-                while px >= 0 && px < SIZE as i8 && py >= 0 && py < SIZE as i8 {
-                    let pos = (px as u8, py as u8);
-                    if let Some(p) = self.piece_on(&pos) {
+            for &(dx, dy) in &diagonals {
+                let mut cur = at(sx + dx, sy + dy);
+                for _ in 0..limit {
+                    let Some(pos) = cur else { break };
+                    if let Some(p) = self.piece_id_on(&pos) {
                         if self.color_on(&pos) == Some(color) {
                             match p {
-                                "bishop" => result.push((330, pos)),
-                                "queen"  => result.push((900, pos)),
+                                ids::BISHOP => result.push((330, pos)),
+                                ids::QUEEN  => result.push((900, pos)),
                                 _        => {}
                             }
                         }
                         break;
                     }
-                    px += dx; py += dy;
+                    cur = at(pos.0 as i8 + dx, pos.1 as i8 + dy);
                 }
             }
 
             // 룩·퀀 직선
-            for &(dx, dy) in &[(-1i8,0i8),(1,0),(0,-1),(0,1)] {
-                let (mut px, mut py) = (sx + dx, sy + dy);
-                while px >= 0 && px < SIZE as i8 && py >= 0 && py < SIZE as i8 {
-                    let pos = (px as u8, py as u8);
-                    if let Some(p) = self.piece_on(&pos) {
+            for &(dx, dy) in &orthogonals {
+                let mut cur = at(sx + dx, sy + dy);
+                for _ in 0..limit {
+                    let Some(pos) = cur else { break };
+                    if let Some(p) = self.piece_id_on(&pos) {
                         if self.color_on(&pos) == Some(color) {
                             match p {
-                                "rook"  => result.push((500, pos)),
-                                "queen" => result.push((900, pos)),
+                                ids::ROOK  => result.push((500, pos)),
+                                ids::QUEEN => result.push((900, pos)),
                                 _       => {}
                             }
                         }
                         break;
                     }
-                    px += dx;
-                    py += dy;
+                    cur = at(pos.0 as i8 + dx, pos.1 as i8 + dy);
                 }
             }
 
             // 킹
-            for dx in -1i8..=1 {
-                for dy in -1i8..=1 {
-                    if dx == 0 && dy == 0 { continue; }
-                    let (px, py) = (sx + dx, sy + dy);
-                    if px >= 0 && px < SIZE as i8 && py >= 0 && py < SIZE as i8 {
-                        let pos = (px as u8, py as u8);
-                        if self.piece_on(&pos) == Some("king") && self.color_on(&pos) == Some(color) {
-                            result.push((20_000, pos));
-                        }
+            for &(dx, dy) in diagonals.iter().chain(orthogonals.iter()) {
+                if let Some(pos) = at(sx + dx, sy + dy) {
+                    if self.piece_id_on(&pos) == Some(ids::KING) && self.color_on(&pos) == Some(color) {
+                        result.push((20_000, pos));
                     }
                 }
             }
@@ -300,13 +364,8 @@ pub mod game_logic {
             let Some(our_color) = self.color_on(&from) else { return 0; };
             let opp_color = our_color.invert();
 
-            let captured_val = match self.piece_on(&to) {
-                Some(p) => heuristics::get_piece_value(p),
-                None    => return 0,
-            };
-            let attacker_val = heuristics::get_piece_value(
-                self.piece_on(&from).unwrap_or("pawn")
-            );
+            let Some(captured_val) = self.piece_value_on(&to) else { return 0; };
+            let attacker_val = self.piece_value_on(&from).unwrap_or(heuristics::get_piece_value("pawn"));
 
             // 우리 측 후속 공격자 (from 제외, 오름차순)
             let mut our_atts: Vec<i32> = self.get_attackers_of(to, our_color)
@@ -361,8 +420,9 @@ pub mod game_logic {
     }
 
     // --- 표준 체스를 위한 GameState 구현 -------------------------------------
-    impl<'a, const MACHO: bool, const IMPRISONED: bool, const SIZE: usize> GameState for Board<'a, MACHO, IMPRISONED, SIZE> {
+    impl<'a, const SIZE: usize> GameState for Board<'a, SIZE> {
         type Move = ChessMove<'a>;
+        type Undo = MoveUndo<'a>;
 
         fn get_legal_moves(&mut self) -> Vec<Self::Move> {
             MoveGen::new_legal(self)
@@ -372,6 +432,14 @@ pub mod game_logic {
             self.make_move_new(&m)
         }
 
+        fn make_move_in_place(&mut self, m: &Self::Move) -> Self::Undo {
+            Board::make_move_in_place(self, m, true)
+        }
+
+        fn unmake_move(&mut self, undo: Self::Undo) {
+            Board::unmake_move(self, undo)
+        }
+
         fn is_terminal(&self) -> bool {
             self.status() != BoardStatus::Ongoing
         }
@@ -382,12 +450,17 @@ pub mod game_logic {
                 return match self.status() {
                     BoardStatus::Checkmate => -1_000_000,
                     BoardStatus::Stalemate => 0,
+                    BoardStatus::Draw => 0,
+                    BoardStatus::Victory(winner) => {
+                        if winner == self.side_to_move() { 1_000_000 } else { -1_000_000 }
+                    }
                     _ => 0,
                 };
             }
 
-            // 2. 보드 전체 평가 (백 절대 시점)
+            // 2. 보드 전체 평가 (백 절대 시점). 기물을 잃어야 이기는 변형은 부호를 뒤집습니다.
             let score = self.evaluate_board();
+            let score = if self.rules.win_conditions.contains(&WinCondition::LoseAll) { -score } else { score };
 
             // 3. 현재 플레이어 시점으로 변환
             if self.side_to_move() == Color::White { score } else { -score }
@@ -405,40 +478,41 @@ pub mod game_logic {
             score += self.static_exchange_evaluation_move(m);
 
             // 3. 캡처 (MVV-LVA)
-            if let Some(victim) = self.piece_on(&m.get_dest()) {
-                let attacker = self.piece_on(&m.get_source()).unwrap_or("pawn");
+            if let Some(victim) = self.piece_value_on(&m.get_dest()) {
+                let attacker = self.piece_value_on(&m.get_source()).unwrap_or(heuristics::get_piece_value("pawn"));
                 score += heuristics::score_capture_mvv_lva(attacker, victim);
             }
 
             // 4. 센터 접근 보너스
-            score += heuristics::score_center_approach(m.get_source(), m.get_dest());
+            score += heuristics::score_center_approach(
+                self.std_square(m.get_source()),
+                self.std_square(m.get_dest()),
+            );
 
             // NOTE: killer/history 보너스는 search 모듈에서 KillerTable/HistoryTable로 가산.
             score
         }
 
         fn board_hash(&self) -> u64 {
-            use std::hash::{Hash, Hasher};
-            use std::collections::hash_map::DefaultHasher;
-            let mut h = DefaultHasher::new();
-            for x in 0..SIZE as u8 {
-                for y in 0..SIZE as u8 {
-                    self.piece_on(&(x, y)).hash(&mut h);
-                    self.color_on(&(x, y)).hash(&mut h);
-                }
-            }
-            self.side_to_move().hash(&mut h);
-            h.finish()
+            self.hash
         }
 
         fn is_capture(&self, m: &Self::Move) -> bool {
-            self.piece_on(&m.get_dest()).is_some() || m.get_promotion().is_some()
+            MoveGen::is_loud(self, m)
+        }
+
+        fn get_loud_moves(&mut self) -> Vec<Self::Move> {
+            MoveGen::new_legal_staged(self, true)
+        }
+
+        fn get_quiet_moves(&mut self) -> Vec<Self::Move> {
+            MoveGen::new_legal_staged(self, false)
         }
 
         fn loud_move_gain(&self, m: &Self::Move) -> i32 {
             let mut gain = 0i32;
-            if let Some(victim) = self.piece_on(&m.get_dest()) {
-                gain += heuristics::get_piece_value(victim);
+            if let Some(victim) = self.piece_value_on(&m.get_dest()) {
+                gain += victim;
             }
             if let Some(promo) = m.get_promotion() {
                 gain += heuristics::get_piece_value(promo)
@@ -450,6 +524,128 @@ pub mod game_logic {
         fn static_exchange_evaluation_move(&self, m: &Self::Move) -> i32 {
             self.static_exchange_evaluation(m.get_source(), m.get_dest())
         }
+
+        fn keeps_turn(&self, next: &Self) -> bool {
+            next.side_to_move() == self.side_to_move()
+        }
+
+        fn kept_turn(&self, undo: &Self::Undo) -> bool {
+            undo.mover() == self.side_to_move()
+        }
+
+        fn chance_outcomes(&self) -> Vec<(Self, f32)> {
+            if !self.awaits_roll() {
+                return Vec::new();
+            }
+            self.roll_outcomes()
+                .into_iter()
+                .map(|(face, chance)| {
+                    let mut next = self.clone_without_dp();
+                    next.roll_dice(face);
+                    (next, chance)
+                })
+                .collect()
+        }
+    }
+
+    // --- 4인 체스를 위한 MultiPlayerState 구현 --------------------------------
+    impl<'a> FourPlayerBoard<'a> {
+        /// 칸에 있는 기물의 센티폰 가치 (`Board::piece_value_on`과 같은 방식).
+        #[inline]
+        fn piece_value_on(&self, position: &(u8, u8)) -> Option<i32> {
+            let (piece, _) = self.piece_id_on(position)?;
+            Some(heuristics::get_piece_value_id(piece).unwrap_or_else(|| heuristics::get_piece_value(self.piece_on(position).unwrap().0)))
+        }
+
+        /// `player`의 재료 점수 + 폰 전진 보너스. 탈락한 플레이어는 0.
+        fn player_material(&self, player: Player) -> i32 {
+            if !self.is_active(player) {
+                return 0;
+            }
+            let mut score = 0;
+            for y in 0..FOUR_PLAYER_SIZE {
+                for x in 0..FOUR_PLAYER_SIZE {
+                    let Some((piece, owner)) = self.piece_id_on(&(x, y)) else { continue; };
+                    if owner != player { continue; }
+                    score += self.piece_value_on(&(x, y)).unwrap_or(0);
+                    if piece == ids::PAWN {
+                        // 시점 보드에서 홈 폰 랭크(12) 기준 전진 수
+                        score += 10 * (FOUR_PLAYER_SIZE as i32 - 2 - player.to_view((x, y)).1 as i32);
+                    }
+                }
+            }
+            score
+        }
+    }
+
+    impl<'a> MultiPlayerState for FourPlayerBoard<'a> {
+        type Move = ChessMove<'a>;
+
+        fn get_legal_moves(&mut self) -> Vec<Self::Move> {
+            self.legal_moves(self.turn)
+        }
+
+        fn make_move(&self, m: &Self::Move) -> Self {
+            FourPlayerBoard::make_move(self, m)
+        }
+
+        fn is_terminal(&self) -> bool {
+            self.status != FourPlayerStatus::Ongoing
+        }
+
+        fn current_player(&self) -> usize {
+            self.turn as usize
+        }
+
+        fn evaluate_for(&mut self, player: usize) -> i32 {
+            let me = Player::ALL[player];
+            match self.status {
+                FourPlayerStatus::TeamWins(team) => {
+                    return if team == me.team() { 1_000_000 } else { -1_000_000 };
+                }
+                FourPlayerStatus::PlayerWins(winner) => {
+                    return if winner == me { 1_000_000 } else { -1_000_000 };
+                }
+                FourPlayerStatus::Draw => return 0,
+                FourPlayerStatus::Ongoing => {}
+            }
+            if !self.is_active(me) {
+                return -1_000_000;
+            }
+            match self.mode {
+                // 팀 재료 차이
+                FourPlayerMode::Teams => Player::ALL
+                    .into_iter()
+                    .map(|p| if p.team() == me.team() { self.player_material(p) } else { -self.player_material(p) })
+                    .sum(),
+                // 내 재료 - 살아 있는 상대들의 평균 재료
+                FourPlayerMode::FreeForAll => {
+                    let others: Vec<i32> = Player::ALL
+                        .into_iter()
+                        .filter(|p| *p != me && self.is_active(*p))
+                        .map(|p| self.player_material(p))
+                        .collect();
+                    let average = if others.is_empty() { 0 } else { others.iter().sum::<i32>() / others.len() as i32 };
+                    self.player_material(me) - average
+                }
+            }
+        }
+
+        fn score_move(&self, m: &Self::Move) -> i32 {
+            let mut score = 0;
+            if let Some(promoted_piece) = m.get_promotion() {
+                score += heuristics::score_promotion(promoted_piece);
+            }
+            if let Some(victim) = self.piece_value_on(&m.get_dest()) {
+                let attacker = self.piece_value_on(&m.get_source()).unwrap_or(heuristics::get_piece_value("pawn"));
+                score += heuristics::score_capture_mvv_lva(attacker, victim);
+            }
+            score
+        }
+
+        fn is_ally(&self, a: usize, b: usize) -> bool {
+            a == b || (self.mode == FourPlayerMode::Teams && Player::ALL[a].team() == Player::ALL[b].team())
+        }
     }
 
 }
@@ -461,7 +657,7 @@ pub mod search {
     use std::collections::HashMap;
     use rand::seq::SliceRandom;
 
-    use super::game_logic::{GameMove, GameState};
+    use super::game_logic::{GameMove, GameState, MultiPlayerState};
 
     /// 기본 탐색 깊이. `find_best_move` 호출 시 이 값을 전달하면 됩니다.
     pub const SEARCH_DEPTH: u8 = 3;
@@ -533,6 +729,75 @@ pub mod search {
         }
     }
 
+    /// 단계별 수 공급기: TT 수 → 캡처·프로모션(MVV-LVA) → 킬러 → 조용한 수(히스토리).
+    /// 조용한 수는 앞 단계를 다 쓴 뒤에야 만들므로, 캡처로 컷오프가 나면 생성하지 않습니다.
+    /// TT 수가 조용한 수면 처음부터 모든 수를 만들어 한 번에 정렬합니다.
+    struct MovePicker<M> {
+        /// 남은 수. 점수 오름차순이라 뒤에서부터 꺼냅니다.
+        moves: Vec<(i32, M)>,
+        quiets_pending: bool,
+        depth: u8,
+        tt_move_hash: u64,
+        /// 빔 폭. 꺼낼 수 있는 남은 수의 개수입니다.
+        limit: Option<usize>,
+    }
+
+    impl<M: GameMove> MovePicker<M> {
+        fn new<S: GameState<Move = M>>(
+            state: &mut S,
+            depth: u8,
+            tt_move_hash: u64,
+            limit: Option<usize>,
+            killers: &KillerTable<M>,
+            history: &HistoryTable,
+        ) -> Self {
+            let mut picker = Self { moves: Vec::new(), quiets_pending: true, depth, tt_move_hash, limit };
+            let loud = state.get_loud_moves();
+            picker.push(state, loud, killers, history);
+            if tt_move_hash != 0 && !picker.moves.iter().any(|(_, m)| m.move_hash() == tt_move_hash) {
+                picker.push_quiets(state, killers, history);
+            }
+            picker
+        }
+
+        fn push<S: GameState<Move = M>>(&mut self, state: &S, moves: Vec<M>, killers: &KillerTable<M>, history: &HistoryTable) {
+            for m in moves {
+                let mhash = m.move_hash();
+                let s = if self.tt_move_hash != 0 && mhash == self.tt_move_hash {
+                    2_000_000
+                } else if state.is_capture(&m) {
+                    1_000_000 + state.score_move(&m)
+                } else if killers.get_bonus(self.depth, &m) > 0 {
+                    900_000 + history.get(mhash)
+                } else {
+                    state.score_move(&m) + history.get(mhash)
+                };
+                self.moves.push((s, m));
+            }
+            self.moves.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        }
+
+        fn push_quiets<S: GameState<Move = M>>(&mut self, state: &mut S, killers: &KillerTable<M>, history: &HistoryTable) {
+            self.quiets_pending = false;
+            let quiet = state.get_quiet_moves();
+            self.push(state, quiet, killers, history);
+        }
+
+        fn next<S: GameState<Move = M>>(&mut self, state: &mut S, killers: &KillerTable<M>, history: &HistoryTable) -> Option<M> {
+            if self.limit == Some(0) {
+                return None;
+            }
+            if self.moves.is_empty() && self.quiets_pending {
+                self.push_quiets(state, killers, history);
+            }
+            let (_, m) = self.moves.pop()?;
+            if let Some(n) = self.limit.as_mut() {
+                *n -= 1;
+            }
+            Some(m)
+        }
+    }
+
     // -------------------------------------------------------------------------
     // 트랜스포지션 테이블: 보드 해시 → (깊이, 점수, 노드 타입) 캐시.
     // -------------------------------------------------------------------------
@@ -664,8 +929,13 @@ pub mod search {
                 }
 
                 for m in moves {
-                    let mut new_state = state.make_move(&m);
-                    let score = -negamax(&mut new_state, current_depth - 1, HARD_DEPTH, -beta, -alpha, beam_width, &mut killers, &mut history, &mut tt);
+                    let undo = state.make_move_in_place(&m);
+                    let score = if state.kept_turn(&undo) {
+                        negamax(state, current_depth - 1, HARD_DEPTH, alpha, beta, beam_width, &mut killers, &mut history, &mut tt)
+                    } else {
+                        -negamax(state, current_depth - 1, HARD_DEPTH, -beta, -alpha, beam_width, &mut killers, &mut history, &mut tt)
+                    };
+                    state.unmake_move(undo);
 
                     if score > iter_best_score {
                         iter_best_score = score;
@@ -734,41 +1004,26 @@ pub mod search {
         let tt_move_hash: u64 = if tt_key != 0 { tt.get_best_move(tt_key) } else { 0 };
 
         // 수 정렬: 1순위 TT move → 2순위 캡처(MVV-LVA) → 3순위 킬러 → 4순위 히스토리
-        let mut moves: Vec<_> = state
-            .get_legal_moves()
-            .into_iter()
-            .map(|m| {
-                let mhash = m.move_hash();
-                let s = if tt_move_hash != 0 && mhash == tt_move_hash {
-                    2_000_000
-                } else if state.is_capture(&m) {
-                    1_000_000 + state.score_move(&m)
-                } else if killers.get_bonus(depth, &m) > 0 {
-                    900_000 + history.get(mhash)
-                } else {
-                    state.score_move(&m) + history.get(mhash)
-                };
-                (s, m)
-            })
-            .collect();
-        moves.sort_unstable_by(|a, b| b.0.cmp(&a.0));
-        if depth > 2 {
-            if let Some(n) = beam_width {
-                moves.truncate(n);
-            }
-        }
-        let moves_len = moves.len();
+        let limit = if depth > 2 { beam_width } else { None };
+        let mut picker = MovePicker::new(state, depth, tt_move_hash, limit, killers, history);
         let mut best_move_hash: u64 = 0;
 
-        for (i, (_, m)) in moves.into_iter().enumerate() {
+        let mut i = 0;
+        while let Some(m) = picker.next(state, killers, history) {
             // LMR: 상위 3수는 depth-1(full), 이후 수는 depth-2(reduced)로 탐색
-            let search_depth = if moves_len >= 4 && i >= 3 {
+            let search_depth = if i >= 3 {
                 depth.saturating_sub(2)
             } else {
                 depth - 1
             };
-            let mut new_state = state.make_move(&m);
-            let score = -negamax(&mut new_state, search_depth, hard_depth - 1, -beta, -alpha, beam_width, killers, history, tt);
+            let undo = state.make_move_in_place(&m);
+            // 같은 쪽의 다음 단계면 부호와 창을 그대로 둡니다.
+            let score = if state.kept_turn(&undo) {
+                negamax(state, search_depth, hard_depth - 1, alpha, beta, beam_width, killers, history, tt)
+            } else {
+                -negamax(state, search_depth, hard_depth - 1, -beta, -alpha, beam_width, killers, history, tt)
+            };
+            state.unmake_move(undo);
 
             if score > value {
                 value = score;
@@ -782,6 +1037,7 @@ pub mod search {
                 history.update(m.move_hash(), depth);
                 break;
             }
+            i += 1;
         }
 
         let final_value = (value as f32 * damper) as i32;
@@ -801,6 +1057,173 @@ pub mod search {
         final_value
     }
 
+    /// 불완전 정보 게임용 검색. 숨겨진 정보를 채운 표본 국면마다 루트 수를 모두 평가하고
+    /// 모든 표본에서 둘 수 있는 수 중 평균 점수가 가장 높은 수를 고릅니다.
+    pub fn find_best_move_sampled<S: GameState>(samples: &mut [S], depth: u8, beam_width: Option<usize>) -> Result<(S::Move, i32), usize> {
+        let Some(first) = samples.first_mut() else {
+            return Err(0);
+        };
+        if first.is_terminal() || depth == 0 {
+            return Err(0);
+        }
+
+        // (수, 점수 합, 평가된 표본 수)
+        let mut totals: Vec<(S::Move, i64, usize)> = Vec::new();
+        for state in samples.iter_mut() {
+            let mut killers = KillerTable::new(depth as usize + 2);
+            let mut history = HistoryTable::new();
+            let mut tt      = TranspositionTable::new();
+            for m in state.get_legal_moves() {
+                let undo = state.make_move_in_place(&m);
+                let score = if state.kept_turn(&undo) {
+                    negamax(state, depth - 1, HARD_DEPTH, -i32::MAX, i32::MAX, beam_width, &mut killers, &mut history, &mut tt)
+                } else {
+                    -negamax(state, depth - 1, HARD_DEPTH, -i32::MAX, i32::MAX, beam_width, &mut killers, &mut history, &mut tt)
+                };
+                state.unmake_move(undo);
+                match totals.iter_mut().find(|(x, _, _)| *x == m) {
+                    Some((_, total, count)) => {
+                        *total += score as i64;
+                        *count += 1;
+                    }
+                    None => totals.push((m, score as i64, 1)),
+                }
+            }
+        }
+
+        let n = totals.len();
+        totals
+            .into_iter()
+            .filter(|(_, _, count)| *count == samples.len())
+            .map(|(m, total, count)| (m, (total / count as i64) as i32))
+            .max_by_key(|(_, score)| *score)
+            .ok_or(n)
+    }
+
+    /// 우연 노드가 있는 게임(주사위 체스 등)용 expectimax 검색. 수를 두는 노드는 네가맥스처럼 최대화하고
+    /// 우연 노드는 결과 국면 점수의 확률 가중 평균을 씁니다. 우연 노드 자체는 깊이를 소모하지 않습니다.
+    /// 루트 국면은 이미 굴린 상태여야 합니다.
+    pub fn find_best_move_expectimax<S: GameState>(state: &mut S, depth: u8, beam_width: Option<usize>) -> Result<(S::Move, i32), usize> {
+        if state.is_terminal() || depth == 0 {
+            return Err(0);
+        }
+        let mut moves = state.get_legal_moves();
+        let n = moves.len();
+        moves.sort_by_cached_key(|m| -state.score_move(m));
+
+        let mut best: Option<(S::Move, i32)> = None;
+        for m in moves {
+            let undo = state.make_move_in_place(&m);
+            let score = if state.kept_turn(&undo) {
+                expectimax(state, depth.min(HARD_DEPTH) - 1, beam_width)
+            } else {
+                -expectimax(state, depth.min(HARD_DEPTH) - 1, beam_width)
+            };
+            state.unmake_move(undo);
+            if best.as_ref().is_none_or(|(_, best_score)| score > *best_score) {
+                best = Some((m, score));
+            }
+        }
+        best.ok_or(n)
+    }
+
+    fn expectimax<S: GameState>(state: &mut S, depth: u8, beam_width: Option<usize>) -> i32 {
+        if state.is_terminal() {
+            return state.evaluate();
+        }
+        if depth == 0 {
+            return state.evaluate();
+        }
+        // 굴린 면으로 둘 수가 없어 차례가 넘어가면 한 플라이로 셉니다.
+        let outcomes = state.chance_outcomes();
+        if !outcomes.is_empty() {
+            let mut total = 0.0;
+            for (mut next, chance) in outcomes {
+                let score = if state.keeps_turn(&next) {
+                    expectimax(&mut next, depth, beam_width)
+                } else {
+                    -expectimax(&mut next, depth - 1, beam_width)
+                };
+                total += chance * score as f32;
+            }
+            return total as i32;
+        }
+
+        let mut moves = state.get_legal_moves();
+        if moves.is_empty() {
+            return state.evaluate();
+        }
+        moves.sort_by_cached_key(|m| -state.score_move(m));
+        if depth > 2 {
+            if let Some(n) = beam_width {
+                moves.truncate(n);
+            }
+        }
+
+        let mut value = -i32::MAX;
+        for m in moves {
+            let undo = state.make_move_in_place(&m);
+            let score = if state.kept_turn(&undo) {
+                expectimax(state, depth - 1, beam_width)
+            } else {
+                -expectimax(state, depth - 1, beam_width)
+            };
+            state.unmake_move(undo);
+            value = value.max(score);
+        }
+        value
+    }
+
+    /// 다인 게임용 paranoid 알파-베타 검색. 루트 플레이어와 같은 편은 루트의 점수를 최대화하고
+    /// 나머지 플레이어는 모두 힘을 합쳐 최소화한다고 가정합니다.
+    pub fn find_best_move_paranoid<S: MultiPlayerState>(state: &mut S, depth: u8) -> Result<(S::Move, i32), usize> {
+        if state.is_terminal() || depth == 0 {
+            return Err(0);
+        }
+        let root = state.current_player();
+        let mut moves = state.get_legal_moves();
+        let n = moves.len();
+        moves.sort_by_cached_key(|m| -state.score_move(m));
+
+        let mut best: Option<(S::Move, i32)> = None;
+        let mut alpha = -i32::MAX;
+        for m in moves {
+            let mut new_state = state.make_move(&m);
+            let score = paranoid(&mut new_state, depth.min(HARD_DEPTH) - 1, alpha, i32::MAX, root);
+            if best.as_ref().is_none_or(|(_, best_score)| score > *best_score) {
+                alpha = alpha.max(score);
+                best = Some((m, score));
+            }
+        }
+        best.ok_or(n)
+    }
+
+    fn paranoid<S: MultiPlayerState>(state: &mut S, depth: u8, mut alpha: i32, mut beta: i32, root: usize) -> i32 {
+        if state.is_terminal() || depth == 0 {
+            return state.evaluate_for(root);
+        }
+        let maximizing = state.is_ally(state.current_player(), root);
+        let mut moves = state.get_legal_moves();
+        moves.sort_by_cached_key(|m| -state.score_move(m));
+
+        let mut value = if maximizing { -i32::MAX } else { i32::MAX };
+        for m in moves {
+            let mut new_state = state.make_move(&m);
+            let score = paranoid(&mut new_state, depth - 1, alpha, beta, root);
+            if maximizing {
+                value = value.max(score);
+                alpha = alpha.max(value);
+            } else {
+                value = value.min(score);
+                beta = beta.min(value);
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+        value
+    }
+
     /// Quiescence Search: depth-0 노드에서 캡처·프로모션만 확장해
     /// 지평선 효과(horizon effect)를 완화합니다.
     ///
@@ -837,9 +1260,9 @@ pub mod search {
 
         // 캡처·프로모션만 필터링 (SEE < 0인 손해 교환 제거), MVV-LVA 정렬
         let mut loud_moves: Vec<_> = state
-            .get_legal_moves()
+            .get_loud_moves()
             .into_iter()
-            .filter(|m| state.is_capture(m) && state.static_exchange_evaluation_move(m) >= 0)
+            .filter(|m| state.static_exchange_evaluation_move(m) >= 0)
             .collect();
 
         loud_moves.sort_unstable_by(|a, b| {
@@ -854,8 +1277,13 @@ pub mod search {
                 continue;
             }
 
-            let mut new_state = state.make_move(&m);
-            let score = -quiescence_search(&mut new_state, -beta, -alpha, depth_limit - 1);
+            let undo = state.make_move_in_place(&m);
+            let score = if state.kept_turn(&undo) {
+                quiescence_search(state, alpha, beta, depth_limit - 1)
+            } else {
+                -quiescence_search(state, -beta, -alpha, depth_limit - 1)
+            };
+            state.unmake_move(undo);
 
             if score >= beta {
                 return beta; // 베타 컷오프
@@ -1004,12 +1432,21 @@ pub mod search {
                 }
 
                 for m in moves {
-                    let mut new_state = state.make_move(&m);
-                    let score = -negamax_debug(
-                        &mut new_state, current_depth - 1, HARD_DEPTH,
-                        -beta, -alpha, beam_width,
-                        &mut killers, &mut history, &mut tt, &mut stats,
-                    );
+                    let undo = state.make_move_in_place(&m);
+                    let score = if state.kept_turn(&undo) {
+                        negamax_debug(
+                            state, current_depth - 1, HARD_DEPTH,
+                            alpha, beta, beam_width,
+                            &mut killers, &mut history, &mut tt, &mut stats,
+                        )
+                    } else {
+                        -negamax_debug(
+                            state, current_depth - 1, HARD_DEPTH,
+                            -beta, -alpha, beam_width,
+                            &mut killers, &mut history, &mut tt, &mut stats,
+                        )
+                    };
+                    state.unmake_move(undo);
 
                     if score > iter_best_score {
                         iter_best_score = score;
@@ -1101,40 +1538,24 @@ pub mod search {
         let tt_move_hash: u64 = if tt_key != 0 { tt.get_best_move(tt_key) } else { 0 };
 
         // 수 정렬: 1순위 TT move → 2순위 캡처(MVV-LVA) → 3순위 킬러 → 4순위 히스토리
-        let mut moves: Vec<_> = state
-            .get_legal_moves()
-            .into_iter()
-            .map(|m| {
-                let mhash = m.move_hash();
-                let s = if tt_move_hash != 0 && mhash == tt_move_hash {
-                    2_000_000
-                } else if state.is_capture(&m) {
-                    1_000_000 + state.score_move(&m)
-                } else if killers.get_bonus(depth, &m) > 0 {
-                    900_000 + history.get(mhash)
-                } else {
-                    state.score_move(&m) + history.get(mhash)
-                };
-                (s, m)
-            })
-            .collect();
-        moves.sort_unstable_by(|a, b| b.0.cmp(&a.0));
-        if depth > 2 {
-            if let Some(n) = beam_width {
-                moves.truncate(n);
-            }
-        }
-        let moves_len = moves.len();
+        let limit = if depth > 2 { beam_width } else { None };
+        let mut picker = MovePicker::new(state, depth, tt_move_hash, limit, killers, history);
         let mut best_move_hash: u64 = 0;
 
-        for (i, (_, m)) in moves.into_iter().enumerate() {
-            let search_depth = if moves_len >= 4 && i >= 3 {
+        let mut i = 0;
+        while let Some(m) = picker.next(state, killers, history) {
+            let search_depth = if i >= 3 {
                 depth.saturating_sub(2)
             } else {
                 depth - 1
             };
-            let mut new_state = state.make_move(&m);
-            let score = -negamax_debug(&mut new_state, search_depth, hard_depth - 1, -beta, -alpha, beam_width, killers, history, tt, stats);
+            let undo = state.make_move_in_place(&m);
+            let score = if state.kept_turn(&undo) {
+                negamax_debug(state, search_depth, hard_depth - 1, alpha, beta, beam_width, killers, history, tt, stats)
+            } else {
+                -negamax_debug(state, search_depth, hard_depth - 1, -beta, -alpha, beam_width, killers, history, tt, stats)
+            };
+            state.unmake_move(undo);
 
             if score > value {
                 value = score;
@@ -1149,6 +1570,7 @@ pub mod search {
                 history.update(m.move_hash(), depth);
                 break;
             }
+            i += 1;
         }
 
         let final_value = (value as f32 * damper) as i32;
@@ -1187,9 +1609,9 @@ pub mod search {
         if depth_limit == 0 { return alpha; }
 
         let mut loud_moves: Vec<_> = state
-            .get_legal_moves()
+            .get_loud_moves()
             .into_iter()
-            .filter(|m| state.is_capture(m) && state.static_exchange_evaluation_move(m) >= 0)
+            .filter(|m| state.static_exchange_evaluation_move(m) >= 0)
             .collect();
         loud_moves.sort_unstable_by(|a, b| state.score_move(b).cmp(&state.score_move(a)));
 
@@ -1200,8 +1622,13 @@ pub mod search {
                 continue;
             }
 
-            let mut new_state = state.make_move(&m);
-            let score = -quiescence_search_debug(&mut new_state, -beta, -alpha, depth_limit - 1, stats);
+            let undo = state.make_move_in_place(&m);
+            let score = if state.kept_turn(&undo) {
+                quiescence_search_debug(state, alpha, beta, depth_limit - 1, stats)
+            } else {
+                -quiescence_search_debug(state, -beta, -alpha, depth_limit - 1, stats)
+            };
+            state.unmake_move(undo);
             if score >= beta { return beta; }
             if score > alpha { alpha = score; }
         }
@@ -1209,3 +1636,36 @@ pub mod search {
         alpha
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::chessembly::board::Board;
+    use crate::chessembly::rules::Rules;
+    use crate::chessembly::{ChessemblyCompiled, Color, Piece, PieceSpan};
+    use super::search;
+
+    #[test]
+    fn expectimax_takes_the_king_on_a_forced_roll() {
+        let compiled = ChessemblyCompiled::new();
+        let rules = Rules::parse("dice-chess");
+        let mut board = Board::<8>::empty(&compiled);
+        board.rules = &rules;
+        for (piece, (x, y), color) in [
+            ("king", (4, 7), Color::White),
+            ("queen", (3, 7), Color::White),
+            ("king", (3, 0), Color::Black),
+            ("knight", (6, 0), Color::Black),
+        ] {
+            board.board[y][x] = PieceSpan::Piece(Piece::new(piece, color, &compiled.pieces));
+        }
+        board.rebuild_occupancy();
+        board.hash = board.zobrist();
+        board.history = vec![board.hash];
+        // 퀸이 나왔으므로 퀸으로 킹을 잡으면 바로 이깁니다.
+        board.roll_dice(4);
+        assert_eq!(board.turn, Color::White);
+        let (best, score) = search::find_best_move_expectimax(&mut board, 3, None).unwrap();
+        assert_eq!((best.get_source(), best.get_dest()), ((3, 7), (3, 0)));
+        assert!(score > 0);
+    }
+}
@@ -5,6 +5,10 @@
 // 순수 함수 위주로 구성해 테스트와 재사용이 쉽도록 설계했습니다.
 // -----------------------------------------------------------------------------
 pub mod heuristics {
+    use std::sync::OnceLock;
+
+    use crate::chessembly::{ids, PieceId};
+    use crate::chessembly::zobrist::BUILTIN_PIECES;
 
     // =========================================================================
     // 섹션 1: 기물 가치 (centipawn)
@@ -51,6 +55,12 @@ pub mod heuristics {
         }
     }
 
+    /// 내장 기물 ID의 센티폰 가치. 스크립트 기물이면 None이며 이름으로 `get_piece_value`를 씁니다.
+    pub fn get_piece_value_id(piece: PieceId) -> Option<i32> {
+        static VALUES: OnceLock<[i32; BUILTIN_PIECES.len()]> = OnceLock::new();
+        VALUES.get_or_init(|| BUILTIN_PIECES.map(get_piece_value)).get(piece as usize).copied()
+    }
+
     // =========================================================================
     // 섹션 2: Piece-Square Table (PST)
     //
@@ -115,6 +125,18 @@ pub mod heuristics {
         }
     }
 
+    /// `pst_bonus`의 기물 ID판.
+    pub fn pst_bonus_id(piece: PieceId, is_white: bool, x: u8, y: u8) -> i32 {
+        let xi = x as usize;
+        let yi = if is_white { y as usize } else { (7 - y) as usize };
+        match piece {
+            ids::PAWN   => PAWN_PST[yi][xi],
+            ids::KNIGHT => KNIGHT_PST[yi][xi],
+            ids::BISHOP => BISHOP_PST[yi][xi],
+            _           => 0,
+        }
+    }
+
     // =========================================================================
     // 섹션 3: 킹 안전 휴리스틱
     // =========================================================================
@@ -174,9 +196,9 @@ pub mod heuristics {
     // =========================================================================
 
     /// MVV-LVA (Most Valuable Victim, Least Valuable Attacker) 캡처 점수.
-    /// 피해자 가치 - 공격자 가치 → 큰 기물을 싼 기물로 잡는 수를 우선.
-    pub fn score_capture_mvv_lva(attacker: &str, victim: &str) -> i32 {
-        get_piece_value(victim) - get_piece_value(attacker)
+    /// 피해자 가치 - 공격자 가치 → 큰 기물을 싼 기물로 잡는 수를 우선. 가치는 센티폰입니다.
+    pub fn score_capture_mvv_lva(attacker: i32, victim: i32) -> i32 {
+        victim - attacker
     }
 
     /// 프로모션 점수. 퀸 프로모션(≈17900)이 가장 높습니다.
@@ -208,7 +230,7 @@ pub mod heuristics {
     /// 구버전 `score_capture` 별칭 → `score_capture_mvv_lva`로 위임.
     #[inline]
     pub fn score_capture(attacker: &str, victim: &str) -> i32 {
-        score_capture_mvv_lva(attacker, victim)
+        score_capture_mvv_lva(get_piece_value(attacker), get_piece_value(victim))
     }
 
     /// 구버전 `score_center_dist` 별칭 → `center_dist`로 위임.
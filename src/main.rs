@@ -3,7 +3,7 @@ use axum::{
     extract::Json as JsonBody,
 };
 use chessembly_bot::{
-    chessembly::{self, ChessemblyCompiled, Piece, PieceSpan, board::{Board, BoardState, BothBoardState}}, engine::{self, game_logic::GameState},
+    chessembly::{self, ChessemblyCompiled, MoveGen, Piece, PieceSpan, SquareSet, board::{Board, BoardState, BothBoardState, Marker}, four_player::{FourPlayerBoard, FourPlayerMode, FourPlayerStatus, Player}, rules::{Grid, Rules, Topology}}, engine::{self, game_logic::GameState},
 };
 use std::{collections::HashMap, env};
 use std::net::SocketAddr;
@@ -21,34 +21,197 @@ struct ApplyMoveRequest {
 
 #[derive(serde::Serialize)]
 struct BoardStateResponse {
+    /// 배치. 전장의 안개에서는 보는 쪽이 못 보는 칸을 `?`로 가립니다.
     position: String,
     turn: String,
     castling_oo: String,
     castling_ooo: String,
     en_passant_white: String,
     en_passant_black: String,
+    checks: String,
+    halfmove_clock: u16,
+    fullmove_number: u16,
+    history: String,
+    /// 차례 안의 현재 단계 (0부터).
+    phase: u8,
+    /// 이번 차례에 남은 수 (지금 둘 수 포함).
+    moves_left: u8,
+    /// 이번 차례에 굴린 주사위가 허락하는 기물 종류. 아직 굴리지 않았으면 null.
+    roll: Option<String>,
+    /// 칸 표식 (`이름,x,y,진영[,남은 플라이]`를 '/'로 구분, 없으면 ".").
+    markers: String,
+    /// 배치 단계의 보관함 (기물 이름을 ','로 구분, 비었으면 ".").
+    reserve_white: String,
+    reserve_black: String,
+    status: String,
 }
 
-fn encode_board_response<'a, const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(
-    board: &Board<'a, MACHO, IMPRISONED, SIZE>,
-) -> BoardStateResponse {
-    let position = (0..SIZE)
+/// `Checks` 헤더("백,흑")에서 각 진영이 준 체크 횟수를 읽습니다. 없으면 (0, 0).
+fn parse_checks(headers: &HeaderMap) -> (u8, u8) {
+    headers.get("Checks")
+        .and_then(|x| x.to_str().ok())
+        .and_then(|x| x.split_once(','))
+        .map(|(w, b)| (w.trim().parse().unwrap_or(0), b.trim().parse().unwrap_or(0)))
+        .unwrap_or((0, 0))
+}
+
+/// 반복 판정용 국면 키 기록(`History` 헤더, 16진수 '/' 구분)과 수 카운터를 읽습니다.
+fn parse_counters(headers: &HeaderMap) -> (u16, u16, Vec<u64>) {
+    let read = |name: &str| headers.get(name).and_then(|x| x.to_str().ok());
+    let halfmove_clock = read("Halfmove-Clock").and_then(|x| x.trim().parse().ok()).unwrap_or(0);
+    let fullmove_number = read("Fullmove-Number").and_then(|x| x.trim().parse().ok()).unwrap_or(1);
+    let history = read("History")
+        .map(|x| x.split('/').filter_map(|key| u64::from_str_radix(key.trim(), 16).ok()).collect())
+        .unwrap_or_default();
+    (halfmove_clock, fullmove_number, history)
+}
+
+/// 차례 안의 현재 단계(`Phase` 헤더, 0부터). 없으면 첫 단계.
+fn parse_phase(headers: &HeaderMap) -> u8 {
+    headers.get("Phase").and_then(|x| x.to_str().ok()).and_then(|x| x.trim().parse().ok()).unwrap_or(0)
+}
+
+/// 이번 차례에 남은 수(`Moves-Left` 헤더). 없으면 규칙과 수 번호로 계산합니다.
+fn parse_moves_left(headers: &HeaderMap) -> Option<u8> {
+    headers.get("Moves-Left").and_then(|x| x.to_str().ok()).and_then(|x| x.trim().parse().ok())
+}
+
+/// 이번 차례에 굴린 주사위 면(`Roll` 헤더, 기물 종류). 없으면 아직 굴리지 않은 것으로 봅니다.
+fn parse_roll(headers: &HeaderMap) -> Option<&str> {
+    headers.get("Roll").and_then(|x| x.to_str().ok()).map(|x| x.trim()).filter(|x| !x.is_empty())
+}
+
+/// 배치 단계의 보관함(`Reserve-White`/`Reserve-Black` 헤더, 기물 이름을 ','로 구분, 같은 기물은 반복).
+fn parse_reserve<'a>(headers: &'a HeaderMap, name: &str) -> Vec<&'a str> {
+    headers.get(name)
+        .and_then(|x| x.to_str().ok())
+        .unwrap_or("")
+        .split(',')
+        .map(|x| x.trim())
+        .filter(|x| !x.is_empty() && *x != ".")
+        .collect()
+}
+
+/// 칸 표식(`Markers` 헤더, `이름,x,y,진영[,남은 플라이]`를 '/'로 구분). 남은 플라이가 없으면 영구 표식.
+fn parse_markers(headers: &HeaderMap) -> Vec<Marker<'_>> {
+    headers.get("Markers")
+        .and_then(|x| x.to_str().ok())
+        .unwrap_or("")
+        .split('/')
+        .filter_map(|marker| {
+            let fields: Vec<&str> = marker.split(',').map(|x| x.trim()).collect();
+            let (name, x, y, owner) = (fields.first()?, fields.get(1)?, fields.get(2)?, fields.get(3)?);
+            Some(Marker {
+                name,
+                position: (x.parse().ok()?, y.parse().ok()?),
+                owner: match *owner {
+                    "white" => chessembly::Color::White,
+                    "black" => chessembly::Color::Black,
+                    _ => chessembly::Color::Neutral,
+                },
+                expires: fields.get(4).and_then(|x| x.parse().ok()),
+            })
+        })
+        .collect()
+}
+
+/// 진영별 스크립트(`Chessembly-White`, `Chessembly-Black` 헤더, URL 인코딩). 없으면 빈 스크립트.
+/// `Chessembly` 헤더는 양쪽이 함께 쓰는 공통 스크립트가 됩니다.
+fn parse_side_scripts(headers: &HeaderMap) -> (String, String) {
+    let read = |name: &str| {
+        headers.get(name)
+            .and_then(|x| x.to_str().ok())
+            .and_then(|x| urlencoding::decode(x).ok())
+            .map(|x| x.replace('{', " { ").replace('}', " } "))
+            .unwrap_or_default()
+    };
+    (read("Chessembly-White"), read("Chessembly-Black"))
+}
+
+/// 전장의 안개에서 보는 쪽(`Viewer` 헤더: white|black). 없으면 `default`.
+fn parse_viewer(headers: &HeaderMap, default: chessembly::Color) -> chessembly::Color {
+    match headers.get("Viewer").and_then(|x| x.to_str().ok()) {
+        Some("white") => chessembly::Color::White,
+        Some("black") => chessembly::Color::Black,
+        _ => default,
+    }
+}
+
+/// `Position` 헤더 값. `glinski`는 Glinski 육각 체스, `four-player`는 4인 체스 시작 배치로 바꿉니다.
+fn resolve_position(position: &str) -> &str {
+    match position {
+        "glinski" => chessembly::hex::GLINSKI_POSITION.as_str(),
+        "four-player" => chessembly::four_player::FOUR_PLAYER_POSITION.as_str(),
+        _ => position,
+    }
+}
+
+/// `Win-Conditions` 헤더의 규칙에 `Macho`/`Imprisoned`/`Topology` 헤더를 더한 변형 규칙.
+/// `Position`에서 `#`로 표시된 칸은 구멍으로 등록하고, `glinski`면 육각 격자를 씁니다.
+fn parse_rules(headers: &HeaderMap) -> Rules<'_> {
+    let mut rules = Rules::parse(headers.get("Win-Conditions").and_then(|x| x.to_str().ok()).unwrap_or(""));
+    rules.macho |= headers.get("Macho").is_some();
+    rules.imprisoned |= headers.get("Imprisoned").is_some();
+    match headers.get("Topology").and_then(|x| x.to_str().ok()) {
+        Some("cylinder") => rules.topology = Topology::Cylinder,
+        Some("torus") => rules.topology = Topology::Torus,
+        _ => {}
+    }
+    if let Some(position) = headers.get("Position").and_then(|x| x.to_str().ok()) {
+        if position == "glinski" {
+            rules.grid = Grid::Hex(5);
+        }
+        let position = resolve_position(position);
+        for (y, line) in position.split('/').take(chessembly::MAX_BOARD_SIZE).enumerate() {
+            for (x, pc) in line.split_whitespace().take(chessembly::MAX_BOARD_SIZE).enumerate() {
+                if pc == "#" {
+                    rules.holes.insert(&(x as u8, y as u8));
+                }
+            }
+        }
+    }
+    rules
+}
+
+/// `Board-Size` 헤더("8" 또는 "가로x세로", 예: "10x8")를 읽습니다. 가로는 1~16칸, 세로는 2~16칸.
+/// 헤더가 없으면 규칙의 격자(`Grid`)에 맞는 기본 크기를 씁니다.
+fn parse_board_size(headers: &HeaderMap, rules: &Rules) -> (u8, u8) {
+    let clamp = |x: &str, min: u8| x.trim().parse::<u8>().unwrap_or(8).clamp(min, chessembly::MAX_BOARD_SIZE as u8);
+    // 폰의 시작·승급 칸을 끝에서 두 번째 줄로 잡으므로 세로는 2줄 이상이어야 합니다.
+    match headers.get("Board-Size").and_then(|x| x.to_str().ok()) {
+        Some(size) => match size.split_once(['x', 'X']) {
+            Some((width, height)) => (clamp(width, 1), clamp(height, 2)),
+            None => (clamp(size, 1), clamp(size, 2)),
+        },
+        None => rules.grid.default_size(),
+    }
+}
+
+/// 배치를 문자열로 만듭니다. `visible`이 주어지면(전장의 안개) 그 밖의 칸은 `?`로 가립니다.
+fn encode_position<'a, const SIZE: usize>(board: &Board<'a, SIZE>, visible: Option<SquareSet>) -> String {
+    (0..board.get_height())
         .map(|i| {
-            (0..SIZE)
+            (0..board.get_width())
                 .map(|j| match &board.board[i][j] {
-                    PieceSpan::Piece(p) => format!(
-                        "{}:{}",
-                        p.piece_type,
-                        if p.color == chessembly::Color::White { "white" } else { "black" }
-                    ),
+                    _ if visible.is_some_and(|v| !v.contains(&(j as u8, i as u8)))
+                        && !board.rules.holes.contains(&(j as u8, i as u8)) => "?".to_string(),
+                    PieceSpan::Piece(p) => format!("{}:{}", p.piece_type, p.color.as_str()),
+                    PieceSpan::Empty if board.rules.holes.contains(&(j as u8, i as u8)) => "#".to_string(),
                     PieceSpan::Empty => ".".to_string(),
                 })
                 .collect::<Vec<_>>()
                 .join(" ")
         })
         .collect::<Vec<_>>()
-        .join("/");
+        .join("/")
+}
 
+/// 보드 상태를 응답으로 만듭니다. `fog`(보는 쪽, 보이는 칸)가 주어지면 배치를 가리고
+/// 상대 진영의 앙파상 칸은 비웁니다.
+fn encode_board_response<'a, const SIZE: usize>(
+    board: &Board<'a, SIZE>,
+    fog: Option<(chessembly::Color, SquareSet)>,
+) -> BoardStateResponse {
     let encode_ep = |ep: &Vec<chessembly::Position>| {
         if ep.is_empty() {
             ".".to_string()
@@ -57,8 +220,11 @@ fn encode_board_response<'a, const MACHO: bool, const IMPRISONED: bool, const SI
         }
     };
 
+    let hidden = |color: chessembly::Color| fog.is_some_and(|(viewer, _)| viewer != color);
+    let encode_reserve = |reserve: &Vec<&str>| if reserve.is_empty() { ".".to_string() } else { reserve.join(",") };
+
     BoardStateResponse {
-        position,
+        position: encode_position(board, fog.map(|(_, visible)| visible)),
         turn: if board.turn == chessembly::Color::White { "white".to_string() } else { "black".to_string() },
         castling_oo: format!(
             "{}{}",
@@ -70,8 +236,33 @@ fn encode_board_response<'a, const MACHO: bool, const IMPRISONED: bool, const SI
             if board.board_state.white.castling_ooo { '1' } else { '0' },
             if board.board_state.black.castling_ooo { '1' } else { '0' },
         ),
-        en_passant_white: encode_ep(&board.board_state.white.enpassant),
-        en_passant_black: encode_ep(&board.board_state.black.enpassant),
+        en_passant_white: if hidden(chessembly::Color::White) { ".".to_string() } else { encode_ep(&board.board_state.white.enpassant) },
+        en_passant_black: if hidden(chessembly::Color::Black) { ".".to_string() } else { encode_ep(&board.board_state.black.enpassant) },
+        checks: format!("{},{}", board.board_state.white.checks, board.board_state.black.checks),
+        halfmove_clock: board.halfmove_clock,
+        fullmove_number: board.fullmove_number,
+        history: board.history.iter().map(|key| format!("{:x}", key)).collect::<Vec<_>>().join("/"),
+        phase: board.phase,
+        moves_left: board.moves_left,
+        roll: board.rolled_piece().map(|x| x.to_string()),
+        reserve_white: encode_reserve(&board.board_state.white.reserve),
+        reserve_black: encode_reserve(&board.board_state.black.reserve),
+        markers: if board.markers.is_empty() {
+            ".".to_string()
+        } else {
+            board.markers
+                .iter()
+                .map(|m| match m.expires {
+                    Some(plies) => format!("{},{},{},{},{}", m.name, m.position.0, m.position.1, m.owner.as_str(), plies),
+                    None => format!("{},{},{},{}", m.name, m.position.0, m.position.1, m.owner.as_str()),
+                })
+                .collect::<Vec<_>>()
+                .join("/")
+        },
+        status: match board.result() {
+            Some(result) => format!("{:?}", result),
+            None => "Ongoing".to_string(),
+        },
     }
 }
 
@@ -92,6 +283,10 @@ async fn main() {
         .route("/debug", post(run_engine_debug))
         .route("/moves", post(get_piece_moves))
         .route("/apply", post(apply_move_endpoint))
+        .route("/visibility", post(get_visibility))
+        .route("/roll", post(roll_dice_endpoint))
+        .route("/four-player", post(run_four_player_engine))
+        .route("/four-player/apply", post(apply_four_player_move))
         .route("/classify", post(classify_piece))
         .route("/classifier", get(serve_classifier_ui))
         .layer(cors);
@@ -121,67 +316,107 @@ struct SetupBoardParams<'a> {
     compiled: &'a ChessemblyCompiled<'a>,
     position: &'a str,
     board_state: BothBoardState<'a>,
-    turn: chessembly::Color
+    turn: chessembly::Color,
+    rules: &'a Rules<'a>,
+    counters: (u16, u16, Vec<u64>),
+    phase: u8,
+    moves_left: Option<u8>,
+    roll: Option<&'a str>,
+    markers: Vec<Marker<'a>>,
+    size: (u8, u8),
 }
 
-fn setup_board<'a, const MACHO: bool, const IMPRISONED: bool, const SIZE: usize>(
+/// 보드 크기에 맞는 저장 공간(8×8 또는 16×16)으로 `Board`를 만들어 본문을 실행합니다.
+/// 변형 규칙은 런타임 값(`Rules`)이므로 저장 공간 크기만 단형화됩니다.
+macro_rules! with_board {
+    ($param:expr, |$board:ident| $body:block) => {{
+        let param = $param;
+        if param.size.0 as usize > 8 || param.size.1 as usize > 8 {
+            let mut $board: Board<16> = setup_board(param);
+            $body
+        } else {
+            let mut $board: Board<8> = setup_board(param);
+            $body
+        }
+    }};
+}
+
+fn setup_board<'a, const SIZE: usize>(
     params: SetupBoardParams<'a>
-) -> Board<'a, MACHO, IMPRISONED, SIZE> {
-    let mut board = Board::<'a, MACHO, IMPRISONED, SIZE>::empty(&params.compiled);
-    let mut i = 0;
-    for line in params.position.split('/') {
-        let mut j = 0;
-        for pc in line.split_whitespace() {
+) -> Board<'a, SIZE> {
+    let (width, height) = params.size;
+    let mut board = Board::<'a, SIZE>::empty_sized(&params.compiled, width, height);
+    for (i, line) in params.position.split('/').take(height as usize).enumerate() {
+        for (j, pc) in line.split_whitespace().take(width as usize).enumerate() {
             if let Some((piece_name, color)) = pc.split_once(':') {
-                board.board[i][j] = chessembly::PieceSpan::Piece(chessembly::Piece {
-                    piece_type: piece_name,
-                    color: if color == "white" {
-                        chessembly::Color::White
-                    } else {
-                        chessembly::Color::Black
-                    },
-                });
+                let color = match color {
+                    "white" => chessembly::Color::White,
+                    "neutral" => chessembly::Color::Neutral,
+                    _ => chessembly::Color::Black,
+                };
+                board.board[i][j] = chessembly::PieceSpan::Piece(chessembly::Piece::new(piece_name, color, &params.compiled.pieces));
             }
-            j += 1;
         }
-        i += 1;
     }
 
     board.board_state = params.board_state;
     board.turn = params.turn;
+    board.rules = params.rules;
+    board.phase = params.phase.min(board.rules.phase_count() - 1);
+    (board.halfmove_clock, board.fullmove_number, board.history) = params.counters;
+    board.moves_left = params.moves_left.unwrap_or_else(|| board.turn_moves()).max(1);
+    board.roll = params.roll
+        .and_then(|piece| board.rules.dice.iter().position(|x| *x == piece))
+        .map(|face| face as u8);
+    board.markers = params.markers;
+    board.rebuild_occupancy();
+    board.hash = board.zobrist();
+    if board.history.is_empty() {
+        board.history.push(board.hash);
+    }
 
     board
 }
 
+/// `Turn` 헤더 (white|black). 없으면 백.
+fn parse_turn(headers: &HeaderMap) -> chessembly::Color {
+    match headers.get("Turn").and_then(|x| x.to_str().ok()) {
+        Some("black") => chessembly::Color::Black,
+        _ => chessembly::Color::White,
+    }
+}
+
+/// 공통 보드 헤더로 `setup_board` 인자를 만듭니다.
+/// 배치의 기물 이름은 미리 `compiled.intern_placement`로 등록해 두어야 합니다.
+fn board_params<'a>(
+    headers: &'a HeaderMap,
+    compiled: &'a ChessemblyCompiled<'a>,
+    rules: &'a Rules<'a>,
+) -> SetupBoardParams<'a> {
+    SetupBoardParams {
+        compiled,
+        position: resolve_position(headers.get("Position").and_then(|x| x.to_str().ok()).unwrap_or("")),
+        board_state: parse_board_state(headers),
+        turn: parse_turn(headers),
+        rules,
+        counters: parse_counters(headers),
+        phase: parse_phase(headers),
+        moves_left: parse_moves_left(headers),
+        roll: parse_roll(headers),
+        markers: parse_markers(headers),
+        size: parse_board_size(headers, rules),
+    }
+}
+
 async fn run_engine(headers: HeaderMap) -> impl IntoResponse {
-    let (
-        Some(position),
-        Some(script),
-        Some(turn),
-        Some(castling_oo),
-        Some(castling_ooo),
-        Some(en_passant_white),
-        Some(en_passant_black),
-        Some(register_white),
-        Some(register_black),
-        Some(depth_header_str),
-    ) = (
-        headers.get("Position"),
+    let (Some(position), Some(script), Some(depth_header_str)) = (
+        headers.get("Position").and_then(|x| x.to_str().ok()),
         headers.get("Chessembly"),
-        headers.get("Turn"),
-        headers.get("Castling-OO"),
-        headers.get("Castling-OOO"),
-        headers.get("En-Passant-White"),
-        headers.get("En-Passant-Black"),
-        headers.get("Register-White"),
-        headers.get("Register-Black"),
         headers.get("Depth"),
     ) else {
         return (StatusCode::OK, "asdf").into_response();
     };
 
-    let board_size = headers.get("Board-Size").map(|b| b.to_str().map(|x| x.parse::<u8>().unwrap_or(8)).unwrap_or(8)).unwrap_or(8);
-    println!("{:?}", board_size);
     
     let Ok(depth) = depth_header_str.to_str().map(|x| x.parse::<u8>().unwrap_or(3)) else {
         return (StatusCode::OK, "asdf").into_response();
@@ -197,89 +432,16 @@ async fn run_engine(headers: HeaderMap) -> impl IntoResponse {
 
     let str_script_fixed = str_script.replace('{', " { ").replace('}', " } ");
 
-    let Ok(compiled) = ChessemblyCompiled::from_script(&str_script_fixed[..]) else {
-        return (StatusCode::OK, "asdf").into_response();
-    };
-
-    let (
-        Ok(castling_oo_tuple),
-        Ok(castling_ooo_tuple),
-        Ok(en_passant_white_str),
-        Ok(en_passant_black_str),
-        Ok(register_white_str),
-        Ok(register_black_str)
-    ) = (
-        castling_oo.to_str().map(|x| (x.chars().nth(0) == Some('1'), x.chars().nth(1) == Some('1'))),
-        castling_ooo.to_str().map(|x| (x.chars().nth(0) == Some('1'), x.chars().nth(1) == Some('1'))),
-        en_passant_white.to_str(),
-        en_passant_black.to_str(),
-        register_white.to_str(),
-        register_black.to_str()
-    ) else {
-        return (StatusCode::OK, "asdf").into_response();
-    };
-    let mut en_passant_white_positions: Vec<chessembly::Position> = Vec::new();
-    let mut en_passant_black_positions: Vec<chessembly::Position> = Vec::new();
-    let mut register_white_map: HashMap<&str, u8> = HashMap::new();
-    let mut register_black_map: HashMap<&str, u8> = HashMap::new();
-    for coord in en_passant_white_str.split('/') {
-        if let Some((x, y)) = coord.split_once(',') {
-            en_passant_white_positions.push((x.parse().unwrap_or(0), y.parse().unwrap_or(0)));
-        }
-    }
-    for coord in en_passant_black_str.split('/') {
-        if let Some((x, y)) = coord.split_once(',') {
-            en_passant_black_positions.push((x.parse().unwrap_or(0), y.parse().unwrap_or(0)));
-        }
-    }
-    for register in register_white_str.split('/') {
-        if let Some((key, value)) = register.split_once(',') {
-            register_white_map.insert(key, value.parse().unwrap_or(0));
-        }
-    }
-    for register in register_black_str.split('/') {
-        if let Some((key, value)) = register.split_once(',') {
-            register_black_map.insert(key, value.parse().unwrap_or(0));
-        }
-    }
-
-    let board_state_white = BoardState {
-        castling_oo: castling_oo_tuple.0,
-        castling_ooo: castling_ooo_tuple.0,
-        enpassant: en_passant_white_positions,
-        register: register_white_map
-    };
-
-    let board_state_black = BoardState {
-        castling_oo: castling_oo_tuple.1,
-        castling_ooo: castling_ooo_tuple.1,
-        enpassant: en_passant_black_positions,
-        register: register_black_map
-    };
-
-    let board_state = BothBoardState {
-        white: board_state_white,
-        black: board_state_black,
-    };
+    let (white_script, black_script) = parse_side_scripts(&headers);
+    let mut compiled = ChessemblyCompiled::from_side_scripts(&str_script_fixed[..], &white_script, &black_script);
 
-    let turn = if turn.to_str().unwrap() == "white" {
-        chessembly::Color::White
-    } else {
-        chessembly::Color::Black
-    };
-    
-    let is_macho = headers.get("Macho").is_some();
-    let is_imprisoned = headers.get("Imprisoned").is_some();
+    let rules = parse_rules(&headers);
     let beam_width: Option<usize> = headers.get("Beam-Width")
         .and_then(|v| v.to_str().ok())
         .and_then(|s| s.parse::<usize>().ok());
 
-    let param = SetupBoardParams {
-        compiled: &compiled,
-        position: position.to_str().unwrap(),
-        board_state: board_state,
-        turn: turn
-    };
+    compiled.intern_placement(resolve_position(position));
+    let param = board_params(&headers, &compiled, &rules);
 
     if let Some(to_evaluate) = headers.get("Target") {
         let Ok(to_evaluate_str) = to_evaluate.to_str() else {
@@ -295,58 +457,39 @@ async fn run_engine(headers: HeaderMap) -> impl IntoResponse {
             return (StatusCode::OK, "asdf").into_response();
         };
 
-        match (is_macho, is_imprisoned, board_size) {
-            (false, false, 8) | (false, false, _) => {
-                let mut board: Board<false, false, 8> = setup_board(param);
-                let moves = board.get_legal_moves();
-                let Some(played_move) = moves.iter().find(|node| node.get_source() == from && node.get_dest() == position) else {
-                    return (StatusCode::OK, "asdf").into_response();
-                };
-                let result = chessembly_bot::analyze::analyze_move(&mut board, played_move, 4);        
-                
-                return (StatusCode::OK, Json(result)).into_response();
-            }
-            _ => {
-                return (StatusCode::OK, format!("{:?}/{:?}", from, position)).into_response();
-            }
-        }
+        with_board!(param, |board| {
+            let moves = board.get_legal_moves();
+            let Some(played_move) = moves.iter().find(|node| node.get_source() == from && node.get_dest() == position) else {
+                return (StatusCode::OK, "asdf").into_response();
+            };
+            let result = chessembly_bot::analyze::analyze_move(&mut board, played_move, 4);        
+            
+            return (StatusCode::OK, Json(result)).into_response();
+        })
     }
     
-    let best_move = match (is_macho, is_imprisoned, board_size) {
-        (true, true, 9) => {
-            let mut board: Board<true, false, 9> = setup_board(param);
-            engine::search::find_best_move(&mut board, depth, beam_width)
-        },
-        (true, false, 9)  => {
-            let mut board: Board<true, false, 9> = setup_board(param);
-            engine::search::find_best_move(&mut board, depth, beam_width)
-        }
-        (false, true, 9) => {
-            let mut board: Board<false, true, 9> = setup_board(param);
-            engine::search::find_best_move(&mut board, depth, beam_width)
-        }
-        (false, false, 9) => {
-            let mut board: Board<false, false, 9> = setup_board(param);
-            engine::search::find_best_move(&mut board, depth, beam_width)
-        }
-        
-        (true, true, 8) | (true, true, _) => {
-            let mut board: Board<true, false, 8> = setup_board(param);
-            engine::search::find_best_move(&mut board, depth, beam_width)
-        },
-        (true, false, 8) | (true, false, _)  => {
-            let mut board: Board<true, false, 8> = setup_board(param);
-            engine::search::find_best_move(&mut board, depth, beam_width)
-        }
-        (false, true, 8) | (false, true, _) => {
-            let mut board: Board<false, true, 8> = setup_board(param);
-            engine::search::find_best_move(&mut board, depth, beam_width)
-        }
-        (false, false, 8) | (false, false, _) => {
-            let mut board: Board<false, false, 8> = setup_board(param);
+    let best_move = with_board!(param, |board| {
+        if board.rules.fog {
+            // 보이지 않는 상대 기물을 무작위로 다시 배치한 표본들로 탐색합니다.
+            let mut rng = rand::rng();
+            let samples = headers.get("Samples").and_then(|x| x.to_str().ok()).and_then(|x| x.parse().ok()).unwrap_or(8usize).clamp(1, 64);
+            let mut boards: Vec<_> = (0..samples).map(|_| board.sample_hidden(board.turn, &mut rng)).collect();
+            engine::search::find_best_move_sampled(&mut boards, depth, beam_width)
+        } else if !board.rules.dice.is_empty() {
+            // 아직 굴리지 않았으면 엔진이 먼저 굴립니다. 굴린 면으로 둘 수가 없으면 차례가 넘어가 수가 없습니다.
+            if board.awaits_roll() {
+                let mover = board.turn;
+                let face = rand::Rng::random_range(&mut rand::rng(), 0..board.rules.dice.len()) as u8;
+                board.roll_dice(face);
+                if board.turn != mover {
+                    return (StatusCode::OK, "null").into_response();
+                }
+            }
+            engine::search::find_best_move_expectimax(&mut board, depth, beam_width)
+        } else {
             engine::search::find_best_move(&mut board, depth, beam_width)
         }
-    };
+    });
 
     
     // if let Ok((node, score)) = best_move {
@@ -368,27 +511,9 @@ async fn run_engine(headers: HeaderMap) -> impl IntoResponse {
 // 기존 run_engine 과 동일한 헤더를 받지만, engine::search::find_best_move_debug 를
 // 호출해 탐색 통계(nodes, qnodes, TT hit rate 등)를 포함한 JSON 을 반환합니다.
 async fn run_engine_debug(headers: HeaderMap) -> impl IntoResponse {
-    let (
-        Some(position),
-        Some(script),
-        Some(turn),
-        Some(castling_oo),
-        Some(castling_ooo),
-        Some(en_passant_white),
-        Some(en_passant_black),
-        Some(register_white),
-        Some(register_black),
-        Some(depth_header_str),
-    ) = (
-        headers.get("Position"),
+    let (Some(position), Some(script), Some(depth_header_str)) = (
+        headers.get("Position").and_then(|x| x.to_str().ok()),
         headers.get("Chessembly"),
-        headers.get("Turn"),
-        headers.get("Castling-OO"),
-        headers.get("Castling-OOO"),
-        headers.get("En-Passant-White"),
-        headers.get("En-Passant-Black"),
-        headers.get("Register-White"),
-        headers.get("Register-Black"),
         headers.get("Depth"),
     ) else {
         return (StatusCode::BAD_REQUEST, "missing headers").into_response();
@@ -406,106 +531,20 @@ async fn run_engine_debug(headers: HeaderMap) -> impl IntoResponse {
         return (StatusCode::BAD_REQUEST, "bad script").into_response();
     };
     let str_script_fixed = str_script.replace('{', " { ").replace('}', " } ");
-    let Ok(compiled) = chessembly::ChessemblyCompiled::from_script(&str_script_fixed[..]) else {
-        return (StatusCode::BAD_REQUEST, "script compile failed").into_response();
-    };
-
-    let (
-        Ok(castling_oo_tuple),
-        Ok(castling_ooo_tuple),
-        Ok(en_passant_white_str),
-        Ok(en_passant_black_str),
-        Ok(register_white_str),
-        Ok(register_black_str),
-    ) = (
-        castling_oo.to_str().map(|x| (x.chars().nth(0) == Some('1'), x.chars().nth(1) == Some('1'))),
-        castling_ooo.to_str().map(|x| (x.chars().nth(0) == Some('1'), x.chars().nth(1) == Some('1'))),
-        en_passant_white.to_str(),
-        en_passant_black.to_str(),
-        register_white.to_str(),
-        register_black.to_str(),
-    ) else {
-        return (StatusCode::BAD_REQUEST, "bad headers").into_response();
-    };
-
-    let mut en_passant_white_positions: Vec<chessembly::Position> = Vec::new();
-    let mut en_passant_black_positions: Vec<chessembly::Position> = Vec::new();
-    let mut register_white_map: HashMap<&str, u8> = HashMap::new();
-    let mut register_black_map: HashMap<&str, u8> = HashMap::new();
-    for coord in en_passant_white_str.split('/') {
-        if let Some((x, y)) = coord.split_once(',') {
-            en_passant_white_positions.push((x.parse().unwrap_or(0), y.parse().unwrap_or(0)));
-        }
-    }
-    for coord in en_passant_black_str.split('/') {
-        if let Some((x, y)) = coord.split_once(',') {
-            en_passant_black_positions.push((x.parse().unwrap_or(0), y.parse().unwrap_or(0)));
-        }
-    }
-    for register in register_white_str.split('/') {
-        if let Some((key, value)) = register.split_once(',') {
-            register_white_map.insert(key, value.parse().unwrap_or(0));
-        }
-    }
-    for register in register_black_str.split('/') {
-        if let Some((key, value)) = register.split_once(',') {
-            register_black_map.insert(key, value.parse().unwrap_or(0));
-        }
-    }
-
-    let board_state = chessembly::board::BothBoardState {
-        white: chessembly::board::BoardState {
-            castling_oo: castling_oo_tuple.0,
-            castling_ooo: castling_ooo_tuple.0,
-            enpassant: en_passant_white_positions,
-            register: register_white_map,
-        },
-        black: chessembly::board::BoardState {
-            castling_oo: castling_oo_tuple.1,
-            castling_ooo: castling_ooo_tuple.1,
-            enpassant: en_passant_black_positions,
-            register: register_black_map,
-        },
-    };
-
-    let turn = if turn.to_str().unwrap_or("white") == "white" {
-        chessembly::Color::White
-    } else {
-        chessembly::Color::Black
-    };
+    let (white_script, black_script) = parse_side_scripts(&headers);
+    let mut compiled = chessembly::ChessemblyCompiled::from_side_scripts(&str_script_fixed[..], &white_script, &black_script);
 
-    let is_macho = headers.get("Macho").is_some();
-    let is_imprisoned = headers.get("Imprisoned").is_some();
+    let rules = parse_rules(&headers);
     let beam_width: Option<usize> = headers.get("Beam-Width")
         .and_then(|v| v.to_str().ok())
         .and_then(|s| s.parse::<usize>().ok());
-    let pos_str = position.to_str().unwrap_or("");
 
-    let param = SetupBoardParams {
-        compiled: &compiled,
-        position: pos_str,
-        board_state: board_state,
-        turn: turn
-    };
+    compiled.intern_placement(resolve_position(position));
+    let param = board_params(&headers, &compiled, &rules);
 
-    let debug_info = match (is_macho, is_imprisoned) {
-        (true, true) => {
-            let mut board: chessembly::board::Board<true, true, 8> = setup_board(param);
-            engine::search::find_best_move_debug(&mut board, depth, beam_width)
-        }
-        (true, false) => {
-            let mut board: chessembly::board::Board<true, false, 8> = setup_board(param);
-            engine::search::find_best_move_debug(&mut board, depth, beam_width)
-        }
-        (false, true) => {
-            let mut board: chessembly::board::Board<false, true, 8> = setup_board(param);
-            engine::search::find_best_move_debug(&mut board, depth, beam_width)
-        }
-        (false, false) => {
-            let mut board: chessembly::board::Board<false, false, 8> = setup_board(param);
-            engine::search::find_best_move_debug(&mut board, depth, beam_width)
-        }
-    };
+    let debug_info = with_board!(param, |board| {
+        engine::search::find_best_move_debug(&mut board, depth, beam_width)
+    });
 
     (StatusCode::OK, Json(debug_info)).into_response()
 }
@@ -516,27 +555,9 @@ async fn run_engine_debug(headers: HeaderMap) -> impl IntoResponse {
 //       Target (col,row)  — Macho / Imprisoned 옵션
 // 반환: 해당 칸 기물의 합법적인 수 목록 (JSON 배열)
 async fn get_piece_moves(headers: HeaderMap) -> impl IntoResponse {
-    let (
-        Some(position),
-        Some(script),
-        Some(turn),
-        Some(castling_oo),
-        Some(castling_ooo),
-        Some(en_passant_white),
-        Some(en_passant_black),
-        Some(register_white),
-        Some(register_black),
-        Some(target_header),
-    ) = (
-        headers.get("Position"),
+    let (Some(position), Some(script), Some(target_header)) = (
+        headers.get("Position").and_then(|x| x.to_str().ok()),
         headers.get("Chessembly"),
-        headers.get("Turn"),
-        headers.get("Castling-OO"),
-        headers.get("Castling-OOO"),
-        headers.get("En-Passant-White"),
-        headers.get("En-Passant-Black"),
-        headers.get("Register-White"),
-        headers.get("Register-Black"),
         headers.get("Target"),
     ) else {
         return (StatusCode::OK, "asdf").into_response();
@@ -556,143 +577,161 @@ async fn get_piece_moves(headers: HeaderMap) -> impl IntoResponse {
         return (StatusCode::OK, "asdf").into_response();
     };
     let str_script_fixed = str_script.replace('{', " { ").replace('}', " } ");
-    let Ok(compiled) = ChessemblyCompiled::from_script(&str_script_fixed[..]) else {
-        return (StatusCode::OK, "asdf").into_response();
-    };
+    let (white_script, black_script) = parse_side_scripts(&headers);
+    let mut compiled = ChessemblyCompiled::from_side_scripts(&str_script_fixed[..], &white_script, &black_script);
 
-    let (
-        Ok(castling_oo_tuple),
-        Ok(castling_ooo_tuple),
-        Ok(en_passant_white_str),
-        Ok(en_passant_black_str),
-        Ok(register_white_str),
-        Ok(register_black_str),
-    ) = (
-        castling_oo.to_str().map(|x| (x.chars().nth(0) == Some('1'), x.chars().nth(1) == Some('1'))),
-        castling_ooo.to_str().map(|x| (x.chars().nth(0) == Some('1'), x.chars().nth(1) == Some('1'))),
-        en_passant_white.to_str(),
-        en_passant_black.to_str(),
-        register_white.to_str(),
-        register_black.to_str(),
-    ) else {
-        return (StatusCode::OK, "asdf").into_response();
-    };
+    let rules = parse_rules(&headers);
 
-    let mut en_passant_white_positions: Vec<chessembly::Position> = Vec::new();
-    let mut en_passant_black_positions: Vec<chessembly::Position> = Vec::new();
-    let mut register_white_map: HashMap<&str, u8> = HashMap::new();
-    let mut register_black_map: HashMap<&str, u8> = HashMap::new();
-    for coord in en_passant_white_str.split('/') {
-        if let Some((x, y)) = coord.split_once(',') {
-            en_passant_white_positions.push((x.parse().unwrap_or(0), y.parse().unwrap_or(0)));
+    compiled.intern_placement(resolve_position(position));
+    let param = board_params(&headers, &compiled, &rules);
+
+    let moves = with_board!(param, |b| {
+        // 배치 단계: 대상 칸에 놓을 수 있는 보관함 기물마다 한 수.
+        if b.is_placing() {
+            return (StatusCode::OK, Json(MoveGen::placement_moves(&b, b.turn, Some((target_col, target_row))))).into_response();
         }
-    }
-    for coord in en_passant_black_str.split('/') {
-        if let Some((x, y)) = coord.split_once(',') {
-            en_passant_black_positions.push((x.parse().unwrap_or(0), y.parse().unwrap_or(0)));
+        let script = b.script;
+        // 전장의 안개: 차례인 쪽의 기물이 아니면 수를 알려주지 않습니다.
+        if b.rules.fog && b.color_on(&(target_col, target_row)) != Some(b.turn) {
+            return (StatusCode::OK, Json(Vec::<chessembly::ChessMove>::new())).into_response();
         }
-    }
-    for register in register_white_str.split('/') {
-        if let Some((key, value)) = register.split_once(',') {
-            register_white_map.insert(key, value.parse().unwrap_or(0));
+        let raw = script.get_moves(&mut b, &(target_col, target_row), true);
+        let mut moves = script.filter_nodes(raw, &mut b);
+        // 강제 캡처: 차례인 쪽의 기물이면 다른 기물의 캡처 때문에 막힌 수를 뺍니다.
+        if b.rules.compulsory_capture && b.color_on(&(target_col, target_row)) == Some(b.turn) {
+            let legal = MoveGen::new_legal(&mut b);
+            moves.retain(|m| legal.contains(m));
         }
-    }
-    for register in register_black_str.split('/') {
-        if let Some((key, value)) = register.split_once(',') {
-            register_black_map.insert(key, value.parse().unwrap_or(0));
-        }
-    }
+        moves
+    });
+
+    (StatusCode::OK, Json(moves)).into_response()
+}
+
+// ─── POST /visibility ─────────────────────────────────────────────────────────
+// 헤더: Position, Chessembly, Turn, Viewer (white|black, 기본은 Turn)
+//       + 선택: Castling-OO, Castling-OOO, En-Passant-*, Register-*, Win-Conditions 등
+// 반환: { visible: [[x, y], ...], position: 보는 쪽 기준으로 가린 배치 }
 
-    let board_state = BothBoardState {
+#[derive(serde::Serialize)]
+struct VisibilityResponse {
+    visible: Vec<chessembly::Position>,
+    position: String,
+}
+
+/// 보드 상태 헤더를 읽습니다. 없는 헤더는 캐슬링 불가, 앙파상·레지스터 없음으로 봅니다.
+fn parse_board_state(headers: &HeaderMap) -> BothBoardState<'_> {
+    let read = |name: &str| headers.get(name).and_then(|x| x.to_str().ok()).unwrap_or("");
+    let flags = |name: &str| {
+        let value = read(name);
+        (value.starts_with('1'), value.chars().nth(1) == Some('1'))
+    };
+    let positions = |name: &str| {
+        read(name)
+            .split('/')
+            .filter_map(|coord| coord.split_once(','))
+            .map(|(x, y)| (x.parse().unwrap_or(0), y.parse().unwrap_or(0)))
+            .collect::<Vec<chessembly::Position>>()
+    };
+    let registers = |name: &'static str| {
+        headers.get(name).and_then(|x| x.to_str().ok()).unwrap_or("")
+            .split('/')
+            .filter_map(|register| register.split_once(','))
+            .map(|(key, value)| (key, value.parse().unwrap_or(0)))
+            .collect::<HashMap<&str, u8>>()
+    };
+    let (castling_oo, castling_ooo, checks) = (flags("Castling-OO"), flags("Castling-OOO"), parse_checks(headers));
+    BothBoardState {
         white: BoardState {
-            castling_oo: castling_oo_tuple.0,
-            castling_ooo: castling_ooo_tuple.0,
-            enpassant: en_passant_white_positions,
-            register: register_white_map,
+            castling_oo: castling_oo.0,
+            castling_ooo: castling_ooo.0,
+            enpassant: positions("En-Passant-White"),
+            register: registers("Register-White"),
+            checks: checks.0,
+            reserve: parse_reserve(headers, "Reserve-White"),
         },
         black: BoardState {
-            castling_oo: castling_oo_tuple.1,
-            castling_ooo: castling_ooo_tuple.1,
-            enpassant: en_passant_black_positions,
-            register: register_black_map,
+            castling_oo: castling_oo.1,
+            castling_ooo: castling_ooo.1,
+            enpassant: positions("En-Passant-Black"),
+            register: registers("Register-Black"),
+            checks: checks.1,
+            reserve: parse_reserve(headers, "Reserve-Black"),
         },
-    };
+    }
+}
 
-    let turn = if turn.to_str().unwrap_or("white") == "white" {
-        chessembly::Color::White
-    } else {
-        chessembly::Color::Black
+async fn get_visibility(headers: HeaderMap) -> impl IntoResponse {
+    let (Some(position), Some(script)) = (
+        headers.get("Position").and_then(|x| x.to_str().ok()),
+        headers.get("Chessembly"),
+    ) else {
+        return (StatusCode::BAD_REQUEST, "missing headers").into_response();
     };
-
-    let is_macho = headers.get("Macho").is_some();
-    let is_imprisoned = headers.get("Imprisoned").is_some();
-
-    let pos_str = position.to_str().unwrap_or("");
-
-    let param = SetupBoardParams {
-        compiled: &compiled,
-        position: pos_str,
-        board_state: board_state,
-        turn: turn
+    let Ok(str_script) = script.to_str().map(|x| urlencoding::decode(x).expect("UTF-8")) else {
+        return (StatusCode::BAD_REQUEST, "bad script").into_response();
     };
+    let str_script_fixed = str_script.replace('{', " { ").replace('}', " } ");
+    let (white_script, black_script) = parse_side_scripts(&headers);
+    let mut compiled = ChessemblyCompiled::from_side_scripts(&str_script_fixed[..], &white_script, &black_script);
+
+    let rules = parse_rules(&headers);
+    let viewer = parse_viewer(&headers, parse_turn(&headers));
+    compiled.intern_placement(resolve_position(position));
+    let param = board_params(&headers, &compiled, &rules);
+
+    let response = with_board!(param, |b| {
+        let visible = b.visible_squares(viewer);
+        VisibilityResponse {
+            visible: visible.iter().collect(),
+            position: encode_position(&b, Some(visible)),
+        }
+    });
+    (StatusCode::OK, Json(response)).into_response()
+}
 
-    let moves = match (is_macho, is_imprisoned) {
-        (true, true) => {
-            let mut b: Board<true, true, 8> = setup_board(param);
-            let script = b.script;
-            let raw = script.get_moves::<true, true, 8>(&mut b, &(target_col, target_row), true);
-            script.filter_nodes::<true, true, 8>(raw, &b)
-        }
-        (true, false) => {
-            let mut b: Board<true, false, 8> = setup_board(param);
-            let script = b.script;
-            let raw = script.get_moves::<true, false, 8>(&mut b, &(target_col, target_row), true);
-            script.filter_nodes::<true, false, 8>(raw, &b)
-        }
-        (false, true) => {
-            let mut b: Board<false, true, 8> = setup_board(param);
-            let script = b.script;
-            let raw = script.get_moves::<false, true, 8>(&mut b, &(target_col, target_row), true);
-            script.filter_nodes::<false, true, 8>(raw, &b)
-        }
-        (false, false) => {
-            let mut b: Board<false, false, 8> = setup_board(param);
-            let script = b.script;
-            let raw = script.get_moves::<false, false, 8>(&mut b, &(target_col, target_row), true);
-            script.filter_nodes::<false, false, 8>(raw, &b)
-        }
+// ─── POST /roll ───────────────────────────────────────────────────────────────
+// 주사위 변형에서 차례 시작의 주사위를 서버에서 굴립니다. 헤더는 /apply와 같습니다.
+// 반환: 굴린 뒤의 보드 상태 (roll = 움직일 기물 종류). 그 종류로 둘 수가 없으면 차례가 넘어가 roll은 null.
+async fn roll_dice_endpoint(headers: HeaderMap) -> impl IntoResponse {
+    let (Some(position), Some(script)) = (
+        headers.get("Position").and_then(|x| x.to_str().ok()),
+        headers.get("Chessembly"),
+    ) else {
+        return (StatusCode::BAD_REQUEST, "missing headers").into_response();
+    };
+    let Ok(str_script) = script.to_str().map(|x| urlencoding::decode(x).expect("UTF-8")) else {
+        return (StatusCode::BAD_REQUEST, "bad script").into_response();
     };
+    let str_script_fixed = str_script.replace('{', " { ").replace('}', " } ");
+    let (white_script, black_script) = parse_side_scripts(&headers);
+    let mut compiled = ChessemblyCompiled::from_side_scripts(&str_script_fixed[..], &white_script, &black_script);
 
-    (StatusCode::OK, Json(moves)).into_response()
+    let rules = parse_rules(&headers);
+    if rules.dice.is_empty() {
+        return (StatusCode::BAD_REQUEST, "no dice").into_response();
+    }
+    compiled.intern_placement(resolve_position(position));
+    let param = SetupBoardParams { roll: None, ..board_params(&headers, &compiled, &rules) };
+
+    let face = rand::Rng::random_range(&mut rand::rng(), 0..rules.dice.len()) as u8;
+    let response = with_board!(param, |b| {
+        b.roll_dice(face);
+        encode_board_response(&b, None)
+    });
+    (StatusCode::OK, Json(response)).into_response()
 }
 
 // ─── POST /apply ──────────────────────────────────────────────────────────────
-// 현재 보드 상태 헤더 + JSON 바디 { from, move_to, transition? }
+// 현재 보드 상태 헤더(+ Phase, Moves-Left, Reserve-*) + JSON 바디 { from, move_to, transition? }
 // → 해당 수를 서버에서 적용하고 새 보드 상태를 JSON으로 반환
 async fn apply_move_endpoint(
     headers: HeaderMap,
     JsonBody(body): JsonBody<ApplyMoveRequest>,
 ) -> impl IntoResponse {
-    let (
-        Some(position),
-        Some(script),
-        Some(turn),
-        Some(castling_oo),
-        Some(castling_ooo),
-        Some(en_passant_white),
-        Some(en_passant_black),
-        Some(register_white),
-        Some(register_black),
-    ) = (
-        headers.get("Position"),
+    let (Some(position), Some(script)) = (
+        headers.get("Position").and_then(|x| x.to_str().ok()),
         headers.get("Chessembly"),
-        headers.get("Turn"),
-        headers.get("Castling-OO"),
-        headers.get("Castling-OOO"),
-        headers.get("En-Passant-White"),
-        headers.get("En-Passant-Black"),
-        headers.get("Register-White"),
-        headers.get("Register-Black"),
     ) else {
         return (StatusCode::BAD_REQUEST, "missing headers").into_response();
     };
@@ -701,127 +740,145 @@ async fn apply_move_endpoint(
         return (StatusCode::BAD_REQUEST, "bad script").into_response();
     };
     let str_script_fixed = str_script.replace('{', " { ").replace('}', " } ");
-    let Ok(compiled) = ChessemblyCompiled::from_script(&str_script_fixed[..]) else {
-        return (StatusCode::BAD_REQUEST, "script compile failed").into_response();
-    };
+    let (white_script, black_script) = parse_side_scripts(&headers);
+    let mut compiled = ChessemblyCompiled::from_side_scripts(&str_script_fixed[..], &white_script, &black_script);
 
-    let (
-        Ok(castling_oo_tuple),
-        Ok(castling_ooo_tuple),
-        Ok(en_passant_white_str),
-        Ok(en_passant_black_str),
-        Ok(register_white_str),
-        Ok(register_black_str),
-    ) = (
-        castling_oo.to_str().map(|x| (x.chars().nth(0) == Some('1'), x.chars().nth(1) == Some('1'))),
-        castling_ooo.to_str().map(|x| (x.chars().nth(0) == Some('1'), x.chars().nth(1) == Some('1'))),
-        en_passant_white.to_str(),
-        en_passant_black.to_str(),
-        register_white.to_str(),
-        register_black.to_str(),
-    ) else {
-        return (StatusCode::BAD_REQUEST, "bad headers").into_response();
-    };
+    let rules = parse_rules(&headers);
 
-    let mut en_passant_white_positions: Vec<chessembly::Position> = Vec::new();
-    let mut en_passant_black_positions: Vec<chessembly::Position> = Vec::new();
-    let mut register_white_map: HashMap<&str, u8> = HashMap::new();
-    let mut register_black_map: HashMap<&str, u8> = HashMap::new();
-    for coord in en_passant_white_str.split('/') {
-        if let Some((x, y)) = coord.split_once(',') {
-            en_passant_white_positions.push((x.parse().unwrap_or(0), y.parse().unwrap_or(0)));
-        }
-    }
-    for coord in en_passant_black_str.split('/') {
-        if let Some((x, y)) = coord.split_once(',') {
-            en_passant_black_positions.push((x.parse().unwrap_or(0), y.parse().unwrap_or(0)));
-        }
-    }
-    for register in register_white_str.split('/') {
-        if let Some((key, value)) = register.split_once(',') {
-            register_white_map.insert(key, value.parse().unwrap_or(0));
-        }
+    compiled.intern_placement(resolve_position(position));
+    let param = board_params(&headers, &compiled, &rules);
+
+    // 합법적인 수 목록에서 요청된 수를 찾아 적용
+    // 여러 단계로 된 차례에서는 단계마다 따로 요청하며, 현재 단계에 맞는 기물만 움직일 수 있습니다.
+    // 배치 단계에서는 transition에 놓을 기물을, move_to에 놓을 칸을 적습니다 (from은 무시).
+    let result: Option<BoardStateResponse> = with_board!(param, |b| {
+        // 주사위 변형에서는 /roll로 먼저 굴려야 둘 수 있습니다.
+        if b.awaits_roll() {
+            return (StatusCode::BAD_REQUEST, "roll required").into_response();
+        }
+        let filtered = if b.is_placing() {
+            MoveGen::placement_moves(&b, b.turn, Some(body.move_to))
+        } else {
+            if !b.is_movable_by(&body.from, b.turn) {
+                return (StatusCode::BAD_REQUEST, "illegal move").into_response();
+            }
+            let script = b.script;
+            let raw = script.get_moves(&mut b, &body.from, true);
+            let mut filtered = script.filter_nodes(raw, &mut b);
+            if b.rules.compulsory_capture {
+                let legal = MoveGen::new_legal(&mut b);
+                filtered.retain(|m| legal.contains(m));
+            }
+            filtered
+        };
+        filtered.into_iter()
+            .find(|m| m.get_dest() == body.move_to && m.get_promotion().as_deref() == body.transition.as_deref())
+            .map(|m| {
+                // 전장의 안개에서는 수를 둔 쪽이 보는 국면만 돌려줍니다.
+                let mover = b.turn;
+                let mut next = b.make_move_new(&m);
+                let fog = next.rules.fog.then(|| (mover, next.visible_squares(mover)));
+                encode_board_response(&next, fog)
+            })
+    });
+
+    match result {
+        Some(resp) => (StatusCode::OK, Json(resp)).into_response(),
+        None => (StatusCode::BAD_REQUEST, "illegal move").into_response(),
     }
-    for register in register_black_str.split('/') {
-        if let Some((key, value)) = register.split_once(',') {
-            register_black_map.insert(key, value.parse().unwrap_or(0));
-        }
+}
+
+// ─── 4인 체스 ─────────────────────────────────────────────────────────────────
+// 헤더: Position (`four-player`면 시작 배치), Chessembly, Turn (red|blue|yellow|green),
+//       Mode (teams|ffa, 기본 ffa), Eliminated (탈락한 플레이어, ',' 구분), Depth (엔진만)
+
+#[derive(serde::Serialize)]
+struct FourPlayerStateResponse {
+    position: String,
+    turn: String,
+    eliminated: Vec<String>,
+    in_check: Vec<String>,
+    status: String,
+}
+
+fn setup_four_player<'a>(headers: &'a HeaderMap, compiled: &'a ChessemblyCompiled<'a>) -> FourPlayerBoard<'a> {
+    let read = |name: &str| headers.get(name).and_then(|x| x.to_str().ok());
+    let mode = match read("Mode") {
+        Some("teams") => FourPlayerMode::Teams,
+        _ => FourPlayerMode::FreeForAll,
+    };
+    let mut board = FourPlayerBoard::from_position(compiled, resolve_position(read("Position").unwrap_or("four-player")), mode);
+    board.turn = read("Turn").and_then(Player::parse).unwrap_or(Player::Red);
+    for player in read("Eliminated").unwrap_or("").split(',').filter_map(Player::parse) {
+        board.eliminated[player as usize] = true;
     }
+    board
+}
 
-    let board_state = BothBoardState {
-        white: BoardState {
-            castling_oo: castling_oo_tuple.0,
-            castling_ooo: castling_ooo_tuple.0,
-            enpassant: en_passant_white_positions,
-            register: register_white_map,
+fn encode_four_player_response(board: &FourPlayerBoard) -> FourPlayerStateResponse {
+    let players = |f: &dyn Fn(Player) -> bool| {
+        Player::ALL.into_iter().filter(|p| f(*p)).map(|p| p.as_str().to_string()).collect::<Vec<_>>()
+    };
+    FourPlayerStateResponse {
+        position: board.to_position(),
+        turn: board.turn.as_str().to_string(),
+        eliminated: players(&|p| !board.is_active(p)),
+        in_check: players(&|p| board.is_active(p) && board.is_in_check(p)),
+        status: match board.status {
+            FourPlayerStatus::Ongoing => "Ongoing".to_string(),
+            FourPlayerStatus::Draw => "Draw".to_string(),
+            FourPlayerStatus::PlayerWins(winner) => format!("{}Wins", winner.as_str()),
+            FourPlayerStatus::TeamWins(0) => "RedYellowWins".to_string(),
+            FourPlayerStatus::TeamWins(_) => "BlueGreenWins".to_string(),
         },
-        black: BoardState {
-            castling_oo: castling_oo_tuple.1,
-            castling_ooo: castling_ooo_tuple.1,
-            enpassant: en_passant_black_positions,
-            register: register_black_map,
-        },
-    };
+    }
+}
 
-    let turn = if turn.to_str().unwrap_or("white") == "white" {
-        chessembly::Color::White
-    } else {
-        chessembly::Color::Black
+// POST /four-player → 최선 수와 점수 (paranoid 검색), 둘 수 없으면 null
+async fn run_four_player_engine(headers: HeaderMap) -> impl IntoResponse {
+    let Some(Ok(str_script)) = headers.get("Chessembly").map(|x| x.to_str().map(|x| urlencoding::decode(x).expect("UTF-8"))) else {
+        return (StatusCode::BAD_REQUEST, "bad script").into_response();
+    };
+    let str_script_fixed = str_script.replace('{', " { ").replace('}', " } ");
+    let Ok(compiled) = ChessemblyCompiled::from_script(&str_script_fixed[..]) else {
+        return (StatusCode::BAD_REQUEST, "script compile failed").into_response();
     };
+    let depth = headers.get("Depth").and_then(|x| x.to_str().ok()).and_then(|x| x.parse::<u8>().ok()).unwrap_or(2);
+    if depth == 0 || depth > engine::search::HARD_DEPTH {
+        return (StatusCode::BAD_REQUEST, "depth out of range").into_response();
+    }
 
-    let is_macho = headers.get("Macho").is_some();
-    let is_imprisoned = headers.get("Imprisoned").is_some();
-    let pos_str = position.to_str().unwrap_or("");
+    let mut board = setup_four_player(&headers, &compiled);
+    match engine::search::find_best_move_paranoid(&mut board, depth) {
+        Ok(node) => (StatusCode::OK, Json(node)).into_response(),
+        Err(_) => (StatusCode::OK, "null").into_response(),
+    }
+}
 
-    let param = SetupBoardParams {
-        compiled: &compiled,
-        position: pos_str,
-        board_state: board_state,
-        turn: turn
+// POST /four-player/apply → 바디 { from, move_to, transition? }를 적용한 새 상태
+async fn apply_four_player_move(
+    headers: HeaderMap,
+    JsonBody(body): JsonBody<ApplyMoveRequest>,
+) -> impl IntoResponse {
+    let Some(Ok(str_script)) = headers.get("Chessembly").map(|x| x.to_str().map(|x| urlencoding::decode(x).expect("UTF-8"))) else {
+        return (StatusCode::BAD_REQUEST, "bad script").into_response();
     };
-
-    // 합법적인 수 목록에서 요청된 수를 찾아 적용
-    let result: Option<BoardStateResponse> = match (is_macho, is_imprisoned) {
-        (true, true) => {
-            let mut b: Board<true, true, 8> = setup_board(param);
-            let script = b.script;
-            let raw = script.get_moves::<true, true, 8>(&mut b, &body.from, true);
-            let filtered = script.filter_nodes::<true, true, 8>(raw, &b);
-            filtered.into_iter()
-                .find(|m| m.get_dest() == body.move_to && m.get_promotion().as_deref() == body.transition.as_deref())
-                .map(|m| encode_board_response(&b.make_move_new(&m)))
-        }
-        (true, false) => {
-            let mut b: Board<true, false, 8> = setup_board(param);
-            let script = b.script;
-            let raw = script.get_moves::<true, false, 8>(&mut b, &body.from, true);
-            let filtered = script.filter_nodes::<true, false, 8>(raw, &b);
-            filtered.into_iter()
-                .find(|m| m.get_dest() == body.move_to && m.get_promotion().as_deref() == body.transition.as_deref())
-                .map(|m| encode_board_response(&b.make_move_new(&m)))
-        }
-        (false, true) => {
-            let mut b: Board<false, true, 8> = setup_board(param);
-            let script = b.script;
-            let raw = script.get_moves::<false, true, 8>(&mut b, &body.from, true);
-            let filtered = script.filter_nodes::<false, true, 8>(raw, &b);
-            filtered.into_iter()
-                .find(|m| m.get_dest() == body.move_to && m.get_promotion().as_deref() == body.transition.as_deref())
-                .map(|m| encode_board_response(&b.make_move_new(&m)))
-        }
-        (false, false) => {
-            let mut b: Board<false, false, 8> = setup_board(param);
-            let script = b.script;
-            let raw = script.get_moves::<false, false, 8>(&mut b, &body.from, true);
-            let filtered = script.filter_nodes::<false, false, 8>(raw, &b);
-            filtered.into_iter()
-                .find(|m| m.get_dest() == body.move_to && m.get_promotion().as_deref() == body.transition.as_deref())
-                .map(|m| encode_board_response(&b.make_move_new(&m)))
-        }
+    let str_script_fixed = str_script.replace('{', " { ").replace('}', " } ");
+    let Ok(compiled) = ChessemblyCompiled::from_script(&str_script_fixed[..]) else {
+        return (StatusCode::BAD_REQUEST, "script compile failed").into_response();
     };
 
-    match result {
-        Some(resp) => (StatusCode::OK, Json(resp)).into_response(),
+    let board = setup_four_player(&headers, &compiled);
+    if board.status != FourPlayerStatus::Ongoing || !board.is_active(board.turn) {
+        return (StatusCode::BAD_REQUEST, "illegal move").into_response();
+    }
+    let found = board.legal_moves(board.turn).into_iter().find(|m| {
+        m.get_source() == body.from
+            && m.get_dest() == body.move_to
+            && m.get_promotion().as_deref() == body.transition.as_deref()
+    });
+    match found {
+        Some(m) => (StatusCode::OK, Json(encode_four_player_response(&board.make_move(&m)))).into_response(),
         None => (StatusCode::BAD_REQUEST, "illegal move").into_response(),
     }
 }
@@ -839,6 +896,11 @@ async fn apply_move_endpoint(
 struct ClassifyRequest {
     piece_name: String,
     script: String,
+    /// 진영별 스크립트 (선택). `color` 진영의 기물로 분류합니다.
+    white_script: Option<String>,
+    black_script: Option<String>,
+    /// white|black, 기본 white.
+    color: Option<String>,
 }
 
 #[derive(serde::Serialize)]
@@ -855,9 +917,13 @@ async fn classify_piece(JsonBody(body): JsonBody<ClassifyRequest>) -> impl IntoR
         body.piece_name,
         body.script.replace('{', " { ").replace('}', " } ")
     );
-    let Ok(compiled) = ChessemblyCompiled::from_script(&combined) else {
-        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error": "script parse failed"}))).into_response();
+    let side_script = |x: &Option<String>| x.as_deref().unwrap_or("").replace('{', " { ").replace('}', " } ");
+    let (white_script, black_script) = (side_script(&body.white_script), side_script(&body.black_script));
+    let color = match body.color.as_deref() {
+        Some("black") => chessembly::Color::Black,
+        _ => chessembly::Color::White,
     };
+    let compiled = ChessemblyCompiled::from_side_scripts(&combined, &white_script, &black_script);
     // combined[1..] 은 '#' 이후의 piece_name 부분을 포함하므로 lifetime이 동일
     let piece_name: &str = &combined[1..1 + body.piece_name.len()];
 
@@ -869,16 +935,16 @@ async fn classify_piece(JsonBody(body): JsonBody<ClassifyRequest>) -> impl IntoR
             // 백 킹은 (7,7) 고정 (기물과 겹치면 (7,6) 사용)
             let (wkc, wkr): (u8, u8) = if (pc, pr) != (7, 7) { (7, 7) } else { (7, 6) };
 
-            let mut board = Board::<false, false, 8>::empty(&compiled);
+            let mut board = Board::<8>::empty(&compiled);
             board.board_state.white.castling_oo = false;
             board.board_state.white.castling_ooo = false;
             board.board_state.black.castling_oo = false;
             board.board_state.black.castling_ooo = false;
-            board.board[pr as usize][pc as usize] = PieceSpan::Piece(Piece { piece_type: piece_name, color: chessembly::Color::White });
-            board.board[wkr as usize][wkc as usize] = PieceSpan::Piece(Piece { piece_type: "king", color: chessembly::Color::White });
+            board.set_square(&(pc, pr), PieceSpan::Piece(Piece::new(piece_name, color, &compiled.pieces)));
+            board.set_square(&(wkc, wkr), PieceSpan::Piece(Piece::new("king", color, &compiled.pieces)));
 
             let script = board.script;
-            let moves = script.get_moves::<false, false, 8>(&mut board, &(pc, pr), true);
+            let moves = script.get_moves::<8>(&mut board, &(pc, pr), true);
             let dests: Vec<(u8, u8)> = moves.iter().map(|m| m.get_dest()).collect();
 
             // 이동 가능 칸들이 2×2 블록을 커버하는지 확인
@@ -906,16 +972,16 @@ async fn classify_piece(JsonBody(body): JsonBody<ClassifyRequest>) -> impl IntoR
         for pr in 0u8..8 {
             let (wkc, wkr): (u8, u8) = if (pc, pr) != (7, 7) { (7, 7) } else { (7, 6) };
 
-            let mut board = Board::<false, false, 8>::empty(&compiled);
+            let mut board = Board::<8>::empty(&compiled);
             board.board_state.white.castling_oo = false;
             board.board_state.white.castling_ooo = false;
             board.board_state.black.castling_oo = false;
             board.board_state.black.castling_ooo = false;
-            board.board[pr as usize][pc as usize] = PieceSpan::Piece(Piece { piece_type: piece_name, color: chessembly::Color::White });
-            board.board[wkr as usize][wkc as usize] = PieceSpan::Piece(Piece { piece_type: "king", color: chessembly::Color::White });
+            board.set_square(&(pc, pr), PieceSpan::Piece(Piece::new(piece_name, color, &compiled.pieces)));
+            board.set_square(&(wkc, wkr), PieceSpan::Piece(Piece::new("king", color, &compiled.pieces)));
 
             let script = board.script;
-            let moves = script.get_moves::<false, false, 8>(&mut board, &(pc, pr), true);
+            let moves = script.get_moves::<8>(&mut board, &(pc, pr), true);
             let dests: Vec<(u8, u8)> = moves.iter().map(|m| m.get_dest()).collect();
 
             // 같은 행에서 인접한 두 칸에 이동 가능하면 행 방향 슬라이딩